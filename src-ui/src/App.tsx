@@ -1,7 +1,7 @@
 import { useCallback, useState } from 'react'
 import './App.css'
 import { useWebViewEvent } from './hooks/useGlobalRustEventListener'
-import { IPC, type WebViewState, type WebViewUpdateCommand } from './ipc/common';
+import { IPC, type ProgressEvent, type WebViewState, type WebViewUpdateCommand } from './ipc/common';
 import LoadingDisplay from './LoadingDisplay';
 import ErrorDisplay from './ErrorDisplay';
 import SuccessDisplay from './SuccessDisplay';
@@ -12,11 +12,17 @@ type DisplayType = WebViewUpdateCommand
 
 function App() {
   const [webViewState, setWebViewState] = useState<WebViewState>("Loading");
-  const [display, setDisplay] = useState<DisplayType>({ title_text: "Loading", sub_text: "", conflict_info: undefined, is_after_game: false });
+  const [display, setDisplay] = useState<DisplayType>({ title_text: "Loading", sub_text: "", conflict_info: undefined, is_after_game: false, is_offline: false });
+  const [progress, setProgress] = useState<ProgressEvent | undefined>(undefined);
 
   useWebViewEvent("WebViewStateChange", useCallback((ev) => {
     setWebViewState(ev.state);
-  }, [setWebViewState]));
+    setProgress(undefined); // progress only belongs to the loading step it was reported in
+  }, [setWebViewState, setProgress]));
+
+  useWebViewEvent("WebViewProgress", useCallback((ev) => {
+    setProgress(ev.progress);
+  }, [setProgress]));
 
   useWebViewEvent("WebViewUpdate", useCallback((ev) => {
     setDisplay(ev);
@@ -42,9 +48,13 @@ function App() {
     IPC.sendUserChoice("pull");
   }, [])
 
+  const sendCancel = useCallback(() => {
+    IPC.sendUserChoice("cancel");
+  }, [])
+
   switch (webViewState) {
     case "Loading":
-      return <LoadingDisplay {...{display}}/>
+      return <LoadingDisplay {...{display, progress}} onCancel={sendCancel} />
     case "Error":
       return <ErrorDisplay
         error={display}
@@ -18,7 +18,7 @@ type ConflictDisplayProps = {
 const ConflictDisplay = ({
   title,
   is_after_game,
-  conflict_info: { local_modified_time, remote_author, remote_uploaded_time },
+  conflict_info: { local_modified_time, remote_author, remote_uploaded_time, local_unpushed_since },
   onChooseLocal,
   onChooseRemote,
 }: ConflictDisplayProps) => {
@@ -28,9 +28,11 @@ const ConflictDisplay = ({
         key: 'remote' as const,
         perform: onChooseRemote,
         confirmTitle: 'Keep Remote Save?',
-        confirmDescription: 'This will overwrite the local save data with the remote version stored on the server.',
+        confirmDescription: local_unpushed_since
+          ? `This device has progress since ${local_unpushed_since} that was never uploaded. It will be overwritten with the remote version stored on the server.`
+          : 'This will overwrite the local save data with the remote version stored on the server.',
         confirmLabel: 'Keep Remote Save',
-        confirmClassName: 'neutral',
+        confirmClassName: local_unpushed_since ? 'danger' : 'neutral',
       },
       {
         key: 'local' as const,
@@ -41,7 +43,7 @@ const ConflictDisplay = ({
         confirmClassName: 'neutral',
       },
     ],
-    [onChooseLocal, onChooseRemote]
+    [onChooseLocal, onChooseRemote, local_unpushed_since]
   )
 
   const [pendingSelection, setPendingSelection] = useState<SelectionKey | null>(null)
@@ -130,6 +132,11 @@ const ConflictDisplay = ({
             Local changes conflict with save data on the remote. Unable to automatically determine which save version to keep.
           </p>
           <p className="conflict-subtext">Choose a version to keep.</p>
+          {local_unpushed_since && (
+            <p className="conflict-subtext conflict-unpushed">
+              This device has un-uploaded progress since {local_unpushed_since} (played offline or upload failed).
+            </p>
+          )}
         </div>
 
         <div className="conflict-options">
@@ -5,14 +5,14 @@ import { ConfirmModal } from './ConfirmModal';
 import { useWebViewEvent } from './hooks/useGlobalRustEventListener';
 
 type ErrorDisplayProps = {
-  error: { title_text: string; sub_text: string, is_after_game: boolean}
+  error: { title_text: string; sub_text: string, is_after_game: boolean, is_offline: boolean }
   onContinueOffline?: () => void
   onClose?: () => void
   onRetry?: () => void
 }
 
 const ErrorDisplay = ({
-  error: { title_text, sub_text, is_after_game },
+  error: { title_text, sub_text, is_after_game, is_offline },
   onContinueOffline,
   onClose,
   onRetry,
@@ -25,13 +25,20 @@ const ErrorDisplay = ({
         { label: 'Exit without Saving', className: 'danger', action: () => setShowConfirm(true) },
         { label: 'Retry', className: 'neutral', action: onRetry },
       ]
+      : is_offline ?
+      // Remote can't be reached - playing offline is the expected choice, so skip the confirmation.
+      [
+        { label: 'Continue Offline', className: 'neutral', action: onContinueOffline },
+        { label: 'Close', className: 'secondary', action: onClose },
+        { label: 'Retry', className: 'secondary', action: onRetry },
+      ]
       :
       [
         { label: 'Continue Offline', className: 'danger', action: () => setShowConfirm(true) },
         { label: 'Close', className: 'secondary', action: onClose },
         { label: 'Retry', className: 'neutral', action: onRetry },
       ]
-    , [onClose, onRetry, setShowConfirm, is_after_game])
+    , [onClose, onRetry, onContinueOffline, setShowConfirm, is_after_game, is_offline])
 
   const onConfirm = useCallback((idx: number) => {
     const entry = baseButtons[idx]
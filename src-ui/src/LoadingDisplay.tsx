@@ -1,26 +1,103 @@
-import { useEffect, useState } from "react";
+import { useCallback, useEffect, useState } from "react";
 import './LoadingDisplay.css'
+import './ErrorDisplay.css' // shared button styles
+import type { ProgressEvent } from "./ipc/common";
+import { useMultiInputNavigation } from "./hooks/useMultiInputNavigation";
 
 type LoadingDisplayProps = {
-    display: { title_text: string, sub_text: string}
+    display: { title_text: string, sub_text: string},
+    progress?: ProgressEvent,
+    onCancel?: () => void,
 }
 
-const LoadingDisplay = ({ display: {title_text, sub_text} }: LoadingDisplayProps) => {
+const BYTE_UNITS = ["B", "KB", "MB", "GB", "TB"];
+
+function formatBytes(bytes: number): string {
+  let value = bytes;
+  let unit = 0;
+  while (value >= 1024 && unit < BYTE_UNITS.length - 1) {
+    value /= 1024;
+    unit += 1;
+  }
+  return unit === 0 ? `${bytes} B` : `${value.toFixed(1)} ${BYTE_UNITS[unit]}`;
+}
+
+function formatEta(etaSecs: number): string {
+  if (etaSecs < 60) return `${etaSecs}s`;
+  if (etaSecs < 3600) return `${Math.floor(etaSecs / 60)}m ${etaSecs % 60}s`;
+  return `${Math.floor(etaSecs / 3600)}h ${Math.floor((etaSecs % 3600) / 60)}m`;
+}
+
+// Completed fraction - by bytes if known, otherwise by files. Mirrors ProgressEvent::fraction in rust.
+function progressFraction(progress: ProgressEvent): number | undefined {
+  if (progress.bytes_total) return Math.min(progress.bytes_done / progress.bytes_total, 1);
+  if (progress.files_total) return Math.min(progress.files_done / progress.files_total, 1);
+  return undefined;
+}
+
+const ProgressBar = ({ progress, fraction }: { progress: ProgressEvent, fraction: number }) => {
+  const details = [
+    progress.files_total !== null ? `${progress.files_done}/${progress.files_total} files` : undefined,
+    progress.bytes_total !== null
+      ? `${formatBytes(progress.bytes_done)} / ${formatBytes(progress.bytes_total)}`
+      : formatBytes(progress.bytes_done),
+    progress.eta_secs !== null ? `ETA ${formatEta(progress.eta_secs)}` : undefined,
+  ].filter(Boolean).join(" · ");
+
+  return (
+    <div className="progress-wrapper">
+      <div className="progress-track">
+        <div className="progress-fill" style={{ width: `${(fraction * 100).toFixed(1)}%` }} />
+      </div>
+      <div className="progress-details">
+        <span>{Math.round(fraction * 100)}%</span>
+        <span>{details}</span>
+      </div>
+      {progress.current_file && <div className="progress-file">{progress.current_file}</div>}
+    </div>
+  )
+}
+
+const LoadingDisplay = ({ display: {title_text, sub_text}, progress, onCancel }: LoadingDisplayProps) => {
   const [fadeKey, setFadeKey] = useState(0); // triggers animation on text change
+  const [cancelling, setCancelling] = useState(false);
+
+  const cancel = useCallback(() => {
+    if (cancelling) return;
+    setCancelling(true); // stays until state changes - rust rolls back before reporting the error
+    onCancel?.();
+  }, [cancelling, setCancelling, onCancel])
+
+  const buttonIndex = useMultiInputNavigation(onCancel ? 1 : 0, cancel, cancel, !cancelling);
 
   useEffect(() => {
     setFadeKey(prev => prev + 1);
   }, [sub_text])
 
+  const fraction = progress ? progressFraction(progress) : undefined;
+
   return (
     <div className="container">
       <div className="loading-wrapper">
-          <div className="spinner"></div>
+        {progress && fraction !== undefined
+          ? <ProgressBar progress={progress} fraction={fraction} />
+          : <div className="spinner"></div>}
         <h1>{title_text}</h1>
         <p key={fadeKey} className="fade-text">{sub_text}</p>
+        {onCancel && (
+          <button
+            className={`btn secondary loading-cancel ${buttonIndex === 0 ? 'focused' : ''}`}
+            onClick={cancel}
+            disabled={cancelling}
+            tabIndex={-1}
+            type="button"
+          >
+            {cancelling ? "Cancelling..." : "Cancel"}
+          </button>
+        )}
       </div>
     </div>
   )
 }
 
-export default LoadingDisplay;
\ No newline at end of file
+export default LoadingDisplay;
@@ -2,7 +2,7 @@
 const _IPCRequests = ["webview-ready", "user-choice"] as const;
 type IPCRequest = typeof _IPCRequests[number];
 
-type UserChoiceType = "pull" | "push" | "close" | "retry" | "continue-offline";;
+type UserChoiceType = "pull" | "push" | "close" | "retry" | "continue-offline" | "cancel";
 
 type WebViewRequest = {
   event_type: IPCRequest,
@@ -28,6 +28,7 @@ export type ConflictDisplayInfo = {
     remote_uploaded_time: string,
     local_author: string,
     remote_author: string,
+    local_unpushed_since: string | null,
 }
 
 /// Types from Rust
@@ -36,6 +37,7 @@ export type WebViewUpdateCommand = {
   sub_text: string,
   conflict_info?: ConflictDisplayInfo,
   is_after_game: boolean,
+  is_offline: boolean,
 }
 
 export type WebViewState = "Loading" | "Conflict" | "Error" | "Success" | "RemoteEmpty"
@@ -46,7 +48,22 @@ export type WebViewStateChangeEvent = {
 
 export type WebViewNotifyCloseEvent = { empty_payload: number };
 
-export type WebViewCommand = { WebViewUpdate: WebViewUpdateCommand } | { WebViewStateChange: WebViewStateChangeEvent } | { WebViewNotifyClose: WebViewNotifyCloseEvent };
+export type SyncPhase = "Locking" | "Comparing" | "Snapshotting" | "Uploading" | "Downloading" | "Merging" | "Finalizing" | "Done"
+
+export type ProgressEvent = {
+  phase: SyncPhase,
+  message: string,
+  files_done: number,
+  files_total: number | null,
+  bytes_done: number,
+  bytes_total: number | null,
+  current_file: string | null,
+  eta_secs: number | null,
+}
+
+export type WebViewProgressEvent = { progress: ProgressEvent };
+
+export type WebViewCommand = { WebViewUpdate: WebViewUpdateCommand } | { WebViewStateChange: WebViewStateChangeEvent } | { WebViewNotifyClose: WebViewNotifyCloseEvent } | { WebViewProgress: WebViewProgressEvent };
 
 export type WebViewCommandMap = {
   [E in WebViewCommand as keyof E]: E[keyof E];
@@ -0,0 +1,36 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+pub const CANCELLED_ERROR: &str = "Sync cancelled by user";
+
+/// Shared flag to stop a running sync. Cloned into whoever can trigger it (UI thread, Ctrl+C handler).
+/// Commands check it between steps - remote clients kill in-flight transfers when it triggers.
+#[derive(Clone, Default, Debug)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+    pub fn new() -> CancellationToken {
+        CancellationToken::default()
+    }
+
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::SeqCst);
+    }
+
+    /// Re-arms the token - e.g. before retrying a cancelled sync.
+    pub fn reset(&self) {
+        self.0.store(false, Ordering::SeqCst);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::SeqCst)
+    }
+
+    /// Errors with `CANCELLED_ERROR` once cancelled - meant to be `?`-ed between steps.
+    pub fn check(&self) -> Result<(), String> {
+        if self.is_cancelled() {
+            return Err(String::from(CANCELLED_ERROR));
+        }
+        Ok(())
+    }
+}
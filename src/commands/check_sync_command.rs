@@ -1,6 +1,8 @@
-use crate::common::Revision;
-use crate::config::RuntimeSyncConfig;
+use crate::common::{Revision, is_ancestor};
+use crate::config::{ConflictStrategy, RuntimeSyncConfig};
+use crate::game_process::{find_running_game_processes, format_running_processes};
 use crate::local_head;
+use crate::manifest::{Manifest, MergePlan, plan_file_merge};
 use crate::remote_save_client::{RemoteSaveClient, get_default_remote_save_client};
 
 #[derive(Debug, Clone)]
@@ -8,14 +10,31 @@ pub enum CheckSyncResult {
     FastForwardRemote,
     FastForwardLocal,
     UpToDate,
-    Conflict { local: Revision, remote: Revision },
+    Conflict {
+        local: Revision,
+        remote: Revision,
+    },
+    /// Both sides changed - but different files. Can be merged without user input.
+    AutoMerge {
+        plan: MergePlan,
+    },
     RemoteEmpty,
 }
 
+/// Manifests required to attempt a file-merge. Only provided if the entry uses the file-merge conflict strategy.
+struct FileMergeInput<'a> {
+    base_manifest: &'a Option<Manifest>,
+    current_manifest: &'a Manifest,
+    remote_manifest: &'a Option<Manifest>,
+}
+
 struct SyncStatusCheckInput<'a> {
     local_head: &'a Option<Revision>,
     current_head: &'a Revision,
     remote_head: &'a Option<Revision>,
+    /// Remote revision log - oldest first. Empty if remote has no history tracked.
+    remote_history: &'a [Revision],
+    file_merge: Option<FileMergeInput<'a>>,
 }
 
 struct SyncCheckContext {
@@ -27,15 +46,50 @@ struct SyncCheckContext {
 
 fn compute_sync_context(sync_config: &RuntimeSyncConfig) -> Result<SyncCheckContext, String> {
     let client = get_default_remote_save_client(sync_config);
-    let local_head = local_head::read_local_head(&sync_config)?;
-    let current_head =
-        local_head::generate_current_head(&sync_config.local_save_folder, &sync_config)?;
     let remote_head = client.get_remote_head()?;
+    // Hash local files like the remote HEAD - so they can be compared even in mixed-algorithm setups.
+    let algorithm = remote_head
+        .as_ref()
+        .map(|head| head.hash_algorithm)
+        .unwrap_or(sync_config.hash_algorithm);
+    let (current_head, current_manifest) = local_head::generate_current_head_with_manifest(
+        &sync_config.local_save_folder,
+        &sync_config,
+        algorithm,
+    )?;
+    let local_head = match local_head::read_local_head(&sync_config)? {
+        // Local HEAD from another algorithm - if files are unchanged since, it is equivalent to the current head.
+        Some(head)
+            if head.hash_algorithm != algorithm
+                && local_head::matches_current_files(&head, &current_head, sync_config)? =>
+        {
+            Some(current_head.clone())
+        }
+        head => head,
+    };
+    let remote_history = client.get_remote_history()?;
+
+    let (base_manifest, remote_manifest) = match sync_config.conflict_strategy {
+        ConflictStrategy::FileMerge => (
+            local_head::read_local_manifest(sync_config)?,
+            client.get_remote_manifest()?,
+        ),
+        ConflictStrategy::Manual => (None, None),
+    };
 
     let check_res = determine_sync_status(&SyncStatusCheckInput {
         local_head: &local_head,
         current_head: &current_head,
         remote_head: &remote_head,
+        remote_history: &remote_history,
+        file_merge: match sync_config.conflict_strategy {
+            ConflictStrategy::FileMerge => Some(FileMergeInput {
+                base_manifest: &base_manifest,
+                current_manifest: &current_manifest,
+                remote_manifest: &remote_manifest,
+            }),
+            ConflictStrategy::Manual => None,
+        },
     });
 
     Ok(SyncCheckContext {
@@ -88,6 +142,12 @@ fn log_sync_result(context: &SyncCheckContext, sync_config: &RuntimeSyncConfig)
                 local, remote
             )
         }
+        CheckSyncResult::AutoMerge { plan } => println!(
+            "Both remote and local have updates - but on different files. Will merge {} remote change(s) and push.\nLocal: {} Remote: {}",
+            plan.pull_files.len() + plan.delete_files.len(),
+            context.current_head,
+            remote_head_display
+        ),
     }
 }
 
@@ -97,6 +157,15 @@ pub fn check_sync_command(
     let context = compute_sync_context(sync_config)?;
     log_sync_result(&context, sync_config);
 
+    let running = find_running_game_processes(sync_config)?;
+    if !running.is_empty() {
+        println!(
+            "{} is running: {} - pulls are refused and pushes wait until it closes",
+            sync_config.game_display_name,
+            format_running_processes(&running)
+        );
+    }
+
     Ok((context.result.clone(), context.remote_head.clone()))
 }
 
@@ -120,7 +189,7 @@ fn determine_sync_status(input: &SyncStatusCheckInput) -> CheckSyncResult {
     if local_head == input.current_head {
         // no local changes
         if remote_head != local_head {
-            // new remote changes - pull those.
+            // new remote changes - pull those. Doesn't matter how far ahead (or even if diverged) - nothing local to lose.
             return CheckSyncResult::FastForwardLocal;
         }
         return CheckSyncResult::UpToDate; // all 3 are equal - no changes up to date
@@ -130,22 +199,63 @@ fn determine_sync_status(input: &SyncStatusCheckInput) -> CheckSyncResult {
         // No changes on remote since last - time can safely push.
         return CheckSyncResult::FastForwardRemote;
     }
-    // Local changes + Remote changes - user needs to pick
+    if is_ancestor(
+        input.remote_history,
+        &input.current_head.hash,
+        &remote_head.hash,
+    ) {
+        // Local is not really modified - it is an older remote revision (e.g. local HEAD is stale). Just behind.
+        return CheckSyncResult::FastForwardLocal;
+    }
+    // Local changes + Remote changes - see if they can be merged. Otherwise user needs to pick
+    if let Some(file_merge) = &input.file_merge {
+        if let Some(plan) = plan_verified_file_merge(
+            file_merge.base_manifest,
+            local_head,
+            file_merge.current_manifest,
+            file_merge.remote_manifest,
+            remote_head,
+        ) {
+            return CheckSyncResult::AutoMerge { plan };
+        }
+    }
+
     return CheckSyncResult::Conflict {
         local: input.current_head.clone(),
         remote: remote_head.clone(),
     };
 }
 
+/// Plans a file-merge - only if the stored manifests actually describe the local and remote HEADs.
+pub(super) fn plan_verified_file_merge(
+    base_manifest: &Option<Manifest>,
+    local_head: &Revision,
+    current_manifest: &Manifest,
+    remote_manifest: &Option<Manifest>,
+    remote_head: &Revision,
+) -> Option<MergePlan> {
+    let base_manifest = base_manifest
+        .as_ref()
+        .filter(|manifest| manifest.tree_hash() == local_head.hash)?;
+    let remote_manifest = remote_manifest
+        .as_ref()
+        .filter(|manifest| manifest.tree_hash() == remote_head.hash)?;
+
+    plan_file_merge(base_manifest, current_manifest, remote_manifest)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::hashing::HashAlgorithm;
 
     fn head() -> Revision {
         return Revision {
             hash: "37df39a38c2f58ec73c309c67702de4d".to_string(),
             timestamp: 1760783380,
             author: "".to_string(),
+            parent: None,
+            hash_algorithm: HashAlgorithm::Md5,
         };
     }
 
@@ -154,6 +264,8 @@ mod tests {
             hash: "ffa755f72c21bf534f54d3a2c75d4ed7".to_string(),
             timestamp: 1760789280,
             author: "".to_string(),
+            parent: None,
+            hash_algorithm: HashAlgorithm::Md5,
         };
     }
 
@@ -162,6 +274,8 @@ mod tests {
             hash: "19db3f74548df29b73598c030066b09d".to_string(),
             timestamp: 1760793480,
             author: "".to_string(),
+            parent: None,
+            hash_algorithm: HashAlgorithm::Md5,
         };
     }
 
@@ -171,7 +285,9 @@ mod tests {
             determine_sync_status(&SyncStatusCheckInput {
                 local_head: &None,
                 current_head: &head(),
-                remote_head: &None
+                remote_head: &None,
+                remote_history: &[],
+                file_merge: None,
             }),
             CheckSyncResult::RemoteEmpty // if both local and remote repos are missing - should upload
         ));
@@ -183,7 +299,9 @@ mod tests {
             determine_sync_status(&SyncStatusCheckInput {
                 local_head: &Some(head()),
                 current_head: &changed_head(), // local and remote up to date - but local changes
-                remote_head: &Some(head())
+                remote_head: &Some(head()),
+                remote_history: &[],
+                file_merge: None,
             }),
             CheckSyncResult::FastForwardRemote // should update remote
         ));
@@ -195,7 +313,9 @@ mod tests {
             determine_sync_status(&SyncStatusCheckInput {
                 local_head: &Some(head()),
                 current_head: &head(), // No local changes up to date - but remote has changes
-                remote_head: &Some(changed_head())
+                remote_head: &Some(changed_head()),
+                remote_history: &[],
+                file_merge: None,
             }),
             CheckSyncResult::FastForwardLocal // should pull
         ));
@@ -207,7 +327,9 @@ mod tests {
             determine_sync_status(&SyncStatusCheckInput {
                 local_head: &Some(head()),
                 current_head: &head(), // No changes anywhere
-                remote_head: &Some(head())
+                remote_head: &Some(head()),
+                remote_history: &[],
+                file_merge: None,
             }),
             CheckSyncResult::UpToDate
         ));
@@ -219,7 +341,9 @@ mod tests {
             determine_sync_status(&SyncStatusCheckInput {
                 local_head: &Some(changed_head()),
                 current_head: &head(),
-                remote_head: &Some(changed_head2())
+                remote_head: &Some(changed_head2()),
+                remote_history: &[],
+                file_merge: None,
             }),
             CheckSyncResult::Conflict {
                 local: _,
@@ -227,4 +351,136 @@ mod tests {
             }
         ));
     }
+
+    fn with_parent(rev: Revision, parent: &Revision) -> Revision {
+        Revision {
+            parent: Some(parent.hash.clone()),
+            ..rev
+        }
+    }
+
+    #[test]
+    pub fn several_revisions_behind_with_stale_local_head_fast_forwards() {
+        // Local head says head() - but local already holds changed_head() (e.g. HEAD write failed after a pull)
+        // Remote moved on to changed_head2() after that.
+        let history = vec![
+            head(),
+            with_parent(changed_head(), &head()),
+            with_parent(changed_head2(), &changed_head()),
+        ];
+        assert!(matches!(
+            determine_sync_status(&SyncStatusCheckInput {
+                local_head: &Some(head()),
+                current_head: &changed_head(),
+                remote_head: &Some(changed_head2()),
+                remote_history: &history,
+                file_merge: None,
+            }),
+            CheckSyncResult::FastForwardLocal
+        ));
+    }
+
+    #[test]
+    pub fn diverged_local_changes_with_history_still_conflict() {
+        let history = vec![head(), with_parent(changed_head2(), &head())];
+        assert!(matches!(
+            determine_sync_status(&SyncStatusCheckInput {
+                local_head: &Some(head()),
+                current_head: &changed_head(), // not part of remote history - real local changes
+                remote_head: &Some(changed_head2()),
+                remote_history: &history,
+                file_merge: None,
+            }),
+            CheckSyncResult::Conflict { .. }
+        ));
+    }
+
+    fn manifest(files: &[(&str, &str)]) -> Manifest {
+        Manifest {
+            algorithm: HashAlgorithm::Md5,
+            files: files
+                .iter()
+                .map(|(f, h)| (f.to_string(), h.to_string()))
+                .collect(),
+            sizes: Default::default(),
+        }
+    }
+
+    fn head_of(manifest: &Manifest) -> Revision {
+        Revision {
+            hash: manifest.tree_hash(),
+            timestamp: 1760783380,
+            author: "".to_string(),
+            parent: None,
+            hash_algorithm: HashAlgorithm::Md5,
+        }
+    }
+
+    #[test]
+    pub fn file_merge_disjoint_changes_auto_merges() {
+        let base = manifest(&[("slot1.sav", "a"), ("slot3.sav", "b")]);
+        let local = manifest(&[("slot1.sav", "a2"), ("slot3.sav", "b")]);
+        let remote = manifest(&[("slot1.sav", "a"), ("slot3.sav", "b2")]);
+
+        let result = determine_sync_status(&SyncStatusCheckInput {
+            local_head: &Some(head_of(&base)),
+            current_head: &head_of(&local),
+            remote_head: &Some(head_of(&remote)),
+            remote_history: &[],
+            file_merge: Some(FileMergeInput {
+                base_manifest: &Some(base.clone()),
+                current_manifest: &local,
+                remote_manifest: &Some(remote.clone()),
+            }),
+        });
+
+        let CheckSyncResult::AutoMerge { plan } = result else {
+            panic!("Expected auto merge - got {:?}", result);
+        };
+        assert_eq!(plan.pull_files, vec!["slot3.sav".to_string()]);
+    }
+
+    #[test]
+    pub fn file_merge_same_file_falls_back_to_conflict() {
+        let base = manifest(&[("slot1.sav", "a")]);
+        let local = manifest(&[("slot1.sav", "a2")]);
+        let remote = manifest(&[("slot1.sav", "a3")]);
+
+        assert!(matches!(
+            determine_sync_status(&SyncStatusCheckInput {
+                local_head: &Some(head_of(&base)),
+                current_head: &head_of(&local),
+                remote_head: &Some(head_of(&remote)),
+                remote_history: &[],
+                file_merge: Some(FileMergeInput {
+                    base_manifest: &Some(base.clone()),
+                    current_manifest: &local,
+                    remote_manifest: &Some(remote.clone()),
+                }),
+            }),
+            CheckSyncResult::Conflict { .. }
+        ));
+    }
+
+    #[test]
+    pub fn file_merge_stale_remote_manifest_falls_back_to_conflict() {
+        let base = manifest(&[("slot1.sav", "a"), ("slot3.sav", "b")]);
+        let local = manifest(&[("slot1.sav", "a2"), ("slot3.sav", "b")]);
+        let remote = manifest(&[("slot1.sav", "a"), ("slot3.sav", "b2")]);
+
+        assert!(matches!(
+            determine_sync_status(&SyncStatusCheckInput {
+                local_head: &Some(head_of(&base)),
+                current_head: &head_of(&local),
+                remote_head: &Some(changed_head2()), // manifest does not describe this HEAD
+                remote_history: &[],
+                file_merge: Some(FileMergeInput {
+                    base_manifest: &Some(base.clone()),
+                    current_manifest: &local,
+                    remote_manifest: &Some(remote.clone()),
+                }),
+            }),
+            CheckSyncResult::Conflict { .. }
+        ));
+    }
 }
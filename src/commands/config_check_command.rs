@@ -0,0 +1,446 @@
+use crate::config::config_commons::{
+    ConfigSources, expand_config_placeholders, read_config_sources,
+};
+use crate::config::{
+    CURRENT_OS_NAME, SyncEntry, TransferCompression, ValidatedSyncOptions, build_ignore_globset,
+    is_valid_remote_key,
+};
+use crate::encryption::read_local_key;
+use crate::remote_save_client::read_remote_layout;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const GREEN_ANSI_ESCAPE: &str = "\x1b[32m";
+const YELLOW_ANSI_ESCAPE: &str = "\x1b[33m";
+const RED_ANSI_ESCAPE: &str = "\x1b[31m";
+const ANSI_RESET_ESCAPE: &str = "\x1b[0m";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum CheckStatus {
+    Pass,
+    Warn,
+    Fail,
+}
+
+/// One line of the `config check` table - the worst status of its details wins.
+#[derive(Debug)]
+struct CheckRow {
+    subject: String,
+    status: CheckStatus,
+    details: Vec<String>,
+}
+
+impl CheckRow {
+    fn new(subject: &str) -> CheckRow {
+        CheckRow {
+            subject: subject.to_string(),
+            status: CheckStatus::Pass,
+            details: vec![],
+        }
+    }
+
+    fn add(&mut self, status: CheckStatus, detail: String) {
+        self.status = self.status.max(status);
+        self.details.push(detail);
+    }
+}
+
+/// An entry as it ends up in the merged config - with where it was defined.
+struct SourcedEntry {
+    entry: SyncEntry,
+    source: String,
+}
+
+/// Entries in merge order - shared entries overridden by a local one are left out.
+fn collect_entries(sources: &ConfigSources) -> Vec<SourcedEntry> {
+    let config_name = sources
+        .config_file
+        .file_name()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .to_string();
+    let mut local: Vec<SourcedEntry> = sources
+        .options
+        .sync_entries
+        .iter()
+        .map(|entry| SourcedEntry {
+            entry: entry.clone(),
+            source: config_name.clone(),
+        })
+        .collect();
+    local.extend(sources.fragments.iter().map(|fragment| SourcedEntry {
+        entry: fragment.entry.clone(),
+        source: fragment.file.display().to_string(),
+    }));
+
+    let mut entries: Vec<SourcedEntry> = sources
+        .shared_entries
+        .iter()
+        .filter(|shared| {
+            !local
+                .iter()
+                .any(|local| local.entry.remote_sync_key == shared.remote_sync_key)
+        })
+        .map(|shared| SourcedEntry {
+            entry: shared.clone(),
+            source: String::from("shared entries"),
+        })
+        .collect();
+    entries.extend(local);
+    entries
+}
+
+/// Checks a single entry - returns its row and the save folder it resolves to on this machine.
+fn check_entry(
+    entry: &SourcedEntry,
+    hostname: &str,
+    variables: &BTreeMap<String, String>,
+) -> (CheckRow, Option<PathBuf>) {
+    let entry_source = &entry.source;
+    let entry = &entry.entry;
+    let mut row = CheckRow::new(&entry.remote_sync_key);
+    row.add(CheckStatus::Pass, format!("defined in {entry_source}"));
+
+    if !is_valid_remote_key(&entry.remote_sync_key) {
+        row.add(
+            CheckStatus::Fail,
+            String::from("remoteSyncKey must only contain [A-Za-z0-9_-]"),
+        );
+    }
+
+    let candidates = entry.save_folder_path.candidates(CURRENT_OS_NAME, hostname);
+    if candidates.is_empty() {
+        row.add(
+            CheckStatus::Fail,
+            format!("saveFolderPath has no path for {CURRENT_OS_NAME} (host {hostname})"),
+        );
+    }
+    let mut save_folder: Option<PathBuf> = None;
+    let mut resolved_any = false;
+    let mut unresolved: Vec<String> = vec![];
+    for (variant, path) in candidates.iter() {
+        let expanded = match expand_config_placeholders(path, variables) {
+            Ok(expanded) => expanded,
+            Err(e) => {
+                unresolved.push(format!("{variant}: {e}"));
+                continue;
+            }
+        };
+        resolved_any = true;
+        if save_folder.is_none() && Path::new(&expanded).is_dir() {
+            row.add(CheckStatus::Pass, format!("{variant}: {expanded}"));
+            save_folder = Some(PathBuf::from(expanded));
+        }
+    }
+    // Skipped when syncing as long as another candidate resolves - only fatal if none does.
+    let unresolved_status = match resolved_any {
+        true => CheckStatus::Warn,
+        false => CheckStatus::Fail,
+    };
+    for detail in unresolved {
+        row.add(unresolved_status, detail);
+    }
+    if save_folder.is_none() && resolved_any {
+        row.add(
+            CheckStatus::Warn,
+            String::from(
+                "save folder does not exist on this machine - game not installed or never launched?",
+            ),
+        );
+    }
+
+    if let Err(e) = build_ignore_globset(&entry.save_ignore_glob) {
+        row.add(CheckStatus::Fail, e);
+    }
+    if let Some(process_names) = &entry.process_names
+        && process_names.iter().any(|name| name.trim().is_empty())
+    {
+        row.add(
+            CheckStatus::Fail,
+            String::from("processNames must not contain empty names"),
+        );
+    }
+
+    (row, save_folder)
+}
+
+/// Per-entry rows - including duplicate keys and save folders shared or nested between entries.
+fn check_entries(
+    entries: &[SourcedEntry],
+    hostname: &str,
+    variables: &BTreeMap<String, String>,
+) -> Vec<CheckRow> {
+    let mut rows = vec![];
+    let mut save_folders: Vec<Option<PathBuf>> = vec![];
+    for entry in entries {
+        let (row, save_folder) = check_entry(entry, hostname, variables);
+        rows.push(row);
+        save_folders.push(save_folder.map(|path| fs::canonicalize(&path).unwrap_or(path)));
+    }
+
+    for (i, entry) in entries.iter().enumerate() {
+        let key = &entry.entry.remote_sync_key;
+        for (j, other) in entries.iter().enumerate() {
+            if i == j {
+                continue;
+            }
+            let other_key = &other.entry.remote_sync_key;
+            if key == other_key {
+                rows[i].add(
+                    CheckStatus::Fail,
+                    format!("duplicate key - also defined in {}", other.source),
+                );
+                continue;
+            }
+            let (Some(folder), Some(other_folder)) = (&save_folders[i], &save_folders[j]) else {
+                continue;
+            };
+            if folder == other_folder {
+                rows[i].add(
+                    CheckStatus::Fail,
+                    format!("same save folder as {other_key}"),
+                );
+            } else if folder.starts_with(other_folder) {
+                rows[i].add(
+                    CheckStatus::Warn,
+                    format!("save folder is inside the save folder of {other_key}"),
+                );
+            } else if other_folder.starts_with(folder) {
+                rows[i].add(
+                    CheckStatus::Warn,
+                    format!("save folder contains the save folder of {other_key}"),
+                );
+            }
+        }
+    }
+    rows
+}
+
+fn check_remote(options: &ValidatedSyncOptions) -> Vec<CheckRow> {
+    let mut ssh_row = CheckRow::new("ssh");
+    let mut layout_row = CheckRow::new("remote layout");
+    let layout = match read_remote_layout(options) {
+        Ok(layout) => layout,
+        Err(e) => {
+            ssh_row.add(CheckStatus::Fail, e);
+            layout_row.add(
+                CheckStatus::Warn,
+                String::from("skipped - remote unreachable"),
+            );
+            return vec![ssh_row, layout_row];
+        }
+    };
+    ssh_row.add(
+        CheckStatus::Pass,
+        format!("{}:{} reachable", options.ssh_host, options.ssh_port),
+    );
+
+    if !layout.root_exists {
+        layout_row.add(
+            CheckStatus::Fail,
+            format!("remoteSyncRoot {} does not exist", options.remote_sync_root),
+        );
+        return vec![ssh_row, layout_row];
+    }
+    layout_row.add(
+        CheckStatus::Pass,
+        format!("remoteSyncRoot {}", options.remote_sync_root),
+    );
+    let requirements = [
+        (layout.root_writable, "remoteSyncRoot is not writable"),
+        (layout.tar_installed, "tar is not installed"),
+        (
+            layout.restic_installed,
+            "restic is not installed - snapshots before pushes will fail",
+        ),
+        (
+            layout.restic_password,
+            ".cloudmeta/restic_password is missing or unreadable - snapshots before pushes will fail",
+        ),
+        (
+            layout.zstd_installed || options.transfer_compression != TransferCompression::Zstd,
+            "zstd is not installed - required for transferCompression zstd",
+        ),
+    ];
+    for (met, problem) in requirements {
+        if !met {
+            layout_row.add(CheckStatus::Fail, problem.to_string());
+        }
+    }
+    vec![ssh_row, layout_row]
+}
+
+fn print_rows(rows: &[CheckRow]) {
+    let width = rows
+        .iter()
+        .map(|row| row.subject.len())
+        .max()
+        .unwrap_or_default()
+        .max("CHECK".len());
+    println!("STATUS  {:width$}  DETAILS", "CHECK");
+    for row in rows {
+        let (color, label) = match row.status {
+            CheckStatus::Pass => (GREEN_ANSI_ESCAPE, "PASS"),
+            CheckStatus::Warn => (YELLOW_ANSI_ESCAPE, "WARN"),
+            CheckStatus::Fail => (RED_ANSI_ESCAPE, "FAIL"),
+        };
+        // Errors may span several lines - each gets its own table line.
+        let mut details = row
+            .details
+            .iter()
+            .flat_map(|detail| detail.lines())
+            .filter(|line| !line.trim().is_empty());
+        println!(
+            "{color}{label:6}{ANSI_RESET_ESCAPE}  {:width$}  {}",
+            row.subject,
+            details.next().unwrap_or_default()
+        );
+        for detail in details {
+            println!("{:6}  {:width$}  {detail}", "", "");
+        }
+    }
+}
+
+/// Validates the global options and every sync entry, and checks the remote unless `skip_remote`.
+/// Prints a pass/warn/fail table - errors if any check failed.
+pub fn config_check_command(
+    config_file_override: Option<&Path>,
+    skip_remote: bool,
+) -> Result<(), String> {
+    let mut rows: Vec<CheckRow> = vec![];
+
+    // 1. Config files
+    let mut config_row = CheckRow::new("config");
+    let sources = match read_config_sources(config_file_override) {
+        Ok(sources) => sources,
+        Err(e) => {
+            config_row.add(CheckStatus::Fail, e);
+            print_rows(&[config_row]);
+            return Err(String::from("Config check failed"));
+        }
+    };
+    config_row.add(
+        CheckStatus::Pass,
+        format!(
+            "{} - {} entries, {} in entries.d, {} shared",
+            sources.config_file.display(),
+            sources.options.sync_entries.len(),
+            sources.fragments.len(),
+            sources.shared_entries.len()
+        ),
+    );
+    rows.push(config_row);
+
+    // 2. Global options + remote
+    let mut options_row = CheckRow::new("global options");
+    match sources.options.clone().validate() {
+        Ok(options) => {
+            options_row.add(
+                CheckStatus::Pass,
+                format!(
+                    "client {} - head folder {}",
+                    options.client_name,
+                    options.local_head_folder.display()
+                ),
+            );
+            if options.encrypt && !matches!(read_local_key(&options.local_head_folder), Ok(Some(_)))
+            {
+                options_row.add(
+                    CheckStatus::Fail,
+                    String::from("encrypt is enabled but no key was found - run `keys init`"),
+                );
+            }
+            rows.push(options_row);
+            if !skip_remote {
+                rows.extend(check_remote(&options));
+            }
+        }
+        Err(e) => {
+            options_row.add(CheckStatus::Fail, e);
+            rows.push(options_row);
+        }
+    }
+
+    // 3. Entries
+    let hostname = gethostname::gethostname().to_string_lossy().to_string();
+    rows.extend(check_entries(
+        &collect_entries(&sources),
+        &hostname,
+        &sources.options.variables,
+    ));
+
+    print_rows(&rows);
+    let failures = rows
+        .iter()
+        .filter(|row| row.status == CheckStatus::Fail)
+        .count();
+    if failures > 0 {
+        return Err(format!("{failures} check(s) failed"));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{PathCandidates, SaveFolderPath};
+
+    fn sourced(key: &str, path: &Path, ignore: &[&str]) -> SourcedEntry {
+        SourcedEntry {
+            entry: SyncEntry {
+                remote_sync_key: key.to_string(),
+                save_folder_path: path.to_string_lossy().to_string().into(),
+                save_ignore_glob: ignore.iter().map(|glob| glob.to_string()).collect(),
+                display_name: None,
+                conflict_strategy: None,
+                remote_storage: None,
+                process_names: None,
+            },
+            source: String::from("sync_config.json"),
+        }
+    }
+
+    #[test]
+    fn reports_entry_problems() {
+        let root = std::env::temp_dir().join(format!(
+            "local_cloud_game_sync_check_test_{}",
+            std::process::id()
+        ));
+        fs::create_dir_all(root.join("game/nested")).unwrap();
+        let mut entries = vec![
+            sourced("game", &root.join("game"), &[]),
+            sourced("nested", &root.join("game/nested"), &["[broken"]),
+            sourced("game", &root.join("missing"), &[]),
+            sourced("bad key", &root.join("game"), &[]),
+            sourced("placeholder", Path::new("{{NOT_DEFINED}}/saves"), &[]),
+        ];
+
+        // Resolves through its second candidate - the first one only warns.
+        let mut other_os = sourced("other-os", &root.join("other-os"), &[]);
+        other_os.entry.save_folder_path = SaveFolderPath::Paths(PathCandidates::Candidates(vec![
+            String::from("{{NOT_DEFINED}}/saves"),
+            root.join("other-os").to_string_lossy().to_string(),
+        ]));
+        entries.push(other_os);
+        fs::create_dir_all(root.join("other-os")).unwrap();
+
+        let rows = check_entries(&entries, "host", &BTreeMap::new());
+        fs::remove_dir_all(&root).unwrap();
+
+        let statuses: Vec<CheckStatus> = rows.iter().map(|row| row.status).collect();
+        let mut expected = vec![CheckStatus::Fail; 5];
+        expected.push(CheckStatus::Warn);
+        assert_eq!(statuses, expected);
+        let details = |i: usize| rows[i].details.join("\n");
+        assert!(details(0).contains("duplicate key"));
+        assert!(details(0).contains("same save folder as bad key"));
+        assert!(details(1).contains("inside the save folder of game"));
+        assert!(details(1).contains("Invalid glob pattern"));
+        assert!(details(2).contains("does not exist on this machine"));
+        assert!(details(3).contains("[A-Za-z0-9_-]"));
+        assert!(details(4).contains("Unknown placeholder {{NOT_DEFINED}}"));
+        assert!(details(5).contains("saveFolderPath[0]: Unknown placeholder"));
+        assert!(!details(5).contains("does not exist on this machine"));
+    }
+}
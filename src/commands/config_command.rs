@@ -0,0 +1,162 @@
+use crate::config::config_commons::{
+    add_sync_entries_to_config, read_config_sources, remove_sync_entry_from_config,
+    resolve_config_file,
+};
+use crate::config::shared_entries::{
+    get_shared_entries_path, hash_shared_entries, parse_shared_entries, read_shared_entries,
+    read_shared_entries_head, write_shared_entries_head,
+};
+use crate::config::{SyncEntry, ValidatedSyncOptions, is_valid_remote_key};
+use crate::remote_save_client::{
+    RemoteLock, acquire_options_lock, read_remote_shared_entries, write_remote_shared_entries,
+};
+use std::fs;
+use std::path::Path;
+
+/// Adds the entry as a new `entries.d/<key>.json` fragment - after checking it validates on this machine.
+pub fn config_add_command(
+    config_file_override: Option<&Path>,
+    entry: SyncEntry,
+) -> Result<(), String> {
+    let variables = read_config_sources(config_file_override)?.options.variables;
+    entry.validate(&variables)?;
+    let entries_dir = add_sync_entries_to_config(config_file_override, &[entry.clone()])?;
+    println!(
+        "Added {} to {}",
+        entry.remote_sync_key,
+        entries_dir.display()
+    );
+    Ok(())
+}
+
+/// Deletes the key's `entries.d` fragment. Local HEAD files and remote saves are kept.
+pub fn config_remove_command(
+    config_file_override: Option<&Path>,
+    sync_key: &str,
+) -> Result<(), String> {
+    let removed_file = remove_sync_entry_from_config(config_file_override, sync_key)?;
+    println!("Removed {sync_key} - deleted {}", removed_file.display());
+    Ok(())
+}
+
+fn ensure_lock_acquired(acquired: bool) -> Result<(), String> {
+    if !acquired {
+        return Err(String::from(
+            "Unable to acquire lock - someone else has it.",
+        ));
+    }
+    Ok(())
+}
+
+/// Replaces the local shared entries with the remote's `.cloudmeta/entries.json`.
+/// Refuses to drop local edits that were never pushed unless `force` is set.
+pub fn config_pull_command(
+    options: &ValidatedSyncOptions,
+    config_file_override: Option<&Path>,
+    force: bool,
+) -> Result<(), String> {
+    let shared_entries_path = get_shared_entries_path(&resolve_config_file(config_file_override)?);
+
+    // 1. Get remote lock
+    let lock = acquire_options_lock(options)?;
+    ensure_lock_acquired(lock.is_acquired())?;
+
+    // 2. Read remote entries
+    let remote_content = read_remote_shared_entries(options)?.ok_or(String::from(
+        "The remote has no shared entries yet - share them with `config push`",
+    ))?;
+    let remote_entries = parse_shared_entries(&remote_content)?;
+
+    // 3. Ensure local edits are not lost
+    let previous_entries = read_shared_entries(&shared_entries_path)?;
+    if shared_entries_path.exists() && !force {
+        let local_content = fs::read_to_string(&shared_entries_path)
+            .map_err(|e| format!("Unable to read {}\n{e}", shared_entries_path.display()))?;
+        let local_head = read_shared_entries_head(&options.local_head_folder)?;
+        if local_head.as_deref() != Some(hash_shared_entries(&local_content).as_str()) {
+            return Err(format!(
+                "{} has changes that were never pushed - run `config push` first or pull with --force to discard them",
+                shared_entries_path.display()
+            ));
+        }
+    }
+
+    // 4. Store remote entries
+    fs::write(&shared_entries_path, &remote_content)
+        .map_err(|e| format!("Unable to write {}\n{e}", shared_entries_path.display()))?;
+    write_shared_entries_head(
+        &options.local_head_folder,
+        &hash_shared_entries(&remote_content),
+    )?;
+
+    println!(
+        "Pulled {} shared entries into {}",
+        remote_entries.len(),
+        shared_entries_path.display()
+    );
+    for entry in remote_entries.iter() {
+        if !previous_entries
+            .iter()
+            .any(|previous| previous.remote_sync_key == entry.remote_sync_key)
+        {
+            println!("\tNew: {}", entry.remote_sync_key);
+        }
+    }
+    Ok(())
+}
+
+/// Uploads the local shared entries as the remote's `.cloudmeta/entries.json`.
+/// Refuses to overwrite remote changes this device has not pulled unless `force` is set.
+pub fn config_push_command(
+    options: &ValidatedSyncOptions,
+    config_file_override: Option<&Path>,
+    force: bool,
+) -> Result<(), String> {
+    let shared_entries_path = get_shared_entries_path(&resolve_config_file(config_file_override)?);
+    if !shared_entries_path.exists() {
+        return Err(format!(
+            "No shared entries at {} - create it (a JSON array of sync entries) or run `config pull` first",
+            shared_entries_path.display()
+        ));
+    }
+
+    // 1. Validate local entries
+    let local_content = fs::read_to_string(&shared_entries_path)
+        .map_err(|e| format!("Unable to read {}\n{e}", shared_entries_path.display()))?;
+    let local_entries = parse_shared_entries(&local_content)?;
+    if let Some(entry) = local_entries
+        .iter()
+        .find(|entry| !is_valid_remote_key(&entry.remote_sync_key))
+    {
+        return Err(format!(
+            "Invalid remoteSyncKey \"{}\" - must only contains [A-Za-z0-9_-]",
+            entry.remote_sync_key
+        ));
+    }
+    let local_hash = hash_shared_entries(&local_content);
+
+    // 2. Get remote lock
+    let lock = acquire_options_lock(options)?;
+    ensure_lock_acquired(lock.is_acquired())?;
+
+    // 3. Ensure the remote did not change since the last pull
+    let remote_hash =
+        read_remote_shared_entries(options)?.map(|content| hash_shared_entries(&content));
+    if remote_hash.as_deref() == Some(local_hash.as_str()) {
+        write_shared_entries_head(&options.local_head_folder, &local_hash)?;
+        println!("Remote shared entries are already up to date");
+        return Ok(());
+    }
+    let local_head = read_shared_entries_head(&options.local_head_folder)?;
+    if remote_hash.is_some() && remote_hash != local_head && !force {
+        return Err(String::from(
+            "Remote shared entries changed since the last `config pull` - pull (and redo your edits) first or push with --force to overwrite them",
+        ));
+    }
+
+    // 4. Upload
+    write_remote_shared_entries(options, &local_content)?;
+    write_shared_entries_head(&options.local_head_folder, &local_hash)?;
+    println!("Pushed {} shared entries", local_entries.len());
+    Ok(())
+}
@@ -0,0 +1,150 @@
+use crate::config::config_commons::{add_sync_entries_to_config, default_save_database_path};
+use crate::config::{SyncEntry, ValidatedSyncOptions};
+use crate::save_database::{
+    ResolvedSaveLocation, SaveDatabaseGame, read_save_database, resolve_native_save_locations,
+    resolve_proton_save_locations,
+};
+use crate::steam::library::{SteamApp, scan_installed_apps};
+use crate::utils::{generate_key_from_name, get_steam_path};
+use std::io::{self, Write};
+use std::path::Path;
+
+/// A database game with a save folder found on this machine.
+struct DiscoveredGame {
+    key: String,
+    title: String,
+    location: ResolvedSaveLocation,
+}
+
+/// Existing save folders of the game - native paths first, then Windows paths inside its Proton prefix.
+fn find_save_locations(
+    options: &ValidatedSyncOptions,
+    game: &SaveDatabaseGame,
+    steam_apps: &[SteamApp],
+) -> Vec<ResolvedSaveLocation> {
+    let mut locations = resolve_native_save_locations(game, &options.variables);
+
+    let steam_app = game.steam.as_ref().and_then(|steam| {
+        steam_apps
+            .iter()
+            .find(|app| app.app_id == steam.id.to_string())
+    });
+    if let Some(app) = steam_app
+        && let Some(user_folder) = app.proton_user_folder()
+    {
+        locations.extend(resolve_proton_save_locations(
+            game,
+            &app.app_id,
+            &user_folder,
+        ));
+    }
+    locations
+}
+
+fn matches_query(title: &str, game: &SaveDatabaseGame, query: &str) -> bool {
+    let steam_id_matches = game
+        .steam
+        .as_ref()
+        .is_some_and(|steam| steam.id.to_string() == query);
+    steam_id_matches || title.to_lowercase().contains(&query.to_lowercase())
+}
+
+fn confirm(question: &str) -> Result<bool, String> {
+    print!("{question} [y/N] ");
+    io::stdout().flush().map_err(|e| e.to_string())?;
+    let mut answer = String::new();
+    io::stdin()
+        .read_line(&mut answer)
+        .map_err(|e| format!("Unable to read answer\n{e}"))?;
+    Ok(matches!(answer.trim().to_lowercase().as_str(), "y" | "yes"))
+}
+
+/// Looks up the database games (optionally filtered by title or Steam app id) whose save folders exist on this machine
+/// and offers to add them to `syncEntries`. Games already configured (same key or folder) are skipped.
+pub fn discover_command(
+    options: &ValidatedSyncOptions,
+    config_file_override: Option<&Path>,
+    database_path: Option<&Path>,
+    query: Option<&str>,
+    assume_yes: bool,
+) -> Result<(), String> {
+    let database_path = match database_path {
+        Some(path) => path.to_path_buf(),
+        None => default_save_database_path()?,
+    };
+    let database = read_save_database(&database_path)?;
+
+    // Steam is optional - without it only native paths are checked.
+    let steam_apps = match get_steam_path() {
+        Ok(steam_path) if steam_path.join("steamapps").is_dir() => {
+            scan_installed_apps(&steam_path)?
+        }
+        _ => vec![],
+    };
+
+    let mut discovered: Vec<DiscoveredGame> = vec![];
+    for (title, game) in database.iter() {
+        if query.is_some_and(|query| !matches_query(title, game, query)) {
+            continue;
+        }
+        let key = generate_key_from_name(title);
+        let locations = find_save_locations(options, game, &steam_apps);
+        let Some(location) = locations.into_iter().next() else {
+            continue;
+        };
+
+        if let Some(existing) = options.sync_entries.iter().find(|entry| {
+            entry.remote_sync_key == key
+                || entry
+                    .save_folder_path
+                    .all_paths()
+                    .contains(&location.config_path.as_str())
+        }) {
+            println!(
+                "{title} - already configured as {}",
+                existing.remote_sync_key
+            );
+            continue;
+        }
+        discovered.push(DiscoveredGame {
+            key: key,
+            title: title.clone(),
+            location: location,
+        });
+    }
+
+    if discovered.is_empty() {
+        println!("No new games with existing save folders found");
+        return Ok(());
+    }
+
+    let mut entries: Vec<SyncEntry> = vec![];
+    for game in discovered {
+        println!("* {} ({})", game.title, game.key);
+        println!("\tSave folder: {}", game.location.resolved_path.display());
+        if !assume_yes && !confirm(&format!("Add {}?", game.key))? {
+            continue;
+        }
+        entries.push(SyncEntry {
+            remote_sync_key: game.key,
+            save_folder_path: game.location.config_path.into(),
+            save_ignore_glob: vec![],
+            display_name: Some(game.title),
+            conflict_strategy: None,
+            remote_storage: None,
+            process_names: None,
+        });
+    }
+
+    if entries.is_empty() {
+        println!("Nothing added");
+        return Ok(());
+    }
+    let config_file = add_sync_entries_to_config(config_file_override, &entries)?;
+    println!(
+        "Added {} entries to {}",
+        entries.len(),
+        config_file.display()
+    );
+    Ok(())
+}
@@ -16,6 +16,10 @@ pub fn files_command(sync_config: &RuntimeSyncConfig) -> Result<(), String> {
         "{MAGENTA_ANSI_ESCAPE}Save Folder: {ANSI_RESET_ESCAPE} {}",
         sync_config.local_save_folder.display()
     );
+    println!(
+        "{MAGENTA_ANSI_ESCAPE}Save Folder Variant: {ANSI_RESET_ESCAPE} {}",
+        sync_config.save_folder_variant
+    );
     println!("{MAGENTA_ANSI_ESCAPE}Tracked Files: {ANSI_RESET_ESCAPE}");
     if tracked_files.is_empty() {
         println!("<no files>");
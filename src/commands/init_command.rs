@@ -1,12 +1,15 @@
-use crate::config::{default_sync_config_path, init_default_config};
+use crate::config::{ConfigFormat, default_sync_config_path, init_default_config};
 
-pub fn init_command() -> Result<(), String> {
-    let initted_folder = init_default_config()?;
+pub fn init_command(format: ConfigFormat) -> Result<(), String> {
+    let initted_folder = init_default_config(format)?;
     println!(
         "Initialized global config at: {} - Please ensure to fill out it's values",
         default_sync_config_path()?.display()
     );
 
-    println!("Initialized config folders at {}", initted_folder.display());
+    println!(
+        "Initialized per-game entries folder at {} - add games with `config add`",
+        initted_folder.display()
+    );
     Ok(())
 }
@@ -0,0 +1,91 @@
+use crate::config::ValidatedSyncOptions;
+use crate::encryption::{
+    EncryptionKey, RemoteKeyParams, get_local_key_filepath, read_local_key, write_local_key,
+};
+use crate::remote_save_client::{init_remote_key_params, read_remote_key_params};
+
+/// Derives the encryption key from the passphrase and stores it locally.
+/// The first device creates the key parameters on the remote - every other device must use the same passphrase.
+pub fn keys_init_command(options: &ValidatedSyncOptions, passphrase: &str) -> Result<(), String> {
+    ensure_no_local_key(options)?;
+
+    let key = match read_remote_key_params(options)? {
+        Some(params) => derive_matching_key(&params, passphrase)?,
+        None => {
+            let salt = RemoteKeyParams::generate_salt();
+            let key = EncryptionKey::derive(passphrase, &salt)?;
+            match init_remote_key_params(options, &RemoteKeyParams::new(&salt, &key)?)? {
+                true => {
+                    println!("Created new encryption key parameters on the remote");
+                    key
+                }
+                // Another device won the race - use its parameters instead.
+                false => {
+                    let params = read_remote_key_params(options)?
+                        .ok_or("Remote key parameters disappeared during init")?;
+                    derive_matching_key(&params, passphrase)?
+                }
+            }
+        }
+    };
+
+    write_local_key(&options.local_head_folder, &key)?;
+    println!(
+        "Stored encryption key at {}",
+        get_local_key_filepath(&options.local_head_folder).display()
+    );
+    Ok(())
+}
+
+/// Stores a key previously printed by `keys export` - verified against the remote if it already has a key.
+pub fn keys_import_command(options: &ValidatedSyncOptions, exported: &str) -> Result<(), String> {
+    ensure_no_local_key(options)?;
+
+    let key = EncryptionKey::import(exported)?;
+    if let Some(params) = read_remote_key_params(options)? {
+        if !params.verify(&key)? {
+            return Err(String::from(
+                "Imported key does not match the key already used by the remote",
+            ));
+        }
+    }
+
+    write_local_key(&options.local_head_folder, &key)?;
+    println!(
+        "Stored encryption key at {}",
+        get_local_key_filepath(&options.local_head_folder).display()
+    );
+    Ok(())
+}
+
+/// Prints the local key so it can be backed up or imported on another device.
+pub fn keys_export_command(options: &ValidatedSyncOptions) -> Result<(), String> {
+    let key = read_local_key(&options.local_head_folder)?
+        .ok_or("No encryption key found - run `keys init` first")?;
+    println!("{}", key.export());
+    Ok(())
+}
+
+fn ensure_no_local_key(options: &ValidatedSyncOptions) -> Result<(), String> {
+    let key_path = get_local_key_filepath(&options.local_head_folder);
+    if key_path.exists() {
+        return Err(format!(
+            "Encryption key already exists at {}. Delete file first if you want to re-create",
+            key_path.display()
+        ));
+    }
+    Ok(())
+}
+
+fn derive_matching_key(
+    params: &RemoteKeyParams,
+    passphrase: &str,
+) -> Result<EncryptionKey, String> {
+    let key = EncryptionKey::derive(passphrase, &params.salt()?)?;
+    if !params.verify(&key)? {
+        return Err(String::from(
+            "Passphrase does not match the key already used by the remote",
+        ));
+    }
+    Ok(key)
+}
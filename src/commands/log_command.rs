@@ -0,0 +1,60 @@
+use crate::config::RuntimeSyncConfig;
+use crate::local_head::{read_local_head, read_local_journal};
+use crate::remote_save_client::{RemoteSaveClient, get_default_remote_save_client};
+
+const YELLOW_ANSI_ESCAPE: &str = "\x1b[33m";
+const MAGENTA_ANSI_ESCAPE: &str = "\x1b[36m";
+const ANSI_RESET_ESCAPE: &str = "\x1b[0m";
+
+/// Prints the sync history of the given key across every device - newest first.
+pub fn log_command(
+    sync_config: &RuntimeSyncConfig,
+    limit: Option<usize>,
+    json: bool,
+) -> Result<(), String> {
+    let client = get_default_remote_save_client(sync_config);
+
+    // Remote history is append-only - safe to read without lock. Fallback to this device's journal when offline.
+    let mut history = match client.get_remote_sync_history() {
+        Ok(history) => history,
+        Err(e) => {
+            eprintln!("Unable to read remote history - showing local journal instead.\n{e}");
+            read_local_journal(sync_config)?
+        }
+    };
+
+    history.reverse();
+    if let Some(limit) = limit {
+        history.truncate(limit);
+    }
+
+    if json {
+        let json_str = serde_json::to_string_pretty(&history)
+            .map_err(|e| format!("Unable to serialize sync history\n{e}"))?;
+        println!("{json_str}");
+        return Ok(());
+    }
+
+    println!(
+        "{MAGENTA_ANSI_ESCAPE}Sync key:{ANSI_RESET_ESCAPE} {}",
+        sync_config.remote_sync_key
+    );
+    if history.is_empty() {
+        println!("<no history>");
+        return Ok(());
+    }
+
+    let local_head = read_local_head(sync_config)?;
+    for event in history.iter() {
+        let marker_display = match local_head
+            .as_ref()
+            .is_some_and(|head| *head == event.revision)
+        {
+            true => format!(" {YELLOW_ANSI_ESCAPE}[local HEAD]{ANSI_RESET_ESCAPE}"),
+            false => String::new(),
+        };
+        println!("* {event}{marker_display}");
+    }
+
+    Ok(())
+}
@@ -0,0 +1,148 @@
+use crate::cancellation::CancellationToken;
+use crate::config::RuntimeSyncConfig;
+use crate::game_process::ensure_game_not_running;
+use crate::local_head::{
+    generate_current_head_with_manifest, read_local_head, read_local_manifest,
+};
+use crate::progress::{ProgressEvent, SyncPhase, TransferProgress};
+use crate::remote_save_client::{RemoteLock, RemoteSaveClient, get_default_remote_save_client};
+use crate::sync_history::SyncEventKind;
+use crate::tree_utils::{apply_merge_plan, create_staging_temp_folder};
+use std::time::Instant;
+
+use super::check_sync_command::plan_verified_file_merge;
+use super::push_command::push_with_lock_held;
+
+pub fn merge_command(
+    sync_config: &RuntimeSyncConfig,
+    merge_if_head: Option<&str>,
+) -> Result<(), String> {
+    merge_command_with_update_callback(
+        sync_config,
+        merge_if_head,
+        |_| {},
+        &CancellationToken::new(),
+    )
+}
+
+/// Merges remote changes into local when both changed different files - then pushes the merged result.
+pub fn merge_command_with_update_callback<F>(
+    sync_config: &RuntimeSyncConfig,
+    merge_if_head: Option<&str>,
+    update_callback: F,
+    cancel: &CancellationToken,
+) -> Result<(), String>
+where
+    F: Fn(ProgressEvent),
+{
+    let started_at = Instant::now();
+    let client = get_default_remote_save_client(&sync_config);
+
+    // 0. Never overwrite saves the game may still have open.
+    ensure_game_not_running(sync_config, "merging")?;
+
+    // 1. Get remote lock
+    update_callback(ProgressEvent::step(
+        SyncPhase::Locking,
+        "Getting Remote Lock...",
+    ));
+    let _lock = client.acquire_lock()?;
+    if !_lock.is_acquired() {
+        return Err(String::from(
+            "Unable to acquire lock - someone else has it.",
+        ));
+    }
+    update_callback(ProgressEvent::step(
+        SyncPhase::Locking,
+        "Remote lock acquired",
+    ));
+    cancel.check()?;
+
+    // 2. Get HEAD contents
+    update_callback(ProgressEvent::step(
+        SyncPhase::Comparing,
+        "Reading Remote HEAD",
+    ));
+    let remote_head = client.get_remote_head()?;
+    let Some(remote_head_rev) = remote_head.as_ref() else {
+        return Err(format!(
+            "Unable to merge - no remote data found for given key {}",
+            sync_config.remote_sync_key
+        ));
+    };
+
+    // 2.1. Check if head matches as expected - if provided
+    if let Some(merge_if_head) = merge_if_head {
+        if remote_head_rev.hash != merge_if_head {
+            return Err(format!(
+                "HEAD was modified between check and merge. Expected: {merge_if_head} Found: {remote_head_rev}. Please try again."
+            ));
+        }
+    };
+
+    // 3. Plan merge - re-done under lock as the remote might have changed since check.
+    update_callback(ProgressEvent::step(
+        SyncPhase::Comparing,
+        "Comparing local and remote files...",
+    ));
+    let Some(local_head) = read_local_head(sync_config)? else {
+        return Err(String::from(
+            "Unable to merge - no local HEAD to use as merge base.",
+        ));
+    };
+    let base_manifest = read_local_manifest(sync_config)?;
+    // Hashed like the remote - so digests of the manifests are comparable.
+    let (_current_head, current_manifest) = generate_current_head_with_manifest(
+        &sync_config.local_save_folder,
+        &sync_config,
+        remote_head_rev.hash_algorithm,
+    )?;
+    let remote_manifest = client.get_remote_manifest()?;
+
+    let plan = plan_verified_file_merge(
+        &base_manifest,
+        &local_head,
+        &current_manifest,
+        &remote_manifest,
+        remote_head_rev,
+    )
+    .ok_or(String::from(
+        "Unable to merge automatically - the same files were changed both locally and on remote.",
+    ))?;
+
+    cancel.check()?;
+
+    // 4. Bring remote changes into local folder
+    let merge_folder = create_staging_temp_folder()?;
+    if !plan.pull_files.is_empty() {
+        let progress = TransferProgress::new(
+            &update_callback,
+            SyncPhase::Merging,
+            "Merging remote changes...",
+            remote_manifest
+                .as_ref()
+                .map(|manifest| manifest.files.len() as u64),
+            remote_manifest
+                .as_ref()
+                .and_then(|manifest| manifest.total_size()),
+        );
+        client.pull_into(&merge_folder.path, &progress, cancel)?;
+    }
+    apply_merge_plan(&merge_folder.path, &sync_config.local_save_folder, &plan)?;
+    println!(
+        "Merged remote HEAD {remote_head_rev} into local: {} file(s) updated, {} file(s) deleted",
+        plan.pull_files.len(),
+        plan.delete_files.len()
+    );
+
+    // 5. Local now holds both changes - push it.
+    push_with_lock_held(
+        &client,
+        sync_config,
+        &remote_head,
+        SyncEventKind::Merge,
+        started_at,
+        &update_callback,
+        cancel,
+    )
+}
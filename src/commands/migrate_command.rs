@@ -0,0 +1,28 @@
+use crate::config::RuntimeSyncConfig;
+use crate::local_head::migrate_local_head;
+use crate::remote_save_client::{RemoteLock, RemoteSaveClient, get_default_remote_save_client};
+
+/// Rewrites legacy-format remote and local HEAD files of the given key in the current format.
+/// NOTE: Clients older than the versioned format won't be able to read migrated HEADs.
+pub fn migrate_command(sync_config: &RuntimeSyncConfig) -> Result<(), String> {
+    let client = get_default_remote_save_client(sync_config);
+
+    let _lock = client.acquire_lock()?;
+    if !_lock.is_acquired() {
+        return Err(String::from(
+            "Unable to acquire lock - someone else has it.",
+        ));
+    }
+
+    match client.migrate_remote_head()? {
+        true => println!("Migrated remote HEAD to the current format"),
+        false => println!("Remote HEAD already up-to-date (or missing) - nothing to migrate"),
+    }
+
+    match migrate_local_head(sync_config)? {
+        true => println!("Migrated local HEAD to the current format"),
+        false => println!("Local HEAD already up-to-date (or missing) - nothing to migrate"),
+    }
+
+    Ok(())
+}
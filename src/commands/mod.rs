@@ -1,16 +1,36 @@
 mod check_sync_command;
+mod config_check_command;
+mod config_command;
+mod discover_command;
 mod files_command;
 mod init_command;
+mod keys_command;
+mod log_command;
+mod merge_command;
+mod migrate_command;
 mod open_config_command;
 mod pull_command;
 mod push_command;
+mod run_command;
+mod steam_scan_command;
 mod sync_command;
 
 pub use check_sync_command::CheckSyncResult;
 pub use check_sync_command::{check_sync_command, check_sync_command_quiet};
+pub use config_check_command::config_check_command;
+pub use config_command::{
+    config_add_command, config_pull_command, config_push_command, config_remove_command,
+};
+pub use discover_command::discover_command;
 pub use files_command::files_command;
 pub use init_command::init_command;
-pub use open_config_command::open_default_config_file;
+pub use keys_command::{keys_export_command, keys_import_command, keys_init_command};
+pub use log_command::log_command;
+pub use merge_command::{merge_command, merge_command_with_update_callback};
+pub use migrate_command::migrate_command;
+pub use open_config_command::open_config_file;
 pub use pull_command::{pull_command, pull_command_with_update_callback};
 pub use push_command::{push_command, push_command_with_update_callback};
-pub use sync_command::sync_command;
+pub use run_command::{LaunchMode, get_run_log_filepath, run_command};
+pub use steam_scan_command::steam_scan_command;
+pub use sync_command::{pending_command, sync_command, sync_pending_command};
@@ -1,7 +1,9 @@
-use crate::{config::default_sync_config_path, utils};
+use crate::{config::config_commons::resolve_config_file, utils};
+use std::path::Path;
 
-pub fn open_default_config_file() -> Result<(), String> {
-    let configspath = default_sync_config_path()?;
+/// Opens the config file in use - the `--config` override or whichever default config exists.
+pub fn open_config_file(config_file_override: Option<&Path>) -> Result<(), String> {
+    let configspath = resolve_config_file(config_file_override)?;
     let _ = utils::open_file(&configspath).map_err(|e| e.to_string())?;
     Ok(())
 }
@@ -1,36 +1,60 @@
+use crate::cancellation::CancellationToken;
 use crate::config::RuntimeSyncConfig;
-use crate::local_head::{generate_current_head, write_local_head};
+use crate::game_process::ensure_game_not_running;
+use crate::local_head::{
+    generate_current_head_with_manifest, matches_current_files, read_local_head, write_local_head,
+    write_local_history, write_local_manifest,
+};
+use crate::pending_sync::clear_pending_sync;
+use crate::progress::{ProgressEvent, SyncPhase, TransferProgress};
 use crate::remote_save_client::{RemoteLock, RemoteSaveClient, get_default_remote_save_client};
+use crate::sync_history::{SyncEventKind, record_sync_event};
+use std::time::Instant;
 
 pub fn pull_command(
     sync_config: &RuntimeSyncConfig,
     push_if_head: Option<&str>,
 ) -> Result<(), String> {
-    pull_command_with_update_callback(sync_config, push_if_head, |_| {})
+    pull_command_with_update_callback(sync_config, push_if_head, |_| {}, &CancellationToken::new())
 }
 
 pub fn pull_command_with_update_callback<F>(
     sync_config: &RuntimeSyncConfig,
     push_if_head: Option<&str>,
     update_callback: F,
+    cancel: &CancellationToken,
 ) -> Result<(), String>
 where
-    F: Fn(String),
+    F: Fn(ProgressEvent),
 {
+    let started_at = Instant::now();
     let client = get_default_remote_save_client(&sync_config);
 
+    // 0. Never overwrite saves the game may still have open.
+    ensure_game_not_running(sync_config, "pulling")?;
+
     // 1. Get remote lock
-    update_callback("Getting Remote Lock...".to_string());
+    update_callback(ProgressEvent::step(
+        SyncPhase::Locking,
+        "Getting Remote Lock...",
+    ));
     let _lock = client.acquire_lock()?;
     if !_lock.is_acquired() {
         return Err(String::from(
             "Unable to acquire lock - someone else has it.",
         ));
     }
-    update_callback("Remote lock acquired".to_string());
+    update_callback(ProgressEvent::step(
+        SyncPhase::Locking,
+        "Remote lock acquired",
+    ));
+    cancel.check()?;
 
     // 2. Get HEAD contents
-    update_callback("Reading Remote HEAD".to_string());
+    update_callback(ProgressEvent::step(
+        SyncPhase::Comparing,
+        "Reading Remote HEAD",
+    ));
     let remote_head = client.get_remote_head()?;
     let Some(remote_head) = remote_head else {
         return Err(format!(
@@ -50,26 +74,76 @@ where
 
     // 3. Get current hash - stop if local already has same hash
     // NOTE: This does not check or rely on current local uploaded logic - this only relies on existing runtime-based logic.
-    update_callback("Comparing with local files...".to_string());
+    update_callback(ProgressEvent::step(
+        SyncPhase::Comparing,
+        "Comparing with local files...",
+    ));
     // Any decision handling logic should be handled by other commands.
-    let local_hash = generate_current_head(&sync_config.local_save_folder, &sync_config)?;
+    let (local_hash, local_manifest) = generate_current_head_with_manifest(
+        &sync_config.local_save_folder,
+        &sync_config,
+        remote_head.hash_algorithm,
+    )?;
     if remote_head == local_hash {
         println!("Local is up-to-date found same HEAD: {local_hash}");
         // Ensure head is up to date anyways. Maybe we reached that good new state manually
         write_local_head(&sync_config, &remote_head)?;
+        write_local_manifest(&sync_config, Some(&local_manifest))?;
+        clear_pending_sync(&sync_config.local_head_folder, &sync_config.remote_sync_key)?;
         return Ok(());
     }
 
+    cancel.check()?;
+
+    // Overwriting local changes that were never pushed means a conflict was resolved.
+    let resolved_conflict = match read_local_head(&sync_config)? {
+        Some(head) => !matches_current_files(&head, &local_hash, &sync_config)?,
+        None => false,
+    };
+
     // 4. Actually pull
-    update_callback("Pulling new version from remote...".to_string());
-    client.pull()?;
+    // Manifest is only kept as merge base if it actually describes the HEAD we're pulling.
+    let remote_manifest = client
+        .get_remote_manifest()?
+        .filter(|manifest| manifest.tree_hash() == remote_head.hash);
+    let history = client.get_remote_history()?;
+    let progress = TransferProgress::new(
+        &update_callback,
+        SyncPhase::Downloading,
+        "Pulling new version from remote...",
+        remote_manifest
+            .as_ref()
+            .map(|manifest| manifest.files.len() as u64),
+        remote_manifest
+            .as_ref()
+            .and_then(|manifest| manifest.total_size()),
+    );
+    client.pull(&progress, cancel)?;
     println!("Pulled local to new HEAD {remote_head} successfully!");
 
-    update_callback("Updating local repository file".to_string());
+    update_callback(ProgressEvent::step(
+        SyncPhase::Finalizing,
+        "Updating local repository file",
+    ));
     // 5. Update local head
     write_local_head(&sync_config, &remote_head)?;
+    write_local_manifest(&sync_config, remote_manifest.as_ref())?;
+    write_local_history(&sync_config, &history)?;
+    // Un-pushed local progress (if any) was deliberately overwritten.
+    clear_pending_sync(&sync_config.local_head_folder, &sync_config.remote_sync_key)?;
     println!("Successfully updated local head");
-    update_callback("Successfully pulled from remote!".to_string());
+    record_sync_event(
+        &client,
+        sync_config,
+        SyncEventKind::Pull,
+        resolved_conflict,
+        &remote_head,
+        started_at,
+    );
+    update_callback(ProgressEvent::step(
+        SyncPhase::Done,
+        "Successfully pulled from remote!",
+    ));
 
     Ok(())
 }
@@ -1,37 +1,61 @@
+use crate::cancellation::CancellationToken;
+use crate::common::Revision;
 use crate::config::RuntimeSyncConfig;
-use crate::local_head::{generate_current_head, write_local_head};
+use crate::game_process::ensure_game_not_running;
+use crate::local_head::{
+    generate_current_head_with_manifest, read_local_head, write_local_head, write_local_history,
+    write_local_manifest,
+};
+use crate::pending_sync::clear_pending_sync;
+use crate::progress::{ProgressEvent, SyncPhase, TransferProgress};
 use crate::remote_save_client::{RemoteLock, RemoteSaveClient, get_default_remote_save_client};
-use crate::tree_utils::tree_folder_temp_copy;
+use crate::sync_history::{SyncEventKind, record_sync_event};
+use std::time::Instant;
 
 pub fn push_command(
     sync_config: &RuntimeSyncConfig,
     push_if_head: Option<&str>,
 ) -> Result<(), String> {
-    push_command_with_update_callback(sync_config, push_if_head, |_| {})
+    push_command_with_update_callback(sync_config, push_if_head, |_| {}, &CancellationToken::new())
 }
 
 pub fn push_command_with_update_callback<F>(
     sync_config: &RuntimeSyncConfig,
     pull_if_head: Option<&str>,
     update_callback: F,
+    cancel: &CancellationToken,
 ) -> Result<(), String>
 where
-    F: Fn(String),
+    F: Fn(ProgressEvent),
 {
+    let started_at = Instant::now();
     let client = get_default_remote_save_client(&sync_config);
 
+    // 0. Never upload saves the game may be halfway through writing.
+    ensure_game_not_running(sync_config, "pushing")?;
+
     // 1. Get remote lock
-    update_callback("Getting Remote Lock...".to_string());
+    update_callback(ProgressEvent::step(
+        SyncPhase::Locking,
+        "Getting Remote Lock...",
+    ));
     let _lock = client.acquire_lock()?;
     if !_lock.is_acquired() {
         return Err(String::from(
             "Unable to acquire lock - someone else has it.",
         ));
     }
-    update_callback("Remote lock acquired".to_string());
+    update_callback(ProgressEvent::step(
+        SyncPhase::Locking,
+        "Remote lock acquired",
+    ));
+    cancel.check()?;
 
     // 2. Get HEAD contents
-    update_callback("Reading Remote HEAD".to_string());
+    update_callback(ProgressEvent::step(
+        SyncPhase::Comparing,
+        "Reading Remote HEAD",
+    ));
     let remote_head = client.get_remote_head()?;
     // 2.1. Check if head matches as expected - if provided
     if let Some(push_if_head) = pull_if_head {
@@ -43,17 +67,65 @@ where
         }
     };
 
+    push_with_lock_held(
+        &client,
+        sync_config,
+        &remote_head,
+        SyncEventKind::Push,
+        started_at,
+        &update_callback,
+        cancel,
+    )
+}
+
+/// Performs the actual push. Caller must be holding the remote lock and have read the remote HEAD under it.
+pub(super) fn push_with_lock_held<'c, F>(
+    client: &impl RemoteSaveClient<'c>,
+    sync_config: &RuntimeSyncConfig,
+    remote_head: &Option<Revision>,
+    event_kind: SyncEventKind,
+    started_at: Instant,
+    update_callback: &F,
+    cancel: &CancellationToken,
+) -> Result<(), String>
+where
+    F: Fn(ProgressEvent),
+{
     // 3. Get current hash - stop if remote already has same hash.
     // NOTE: This does not check or rely on current local uploaded logic - this only relies on existing runtime-based logic.
     // Any decision handling logic should be handled by other commands.
-    update_callback("Comparing with local files...".to_string());
-    let local_hash = generate_current_head(&sync_config.local_save_folder, &sync_config)?;
+    update_callback(ProgressEvent::step(
+        SyncPhase::Comparing,
+        "Comparing with local files...",
+    ));
+    let (mut local_hash, local_manifest) = generate_current_head_with_manifest(
+        &sync_config.local_save_folder,
+        &sync_config,
+        sync_config.hash_algorithm,
+    )?;
     if remote_head.clone().is_some_and(|head| head == local_hash) {
         println!("Remote is up-to-date found same HEAD: {local_hash}");
+        // Ensure head is up to date anyways. Maybe both sides reached the same state (e.g. after a merge)
+        write_local_head(&sync_config, &local_hash)?;
+        write_local_manifest(&sync_config, Some(&local_manifest))?;
+        clear_pending_sync(&sync_config.local_head_folder, &sync_config.remote_sync_key)?;
         return Ok(());
     }
 
-    update_callback("Snapshotting previous save version...".to_string());
+    cancel.check()?;
+
+    // Overwriting a remote HEAD other than the one we last synced means a conflict was resolved.
+    let local_head = read_local_head(&sync_config)?;
+    let resolved_conflict = remote_head.as_ref().is_some_and(|head| {
+        local_head
+            .as_ref()
+            .is_none_or(|local_head| local_head != head)
+    });
+
+    update_callback(ProgressEvent::step(
+        SyncPhase::Snapshotting,
+        "Snapshotting previous save version...",
+    ));
     // 4. Perform remote snapshot
     match remote_head.as_ref() {
         Some(head) => {
@@ -69,24 +141,59 @@ where
         }
     };
 
-    // 5. Actually push.
-    update_callback("Uploading game saves...".to_string());
-    let temp_folder =
-        tree_folder_temp_copy(&sync_config.local_save_folder, &sync_config.ignore_globset)?;
-    client.push(&temp_folder, &local_hash)?;
+    cancel.check()?;
+
+    // 5. Actually push - new revision descends from the remote HEAD it replaces.
+    local_hash.parent = remote_head.as_ref().map(|head| head.hash.clone());
+    let mut history = client.get_remote_history()?;
+    let progress = TransferProgress::new(
+        update_callback,
+        SyncPhase::Uploading,
+        "Uploading game saves...",
+        Some(local_manifest.files.len() as u64),
+        local_manifest.total_size(),
+    );
+    client.push(
+        &sync_config.local_save_folder,
+        &local_hash,
+        &local_manifest,
+        &progress,
+        cancel,
+    )?;
     println!("Pushed to remote new HEAD {local_hash} successfully!");
 
-    update_callback("Updating local repository file...".to_string());
+    update_callback(ProgressEvent::step(
+        SyncPhase::Finalizing,
+        "Updating local repository file...",
+    ));
     // 6. Update local head
     write_local_head(&sync_config, &local_hash)?;
+    write_local_manifest(&sync_config, Some(&local_manifest))?;
+    history.push(local_hash.clone());
+    write_local_history(&sync_config, &history)?;
+    clear_pending_sync(&sync_config.local_head_folder, &sync_config.remote_sync_key)?;
     println!("Successfully updated local head");
+    record_sync_event(
+        client,
+        sync_config,
+        event_kind,
+        resolved_conflict,
+        &local_hash,
+        started_at,
+    );
 
     // 7. Perform snapshot again after update.
-    update_callback("Snapshotting newly uploaded save version...".to_string());
+    update_callback(ProgressEvent::step(
+        SyncPhase::Snapshotting,
+        "Snapshotting newly uploaded save version...",
+    ));
     println!("Triggering post-upload remote snapshot");
     client.remote_snapshot()?;
     println!("Successfully snapshotted HEAD: {}", local_hash);
-    update_callback("Successfully uploaded to remote...".to_string());
+    update_callback(ProgressEvent::step(
+        SyncPhase::Done,
+        "Successfully uploaded to remote...",
+    ));
 
     Ok(())
 }
@@ -0,0 +1,120 @@
+use crate::game_process::run_and_wait_for_process_tree;
+use crate::log_file::LogFile;
+use crate::proton::ProtonLaunch;
+use std::env;
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// How `run` starts the game command.
+pub enum LaunchMode {
+    /// Runs the command as given.
+    Native,
+    /// Runs a Windows executable through reaper + Steam Linux Runtime + Proton - like Steam does.
+    /// Uses the newest installed Proton unless a folder name under `steamapps/common` is given.
+    Proton { proton_version: Option<String> },
+}
+
+pub fn get_run_log_filepath(local_head_folder: &Path, sync_key: &str) -> PathBuf {
+    return local_head_folder.join(format!("{sync_key}.run.log"));
+}
+
+/// Runs the sync UI in a separate process - the UI event loop can only run once per process.
+fn run_sync_ui(
+    sync_key: &str,
+    global_args: &[OsString],
+    after_game: bool,
+    log: &LogFile,
+) -> Result<bool, String> {
+    let current_exe =
+        env::current_exe().map_err(|e| format!("Unable to locate lcgsync executable\n{e}"))?;
+    let mut command = Command::new(current_exe);
+    command.args(global_args).arg("ui").arg(sync_key);
+    if after_game {
+        command.arg("--after-game");
+    }
+
+    let status = command
+        .status()
+        .map_err(|e| format!("Unable to start sync UI\n{e}"))?;
+    if !status.success() {
+        log.log(&format!("Sync UI exited with {status}"));
+    }
+    Ok(status.success())
+}
+
+fn build_game_command(
+    game_command: &[String],
+    launch_mode: &LaunchMode,
+) -> Result<Command, String> {
+    let (program, args) = game_command
+        .split_first()
+        .ok_or(String::from("No game command given"))?;
+
+    match launch_mode {
+        LaunchMode::Native => {
+            let mut command = Command::new(program);
+            command.args(args);
+            Ok(command)
+        }
+        LaunchMode::Proton { proton_version } => {
+            let game_exe = Path::new(program);
+            if !game_exe.is_file() {
+                return Err(format!("Executable not found: {}", game_exe.display()));
+            }
+            let proton = ProtonLaunch::detect(proton_version.as_deref())?;
+            Ok(proton.build_command(game_exe, args))
+        }
+    }
+}
+
+/// Pre-game sync UI -> game -> after-game sync UI. Returns the game's exit code.
+/// The game is not launched if the pre-game sync is closed without syncing. `global_args` are passed on to the UI (e.g. `--config`).
+pub fn run_command(
+    sync_key: &str,
+    game_command: &[String],
+    launch_mode: &LaunchMode,
+    global_args: &[OsString],
+    local_head_folder: &Path,
+) -> Result<i32, String> {
+    let log = LogFile::open(&get_run_log_filepath(local_head_folder, sync_key))?;
+    log.log(&format!(
+        "=== Run started for {sync_key}: {} ===",
+        game_command.join(" ")
+    ));
+
+    // 0. Resolve the launch before syncing - so a broken setup doesn't prompt for a sync first.
+    let mut command = build_game_command(game_command, launch_mode).inspect_err(|e| log.log(e))?;
+    log.log(&format!("Game command: {command:?}"));
+
+    // 1. Pre-sync
+    log.log("[PRE-SYNC] Running sync UI...");
+    if !run_sync_ui(sync_key, global_args, false, &log)? {
+        log.log("[PRE-SYNC] Failed - aborting game launch.");
+        return Err(format!(
+            "Pre-game sync for {sync_key} did not complete - game not launched"
+        ));
+    }
+    log.log("[PRE-SYNC] Success.");
+
+    // 2. Game - Ctrl+C reaches the game too. It is ignored here so the after-game sync still runs.
+    if let Err(e) = ctrlc::set_handler(|| eprintln!("\nWaiting for the game to exit...")) {
+        log.log(&format!("Unable to set Ctrl+C handler\n{e}"));
+    }
+    log.log("[GAME] Launching...");
+    let game_exit_code = run_and_wait_for_process_tree(&mut command).inspect_err(|e| log.log(e))?;
+    log.log(&format!("[GAME] Exited with code {game_exit_code}"));
+
+    // 3. Post-sync - failures are recorded as pending by the UI. The game's exit code is kept either way.
+    log.log("[POST-SYNC] Running sync UI after game...");
+    match run_sync_ui(sync_key, global_args, true, &log) {
+        Ok(true) => log.log("[POST-SYNC] Success."),
+        Ok(false) => log.log("[POST-SYNC] Warning: after-game sync did not complete."),
+        Err(e) => log.log(&format!("[POST-SYNC] Warning: {e}")),
+    }
+
+    log.log(&format!(
+        "=== Run finished for {sync_key} - exit code {game_exit_code} ==="
+    ));
+    Ok(game_exit_code)
+}
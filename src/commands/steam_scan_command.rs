@@ -0,0 +1,122 @@
+use crate::config::SyncEntry;
+use crate::steam::library::{SteamApp, is_same_path, read_library_folders, scan_installed_apps};
+use crate::utils::{generate_key_from_name, get_steam_path};
+use std::path::Path;
+
+/// Draft entry for the app - pointing at the Proton user folder (or install folder for native games).
+/// Placeholders are used where possible - native apps in secondary libraries get absolute paths.
+fn draft_sync_entry(app: &SteamApp, steam_path: &Path) -> SyncEntry {
+    let in_primary_library = is_same_path(&app.library, steam_path);
+    let proton_user_folder = app.proton_user_folder_config_path();
+    let save_folder_path = match (proton_user_folder, in_primary_library) {
+        (Some(proton_user_folder), _) => proton_user_folder,
+        (None, true) => format!(
+            "{{{{STEAM_COMMON}}}}/{}",
+            app.install_dir
+                .file_name()
+                .unwrap_or_default()
+                .to_string_lossy()
+        ),
+        (None, false) => app.install_dir.to_string_lossy().to_string(),
+    };
+
+    SyncEntry {
+        remote_sync_key: generate_key_from_name(&app.name),
+        save_folder_path: save_folder_path.into(),
+        save_ignore_glob: vec![],
+        display_name: Some(app.name.clone()),
+        conflict_strategy: None,
+        remote_storage: None,
+        process_names: None,
+    }
+}
+
+/// Lists the installed Steam apps across all libraries. With `draft_entries` prints draft sync entries instead -
+/// for the given app ids or every app.
+pub fn steam_scan_command(draft_entries: bool, app_ids: &[String]) -> Result<(), String> {
+    let steam_path = get_steam_path()?;
+    if !steam_path.join("steamapps").is_dir() {
+        return Err(format!(
+            "No Steam installation found at {}",
+            steam_path.display()
+        ));
+    }
+    let apps: Vec<SteamApp> = scan_installed_apps(&steam_path)?
+        .into_iter()
+        .filter(|app| app_ids.is_empty() || app_ids.contains(&app.app_id))
+        .collect();
+
+    if draft_entries {
+        let entries: Vec<SyncEntry> = apps
+            .iter()
+            .map(|app| draft_sync_entry(app, &steam_path))
+            .collect();
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&entries)
+                .map_err(|e| format!("Unable to serialize draft entries\n{e}"))?
+        );
+        eprintln!(
+            "Drafts point at the whole Proton user (or install) folder - narrow saveFolderPath down to the save folder before adding them to syncEntries."
+        );
+        return Ok(());
+    }
+
+    println!("Steam libraries:");
+    for library in read_library_folders(&steam_path)? {
+        println!("\t{}", library.display());
+    }
+    println!("Installed apps:");
+    if apps.is_empty() {
+        println!("<no entries>");
+    }
+    for app in apps.iter() {
+        println!("* {} - {}", app.app_id, app.name);
+        println!("\tInstall folder: {}", app.install_dir.display());
+        match app.proton_prefix() {
+            Some(prefix) => println!("\tProton prefix:  {}", prefix.display()),
+            None => println!("\tProton prefix:  <none>"),
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    #[test]
+    fn drafts_use_placeholders_for_primary_library() {
+        let steam_path = PathBuf::from("/nonexistent/Steam");
+        let app = SteamApp {
+            app_id: String::from("620"),
+            name: String::from("Portal 2"),
+            library: steam_path.clone(),
+            install_dir: steam_path.join("steamapps/common/Portal 2"),
+            compat_data: Some(steam_path.join("steamapps/compatdata/620")),
+        };
+        let entry = draft_sync_entry(&app, &steam_path);
+        assert_eq!(entry.remote_sync_key, "portal-2");
+        assert_eq!(
+            entry.save_folder_path.all_paths(),
+            vec!["{{PROTON_PREFIX:620}}/drive_c/users/steamuser"]
+        );
+
+        let secondary = SteamApp {
+            library: PathBuf::from("/mnt/games"),
+            compat_data: None,
+            install_dir: PathBuf::from("/mnt/games/steamapps/common/Portal 2"),
+            ..app
+        };
+        let entry = draft_sync_entry(&secondary, &steam_path);
+        assert_eq!(
+            entry.save_folder_path.all_paths(),
+            vec!["/mnt/games/steamapps/common/Portal 2"]
+        );
+
+        let json = serde_json::to_string(&vec![entry]).unwrap();
+        assert!(!json.contains("null"));
+        let _: Vec<SyncEntry> = serde_json::from_str(&json).unwrap();
+    }
+}
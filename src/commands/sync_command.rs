@@ -1,3 +1,124 @@
-pub fn sync_command(_save_key: &str) -> Result<(), String> {
-    todo!()
+use crate::cancellation::CancellationToken;
+use crate::config::RuntimeSyncConfig;
+use crate::game_process::{
+    find_running_game_processes, format_running_processes, wait_for_game_to_close,
+};
+use crate::pending_sync::{clear_pending_sync, list_pending_syncs};
+use crate::progress::ProgressEvent;
+use std::path::Path;
+
+use super::check_sync_command::{CheckSyncResult, check_sync_command};
+use super::merge_command::merge_command_with_update_callback;
+use super::pull_command::pull_command_with_update_callback;
+use super::push_command::push_command_with_update_callback;
+
+/// Non-interactive bi-directional sync - performs whatever check-sync decides.
+/// Conflicts are never resolved automatically - they must be resolved through the UI.
+/// Pushes wait for the game to close first - pulls are refused while it runs.
+/// Returns the check-sync result that was acted upon.
+pub fn sync_command<F>(
+    sync_config: &RuntimeSyncConfig,
+    update_callback: F,
+    cancel: &CancellationToken,
+) -> Result<CheckSyncResult, String>
+where
+    F: Fn(ProgressEvent),
+{
+    let (mut check_sync_result, mut remote_head) = check_sync_command(sync_config)?;
+    if matches!(
+        check_sync_result,
+        CheckSyncResult::FastForwardRemote | CheckSyncResult::RemoteEmpty
+    ) && !find_running_game_processes(sync_config)?.is_empty()
+    {
+        wait_for_game_to_close(sync_config, cancel, |running| {
+            println!(
+                "Waiting for {} to close before pushing: {}",
+                sync_config.game_display_name,
+                format_running_processes(running)
+            )
+        })?;
+        // Saves most likely changed while waiting.
+        (check_sync_result, remote_head) = check_sync_command(sync_config)?;
+    }
+    let if_head = remote_head.as_ref().map(|head| head.hash.as_str());
+
+    match &check_sync_result {
+        CheckSyncResult::UpToDate => {
+            clear_pending_sync(&sync_config.local_head_folder, &sync_config.remote_sync_key)
+        }
+        CheckSyncResult::FastForwardLocal => {
+            pull_command_with_update_callback(sync_config, if_head, update_callback, cancel)
+        }
+        CheckSyncResult::FastForwardRemote | CheckSyncResult::RemoteEmpty => {
+            push_command_with_update_callback(sync_config, if_head, update_callback, cancel)
+        }
+        CheckSyncResult::AutoMerge { .. } => {
+            merge_command_with_update_callback(sync_config, if_head, update_callback, cancel)
+        }
+        CheckSyncResult::Conflict { .. } => Err(format!(
+            "Conflict found for {} - run `ui {}` to choose which version to keep",
+            sync_config.remote_sync_key, sync_config.remote_sync_key
+        )),
+    }?;
+    Ok(check_sync_result)
+}
+
+/// Syncs every key with a pending-sync marker - e.g. once connectivity is back.
+/// Keeps going when a key fails - those keep their marker and are reported at the end.
+pub fn sync_pending_command<L, F>(
+    local_head_folder: &Path,
+    load_config: L,
+    update_callback: F,
+    cancel: &CancellationToken,
+) -> Result<(), String>
+where
+    L: Fn(&str) -> Result<RuntimeSyncConfig, String>,
+    F: Fn(ProgressEvent),
+{
+    let pending = list_pending_syncs(local_head_folder)?;
+    if pending.is_empty() {
+        println!("No pending syncs");
+        return Ok(());
+    }
+
+    let mut failed_keys = vec![];
+    for marker in pending {
+        println!("Syncing pending {marker}");
+        let res = load_config(&marker.sync_key)
+            .and_then(|sync_config| sync_command(&sync_config, &update_callback, cancel));
+        cancel.check()?;
+        match res {
+            Ok(_) => println!("Synced {}", marker.sync_key),
+            Err(e) => {
+                eprintln!("Unable to sync {}\n{e}", marker.sync_key);
+                failed_keys.push(marker.sync_key);
+            }
+        }
+    }
+
+    if !failed_keys.is_empty() {
+        return Err(format!(
+            "Unable to sync pending keys: {}",
+            failed_keys.join(", ")
+        ));
+    }
+    Ok(())
+}
+
+/// Lists keys with local progress that never reached the remote.
+pub fn pending_command(local_head_folder: &Path) -> Result<(), String> {
+    let pending = list_pending_syncs(local_head_folder)?;
+    if pending.is_empty() {
+        println!("<no pending syncs>");
+        return Ok(());
+    }
+
+    for marker in pending.iter() {
+        println!("* {marker}");
+        if let Some(error) = &marker.error {
+            println!("    {}", error.lines().next().unwrap_or_default());
+        }
+    }
+    println!("Run `sync --pending` once the remote is reachable again.");
+    Ok(())
 }
@@ -1,12 +1,41 @@
+use crate::hashing::HashAlgorithm;
 use base64::{Engine as _, engine::general_purpose};
 use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
 use std::fmt;
 
-#[derive(Debug, Clone)]
+/// Current HEAD serialization format version.
+/// Only bump for changes older readers can't ignore - adding optional fields does not need it.
+/// v2: `hashAlgorithm` - v1 readers would compare hashes of different algorithms.
+pub const REVISION_FORMAT_VERSION: u32 = 2;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct Revision {
     pub hash: String,
     pub timestamp: u64,
     pub author: String,
+    /// Hash of the remote HEAD this revision replaced when pushed. None for the first revision (or unknown).
+    #[serde(default)]
+    pub parent: Option<String>,
+    /// Algorithm `hash` was computed with. Missing means md5 (written before algorithms were recorded).
+    #[serde(default)]
+    pub hash_algorithm: HashAlgorithm,
+}
+
+#[derive(Serialize)]
+struct VersionedRevisionRef<'a> {
+    v: u32,
+    #[serde(flatten)]
+    revision: &'a Revision,
+}
+
+#[derive(Deserialize)]
+struct VersionedRevision {
+    v: u32,
+    #[serde(flatten)]
+    revision: Revision,
 }
 
 impl PartialEq for Revision {
@@ -15,28 +44,67 @@ impl PartialEq for Revision {
     }
 }
 
+/// Formats a unix timestamp in local time - empty if out of range.
+pub fn format_unix_timestamp(timestamp: u64, format: &str) -> String {
+    let timestamp_as_int = i64::try_from(timestamp).unwrap_or_default();
+    DateTime::from_timestamp_secs(timestamp_as_int)
+        .map(|dt| dt.with_timezone(&Local).format(format).to_string())
+        .unwrap_or_default()
+}
+
 impl fmt::Display for Revision {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let timestamp_as_int = i64::try_from(self.timestamp).unwrap_or_default();
-        let utctime = DateTime::from_timestamp_secs(timestamp_as_int)
-            .map(|dt| {
-                dt.with_timezone(&Local)
-                    .format("%H:%M:%S %a %e %b %Y [%Z]")
-                    .to_string()
-            })
-            .unwrap_or_default();
-
+        let utctime = format_unix_timestamp(self.timestamp, "%H:%M:%S %a %e %b %Y [%Z]");
         write!(f, "{} ({}, by {})", utctime, self.hash, self.author)
     }
 }
 
 impl Revision {
+    /// Serializes as versioned JSON: `{"v":2,"hash":..,"timestamp":..,"author":..,"parent":..,"hashAlgorithm":..}`.
+    /// md5 HEADs are still written as v1 - so clients that predate algorithm tracking can keep reading them.
     pub fn serialize(&self) -> String {
+        let versioned = VersionedRevisionRef {
+            v: match self.hash_algorithm {
+                HashAlgorithm::Md5 => 1,
+                _ => REVISION_FORMAT_VERSION,
+            },
+            revision: self,
+        };
+        // Plain strings and numbers only - this can't fail.
+        serde_json::to_string(&versioned).unwrap_or_default()
+    }
+
+    /// Reads both the versioned JSON format and the legacy `hash,timestamp,base64(author)` format.
+    pub fn deserialize(s: &str) -> Result<Self, String> {
+        let s = s.trim();
+        if Revision::is_legacy_format(s) {
+            return Revision::deserialize_legacy(s);
+        }
+
+        let versioned: VersionedRevision =
+            serde_json::from_str(s).map_err(|e| format!("Invalid revision: {}", e))?;
+        if versioned.v > REVISION_FORMAT_VERSION {
+            return Err(format!(
+                "Revision was written with a newer format version (v{}) - please update this client.",
+                versioned.v
+            ));
+        }
+
+        Ok(versioned.revision)
+    }
+
+    /// Whether the given serialized revision uses the legacy (pre-versioned) CSV format.
+    pub fn is_legacy_format(s: &str) -> bool {
+        !s.trim_start().starts_with('{')
+    }
+
+    /// Legacy serialization format - kept to be able to read HEADs written by older clients.
+    pub fn serialize_legacy(&self) -> String {
         let author_encoded = general_purpose::STANDARD.encode(&self.author);
         format!("{},{},{}", self.hash, self.timestamp, author_encoded)
     }
 
-    pub fn deserialize(s: &str) -> Result<Self, String> {
+    pub fn deserialize_legacy(s: &str) -> Result<Self, String> {
         let mut parts = s.splitn(3, ',');
         let hash = parts.next().ok_or("Missing hash part")?.to_string();
         let timestamp_str = parts.next().ok_or("Missing timestamp part")?;
@@ -56,19 +124,62 @@ impl Revision {
             hash,
             timestamp,
             author,
+            parent: None,
+            hash_algorithm: HashAlgorithm::Md5,
         })
     }
 
+    /// Serializes as revision log line - same as HEAD as it already includes the parent.
+    pub fn serialize_log_entry(&self) -> String {
+        self.serialize()
+    }
+
+    /// Reads both versioned log lines and legacy `parent,hash,timestamp,base64(author)` lines.
+    pub fn deserialize_log_entry(s: &str) -> Result<Self, String> {
+        if !Revision::is_legacy_format(s) {
+            return Revision::deserialize(s);
+        }
+
+        let (parent, rev) = s.split_once(',').ok_or("Missing parent part")?;
+        let mut rev = Revision::deserialize_legacy(rev)?;
+        rev.parent = Some(parent.to_string()).filter(|p| !p.is_empty());
+        Ok(rev)
+    }
+
     pub fn time_display_str(&self) -> String {
-        let timestamp_as_int = i64::try_from(self.timestamp).unwrap_or_default();
-        DateTime::from_timestamp_secs(timestamp_as_int)
-            .map(|dt| {
-                dt.with_timezone(&Local)
-                    .format("%a %e %b %Y at %H:%M:%S")
-                    .to_string()
-            })
-            .unwrap_or_default()
+        format_unix_timestamp(self.timestamp, "%a %e %b %Y at %H:%M:%S")
+    }
+}
+
+/// Parses a revision log - one entry per line, oldest first.
+pub fn deserialize_revision_log(s: &str) -> Result<Vec<Revision>, String> {
+    s.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(Revision::deserialize_log_entry)
+        .collect()
+}
+
+/// Whether `ancestor_hash` is reachable by following parents from `descendant_hash` in the given revision log.
+pub fn is_ancestor(history: &[Revision], ancestor_hash: &str, descendant_hash: &str) -> bool {
+    let mut visited: HashSet<&str> = HashSet::new();
+    let mut current = descendant_hash;
+
+    // Hashes can repeat if a save returns to an older state - the latest entry is the one that matters.
+    while let Some(rev) = history.iter().rev().find(|rev| rev.hash == current) {
+        if !visited.insert(current) {
+            return false; // cycle
+        }
+        let Some(parent) = rev.parent.as_deref() else {
+            return false;
+        };
+        if parent == ancestor_hash {
+            return true;
+        }
+        current = parent;
     }
+
+    false
 }
 
 #[cfg(test)]
@@ -81,12 +192,124 @@ mod tests {
             hash: "abcdef".to_string(),
             timestamp: 12000,
             author: "Jane Doe".to_string(),
+            parent: None,
+            hash_algorithm: HashAlgorithm::Md5,
+        };
+        let serialized = rev.serialize();
+        let deserialized = Revision::deserialize(&serialized).unwrap();
+
+        assert_eq!(rev.hash, deserialized.hash);
+        assert_eq!(rev.timestamp, deserialized.timestamp);
+        assert_eq!(rev.author, deserialized.author);
+    }
+
+    #[test]
+    fn versioned_serialization_roundtrip() {
+        let rev = Revision {
+            hash: "abcdef".to_string(),
+            timestamp: 12000,
+            author: "Jane, \"Doe\"".to_string(),
+            parent: Some("012345".to_string()),
+            hash_algorithm: HashAlgorithm::Blake3,
         };
         let serialized = rev.serialize();
+        assert!(!Revision::is_legacy_format(&serialized));
+        assert!(serialized.contains("\"v\":2"));
+
         let deserialized = Revision::deserialize(&serialized).unwrap();
+        assert_eq!(rev.hash, deserialized.hash);
+        assert_eq!(rev.timestamp, deserialized.timestamp);
+        assert_eq!(rev.author, deserialized.author);
+        assert_eq!(rev.parent, deserialized.parent);
+        assert_eq!(rev.hash_algorithm, deserialized.hash_algorithm);
+
+        let md5_rev = Revision {
+            hash_algorithm: HashAlgorithm::Md5,
+            ..rev
+        };
+        assert!(md5_rev.serialize().contains("\"v\":1"));
+    }
+
+    #[test]
+    fn legacy_serialization_still_readable() {
+        let rev = Revision {
+            hash: "abcdef".to_string(),
+            timestamp: 12000,
+            author: "Jane Doe".to_string(),
+            parent: None,
+            hash_algorithm: HashAlgorithm::Md5,
+        };
+        let legacy = rev.serialize_legacy();
+        assert!(Revision::is_legacy_format(&legacy));
 
+        let deserialized = Revision::deserialize(&format!("{legacy}\n")).unwrap();
         assert_eq!(rev.hash, deserialized.hash);
         assert_eq!(rev.timestamp, deserialized.timestamp);
         assert_eq!(rev.author, deserialized.author);
+
+        let deserialized_entry =
+            Revision::deserialize_log_entry(&format!("012345,{legacy}")).unwrap();
+        assert_eq!(deserialized_entry.parent.as_deref(), Some("012345"));
+        assert_eq!(deserialized_entry.author, "Jane Doe");
+    }
+
+    #[test]
+    fn versioned_format_ignores_unknown_fields() {
+        let s = r#"{"v":1,"hash":"abcdef","timestamp":12000,"author":"Jane","fileCount":3}"#;
+        let deserialized = Revision::deserialize(s).unwrap();
+        assert_eq!(deserialized.hash, "abcdef");
+        assert_eq!(deserialized.parent, None);
+        assert_eq!(deserialized.hash_algorithm, HashAlgorithm::Md5);
+    }
+
+    #[test]
+    fn newer_format_version_is_rejected() {
+        let s = r#"{"v":999,"hash":"abcdef","timestamp":12000,"author":"Jane"}"#;
+        assert!(Revision::deserialize(s).is_err());
+    }
+
+    fn rev(hash: &str, parent: Option<&str>) -> Revision {
+        Revision {
+            hash: hash.to_string(),
+            timestamp: 12000,
+            author: "Jane Doe".to_string(),
+            parent: parent.map(str::to_string),
+            hash_algorithm: HashAlgorithm::Md5,
+        }
+    }
+
+    #[test]
+    fn log_entry_roundtrip() {
+        let entry = rev("abcdef", Some("012345"));
+        let deserialized = Revision::deserialize_log_entry(&entry.serialize_log_entry()).unwrap();
+        assert_eq!(entry.hash, deserialized.hash);
+        assert_eq!(entry.parent, deserialized.parent);
+        assert_eq!(entry.author, deserialized.author);
+
+        let root = rev("abcdef", None);
+        let deserialized = Revision::deserialize_log_entry(&root.serialize_log_entry()).unwrap();
+        assert_eq!(deserialized.parent, None);
+    }
+
+    #[test]
+    fn ancestry_walks_parents() {
+        let history = vec![rev("a", None), rev("b", Some("a")), rev("c", Some("b"))];
+        assert!(is_ancestor(&history, "a", "c"));
+        assert!(is_ancestor(&history, "b", "c"));
+        assert!(!is_ancestor(&history, "c", "a"));
+        assert!(!is_ancestor(&history, "x", "c"));
+    }
+
+    #[test]
+    fn ancestry_handles_repeated_hashes() {
+        // Save went back to an older state "a" - then moved on.
+        let history = vec![
+            rev("a", None),
+            rev("b", Some("a")),
+            rev("a", Some("b")),
+            rev("c", Some("a")),
+        ];
+        assert!(is_ancestor(&history, "b", "c"));
+        assert!(!is_ancestor(&history, "x", "c"));
     }
 }
@@ -1,8 +1,19 @@
 use crate::config::RuntimeSyncConfig;
-use crate::config::sync_options::SyncOptionsJson;
+use crate::config::config_format::ConfigFormat;
+use crate::config::entry_fragments::{
+    EntryFragment, get_entries_dir, merge_entry_fragments, read_entry_fragments,
+    write_entry_fragment,
+};
+use crate::config::shared_entries::{
+    get_shared_entries_path, merge_shared_entries, read_shared_entries,
+};
+use crate::config::sync_options::{
+    SyncEntry, SyncOptionsJson, ValidatedSyncOptions, is_valid_remote_key,
+};
 use crate::utils::get_steam_common;
 use crate::utils::get_steam_compatdata;
 
+use std::collections::BTreeMap;
 use std::env;
 use std::fs;
 use std::path::Path;
@@ -12,20 +23,59 @@ pub const REMOTE_SNAPSHOT_FOLDER_NAME: &str = "Snapshots";
 pub const REMOTE_SAVES_FOLDER_NAME: &str = "GameSaves";
 pub const REMOTE_HEAD_FOLDER_NAME: &str = ".cloudmeta";
 pub const DATA_DIR_NAME: &str = "local_cloud_game_sync";
+/// Archive storage mode - files inside `GameSaves/<key>`.
+pub const REMOTE_ARCHIVE_FILE_NAME: &str = "saves.tar.zst";
+pub const REMOTE_ARCHIVE_MANIFEST_FILE_NAME: &str = "manifest.json";
+/// Encryption key derivation parameters - inside the remote HEAD folder.
+pub const REMOTE_KEY_PARAMS_FILE_NAME: &str = "encryption.json";
+/// Sync entries shared by every device - inside the remote HEAD folder.
+pub const REMOTE_SHARED_ENTRIES_FILE_NAME: &str = "entries.json";
+/// Encryption key - inside the local head folder.
+pub const LOCAL_ENCRYPTION_KEY_FILE_NAME: &str = "encryption.key";
 
 // defaults
 pub const DEFAULT_SSH_PORT: u32 = 22;
-pub const DEFAULT_SYNC_CONFIG_NAME: &str = "sync_config.json";
+pub const DEFAULT_CONNECT_TIMEOUT_SECS: u64 = 10;
+pub const DEFAULT_OPERATION_TIMEOUT_SECS: u64 = 120;
+pub const DEFAULT_REMOTE_RETRIES: u32 = 2;
+pub const DEFAULT_DAEMON_QUIET_SECS: u64 = 30;
+pub const DEFAULT_DAEMON_POLL_SECS: u64 = 300;
+/// Without extension - `sync_config.json`, `sync_config.jsonc` or `sync_config.toml` is used, whichever exists.
+pub const DEFAULT_SYNC_CONFIG_NAME: &str = "sync_config";
 pub const DEFAULT_HEAD_FOLDER_NAME: &str = "uploaded";
+pub const DEFAULT_SAVE_DATABASE_NAME: &str = "save_locations.yaml";
 
-pub fn default_sync_config_path() -> Result<PathBuf, String> {
+pub fn default_sync_config_path_for(format: ConfigFormat) -> Result<PathBuf, String> {
     let base_dir = dirs::data_dir().ok_or("Could not determine data directory")?;
     let configs_path = PathBuf::from(base_dir)
         .join(DATA_DIR_NAME)
-        .join(DEFAULT_SYNC_CONFIG_NAME);
+        .join(format!("{DEFAULT_SYNC_CONFIG_NAME}.{}", format.extension()));
     Ok(configs_path)
 }
 
+/// The default config file in use - `sync_config.json` if none exists yet. Errors if several formats exist.
+pub fn default_sync_config_path() -> Result<PathBuf, String> {
+    let mut existing = vec![];
+    for format in ConfigFormat::ALL {
+        let config_path = default_sync_config_path_for(format)?;
+        if config_path.exists() {
+            existing.push(config_path);
+        }
+    }
+    match existing.len() {
+        0 => default_sync_config_path_for(ConfigFormat::Json),
+        1 => Ok(existing.remove(0)),
+        _ => Err(format!(
+            "Found several config files - keep only one of:\n{}",
+            existing
+                .iter()
+                .map(|path| path.display().to_string())
+                .collect::<Vec<String>>()
+                .join("\n")
+        )),
+    }
+}
+
 pub fn default_local_head_folder_path() -> Result<PathBuf, String> {
     let base_dir = dirs::data_dir().ok_or("Could not determine data directory")?;
     let head_folder_path = PathBuf::from(base_dir)
@@ -34,54 +84,128 @@ pub fn default_local_head_folder_path() -> Result<PathBuf, String> {
     Ok(head_folder_path)
 }
 
-pub fn expand_config_placeholders(input: &str) -> String {
-    let mut result = input.to_string();
-
-    let placeholders = [
-        (
-            "{{HOME}}",
-            dirs::home_dir()
-                .unwrap_or_default()
-                .to_string_lossy()
-                .to_string(),
-        ),
-        ("{{APPDATA}}", env::var("APPDATA").unwrap_or_default()),
-        (
-            "{{LOCALAPPDATA}}",
-            env::var("LOCALAPPDATA").unwrap_or_default(),
-        ),
-        (
-            "{{STEAM_COMMON}}",
-            get_steam_common()
-                .map(|p| p.to_string_lossy().to_string())
-                .unwrap_or_default(),
-        ),
-        (
-            "{{STEAM_COMPATDATA}}",
-            get_steam_compatdata()
-                .map(|p| p.to_string_lossy().to_string())
-                .unwrap_or_default(),
-        ),
-    ];
-
-    for (placeholder, value) in placeholders.iter() {
-        result = result.replace(placeholder, value);
+/// Local save-location database used by `discover`.
+pub fn default_save_database_path() -> Result<PathBuf, String> {
+    let base_dir = dirs::data_dir().ok_or("Could not determine data directory")?;
+    let database_path = PathBuf::from(base_dir)
+        .join(DATA_DIR_NAME)
+        .join(DEFAULT_SAVE_DATABASE_NAME);
+    Ok(database_path)
+}
+
+/// Expands every `{{NAME}}` placeholder - the global config `variables` first, then the built-in ones.
+/// Errors naming the first placeholder without a value on this machine - a missing one must not silently expand to "".
+pub fn expand_config_placeholders(
+    input: &str,
+    variables: &BTreeMap<String, String>,
+) -> Result<String, String> {
+    let mut result = String::new();
+    let mut rest = input;
+    while let Some(start) = rest.find("{{") {
+        let Some(length) = rest[start..].find("}}") else {
+            return Err(format!("Unterminated placeholder in {input}"));
+        };
+        let name = &rest[start + 2..start + length];
+        let value = match variables.get(name) {
+            // Variables may use the built-in placeholders - but not each other.
+            Some(value) => expand_config_placeholders(value, &BTreeMap::new())
+                .map_err(|e| format!("{e} - used by variable {name}"))?,
+            None => resolve_placeholder(name)?,
+        };
+
+        result.push_str(&rest[..start]);
+        result.push_str(&value);
+        rest = &rest[start + length + 2..];
+    }
+    result.push_str(rest);
+    Ok(result)
+}
+
+/// Built-in placeholders - `{{ENV:<name>}}`, folders of this machine and the per-app Proton ones.
+fn resolve_placeholder(name: &str) -> Result<String, String> {
+    if let Some(env_name) = name.strip_prefix("ENV:") {
+        return env::var(env_name)
+            .ok()
+            .filter(|value| !value.is_empty())
+            .ok_or(format!(
+                "Placeholder {{{{{name}}}}} - environment variable {env_name} is not set"
+            ));
+    }
+
+    let resolved = match name {
+        "HOME" => dirs::home_dir(),
+        "DOCUMENTS" => dirs::document_dir(),
+        // dirs follows the XDG variables on Linux - and returns the platform equivalent elsewhere.
+        "XDG_DATA_HOME" => dirs::data_dir(),
+        "XDG_CONFIG_HOME" => dirs::config_dir(),
+        "APPDATA" => env::var("APPDATA").ok().map(PathBuf::from),
+        "LOCALAPPDATA" => env::var("LOCALAPPDATA").ok().map(PathBuf::from),
+        "STEAM_COMMON" => get_steam_common().ok(),
+        "STEAM_COMPATDATA" => get_steam_compatdata().ok(),
+        _ => match name.split_once(':') {
+            Some((proton_name, app_id)) if proton_name.starts_with("PROTON_") => {
+                resolve_proton_placeholder(proton_name, app_id)
+            }
+            _ => {
+                return Err(format!(
+                    "Unknown placeholder {{{{{name}}}}} - define it in the variables of the global config"
+                ));
+            }
+        },
+    };
+    resolved
+        .map(|path| path.to_string_lossy().to_string())
+        .filter(|value| !value.is_empty())
+        .ok_or(format!(
+            "Placeholder {{{{{name}}}}} has no value on this machine - define it in the variables of the global config"
+        ))
+}
+
+/// Folder of a `{{PROTON_*:<appid>}}` placeholder on Linux - inside the app's Proton prefix, searched across every Steam library.
+#[cfg(not(target_os = "windows"))]
+fn resolve_proton_placeholder(name: &str, app_id: &str) -> Option<PathBuf> {
+    use crate::steam::library::{PROTON_PREFIX_USER_FOLDER, find_app_compat_data};
+    use crate::utils::get_steam_path;
+
+    let steam_path = get_steam_path().ok()?;
+    // Unknown prefixes resolve into the main library - so validation reports a missing folder.
+    let prefix = find_app_compat_data(&steam_path, app_id)
+        .unwrap_or(get_steam_compatdata().ok()?.join(app_id))
+        .join("pfx");
+    let user_folder = prefix.join(PROTON_PREFIX_USER_FOLDER);
+    match name {
+        "PROTON_PREFIX" => Some(prefix),
+        "PROTON_DOCUMENTS" => Some(user_folder.join("Documents")),
+        "PROTON_APPDATA" => Some(user_folder.join("AppData").join("Roaming")),
+        "PROTON_LOCALAPPDATA" => Some(user_folder.join("AppData").join("Local")),
+        _ => None,
     }
+}
 
-    result
+/// Folder of a `{{PROTON_*:<appid>}}` placeholder on Windows - the regular user folders, so entries work unchanged.
+/// There is no prefix on Windows - so `{{PROTON_PREFIX:<appid>}}` has no value.
+#[cfg(target_os = "windows")]
+fn resolve_proton_placeholder(name: &str, _app_id: &str) -> Option<PathBuf> {
+    match name {
+        "PROTON_DOCUMENTS" => dirs::document_dir(),
+        "PROTON_APPDATA" => env::var("APPDATA").ok().map(PathBuf::from),
+        "PROTON_LOCALAPPDATA" => env::var("LOCALAPPDATA").ok().map(PathBuf::from),
+        _ => None,
+    }
 }
 
-pub fn init_default_config() -> Result<PathBuf, String> {
-    let sync_config_path = default_sync_config_path()?;
+pub fn init_default_config(format: ConfigFormat) -> Result<PathBuf, String> {
+    let existing_config_path = default_sync_config_path()?;
+    let sync_config_path = default_sync_config_path_for(format)?;
     let head_folder_path = default_local_head_folder_path()?;
     let data_path = sync_config_path
         .parent()
         .ok_or("Internal error determining config path")?;
 
-    if sync_config_path.exists() {
+    if existing_config_path.exists() {
         return Err(format!(
             "Config already exists at {}. Delete file first if you want to re-create",
-            sync_config_path.display()
+            existing_config_path.display()
         ));
     }
 
@@ -99,16 +223,35 @@ pub fn init_default_config() -> Result<PathBuf, String> {
         ssh_port: Some(22),
         remote_sync_root: String::from(""),
         local_head_folder: None,
+        hash_algorithm: None,
+        transfer_compression: None,
+        encrypt: None,
+        connect_timeout_secs: None,
+        operation_timeout_secs: None,
+        remote_retries: None,
+        daemon_quiet_secs: None,
+        daemon_poll_secs: None,
+        variables: BTreeMap::new(),
         sync_entries: vec![],
     };
-    fs::write(
-        &sync_config_path,
-        serde_json::to_string_pretty(&default_global_options)
+    let content = match format.commented_template() {
+        Some(template) => template.to_string(),
+        None => serde_json::to_string_pretty(&default_global_options)
             .map_err(|e| format!("Error initializing\n{}", e))?,
-    )
-    .map_err(|e| format!("Unable to initialize sync config \n{e}"))?;
+    };
+    fs::write(&sync_config_path, content)
+        .map_err(|e| format!("Unable to initialize sync config \n{e}"))?;
 
-    // 2. Ensure that head folder is also created.
+    // 2. Per-game entries go into entries.d - see `config add`.
+    let entries_dir = get_entries_dir(&sync_config_path);
+    fs::create_dir_all(&entries_dir).map_err(|e| {
+        format!(
+            "Could not create entries directory {:?}: {}",
+            entries_dir, e
+        )
+    })?;
+
+    // 3. Ensure that head folder is also created.
     fs::create_dir_all(&head_folder_path).map_err(|e| {
         format!(
             "Could not create HEAD folder directory {:?}: {}",
@@ -116,35 +259,219 @@ pub fn init_default_config() -> Result<PathBuf, String> {
         )
     })?;
 
-    Ok(sync_config_path)
+    Ok(entries_dir)
+}
+
+/// Loads and validates only the global options - for commands not bound to a sync key.
+pub fn load_global_options(
+    config_file_override: Option<&Path>,
+) -> Result<ValidatedSyncOptions, String> {
+    read_config_file(config_file_override)?.validate()
 }
 
 pub fn load_config(
     sync_key: &str,
     config_file_override: Option<&Path>,
 ) -> Result<RuntimeSyncConfig, String> {
-    // 1. Get config path + Ensure file exists
-    let default_config_path = default_sync_config_path()?;
-    let config_file = config_file_override.unwrap_or(&default_config_path);
+    let parsed_options = read_config_file(config_file_override)?;
 
-    if !config_file.exists() {
-        if config_file == default_config_path {
+    // Validate file and load it.
+    let config = RuntimeSyncConfig::validate(parsed_options, sync_key)?;
+    Ok(config)
+}
+
+/// Path of the config file in use - errors if it does not exist.
+pub fn resolve_config_file(config_file_override: Option<&Path>) -> Result<PathBuf, String> {
+    let Some(config_file) = config_file_override else {
+        let default_config_path = default_sync_config_path()?;
+        if !default_config_path.exists() {
             return Err(format!(
                 "Sync config file does not exist! Have you executed init-config ?"
             ));
         }
+        return Ok(default_config_path);
+    };
+
+    if !config_file.exists() {
         return Err(format!(
             "Provided sync config file {} does not exist!",
             config_file.display()
         ));
     }
+    Ok(config_file.to_path_buf())
+}
+
+/// Everything sync entries are read from - before merging. `config check` reports problems per source with it.
+pub struct ConfigSources {
+    pub config_file: PathBuf,
+    /// The config file itself - `sync_entries` holds only its own entries.
+    pub options: SyncOptionsJson,
+    pub fragments: Vec<EntryFragment>,
+    pub shared_entries: Vec<SyncEntry>,
+}
+
+pub fn read_config_sources(config_file_override: Option<&Path>) -> Result<ConfigSources, String> {
+    // 1. Get config path + Ensure file exists
+    let config_file = resolve_config_file(config_file_override)?;
+
+    // 2. Parse file - JSON, JSONC or TOML depending on its extension
+    let content = fs::read_to_string(&config_file)
+        .map_err(|e| format!("Error reading config file: {}", e))?;
+    let parsed_options = ConfigFormat::from_path(&config_file).parse_sync_options(&content)?;
+
+    // 3. Per-game fragments from entries.d and entries shared through the remote
+    Ok(ConfigSources {
+        fragments: read_entry_fragments(&get_entries_dir(&config_file))?,
+        shared_entries: read_shared_entries(&get_shared_entries_path(&config_file))?,
+        options: parsed_options,
+        config_file: config_file,
+    })
+}
+
+fn read_config_file(config_file_override: Option<&Path>) -> Result<SyncOptionsJson, String> {
+    let sources = read_config_sources(config_file_override)?;
+    let mut parsed_options = sources.options;
+
+    // Fragments must not redefine keys - shared entries are overridden by local ones.
+    merge_entry_fragments(
+        &sources.config_file,
+        &mut parsed_options.sync_entries,
+        sources.fragments,
+    )?;
+    parsed_options.sync_entries =
+        merge_shared_entries(sources.shared_entries, parsed_options.sync_entries);
+
+    Ok(parsed_options)
+}
+
+/// Adds each entry as its own fragment in `entries.d` - the config file itself is left untouched.
+/// Fails before writing anything if a key is invalid or already configured. Returns the entries folder.
+pub fn add_sync_entries_to_config(
+    config_file_override: Option<&Path>,
+    entries: &[SyncEntry],
+) -> Result<PathBuf, String> {
+    let config_file = resolve_config_file(config_file_override)?;
+    let existing = read_config_file(config_file_override)?.sync_entries;
+
+    for (i, entry) in entries.iter().enumerate() {
+        let duplicate = existing
+            .iter()
+            .chain(&entries[..i])
+            .any(|other| other.remote_sync_key == entry.remote_sync_key);
+        if duplicate {
+            return Err(format!(
+                "A sync entry with key {} already exists",
+                entry.remote_sync_key
+            ));
+        }
+        if !is_valid_remote_key(&entry.remote_sync_key) {
+            return Err(format!(
+                "Invalid remoteSyncKey \"{}\" - must only contains [A-Za-z0-9_-]",
+                entry.remote_sync_key
+            ));
+        }
+    }
 
-    // 2. Parse file
-    let bytes = fs::read(config_file).map_err(|e| format!("Error reading config file: {}", e))?;
-    let parsed_options: SyncOptionsJson =
-        serde_json::from_slice(&bytes).map_err(|e| format!("Error parsing config file\n{}", e))?;
+    let entries_dir = get_entries_dir(&config_file);
+    for entry in entries {
+        write_entry_fragment(&entries_dir, entry)?;
+    }
+    Ok(entries_dir)
+}
 
-    // 3. Validate file and load it.
-    let config = RuntimeSyncConfig::validate(parsed_options, sync_key)?;
-    Ok(config)
+/// Deletes the `entries.d` fragment defining the key. Entries inside the config file itself must be removed by hand.
+pub fn remove_sync_entry_from_config(
+    config_file_override: Option<&Path>,
+    sync_key: &str,
+) -> Result<PathBuf, String> {
+    let config_file = resolve_config_file(config_file_override)?;
+    let fragments = read_entry_fragments(&get_entries_dir(&config_file))?;
+    if let Some(fragment) = fragments
+        .iter()
+        .find(|fragment| fragment.entry.remote_sync_key == sync_key)
+    {
+        fs::remove_file(&fragment.file)
+            .map_err(|e| format!("Unable to delete {}\n{e}", fragment.file.display()))?;
+        return Ok(fragment.file.clone());
+    }
+
+    let in_config_file = read_config_file(config_file_override)?
+        .sync_entries
+        .iter()
+        .any(|entry| entry.remote_sync_key == sync_key);
+    match in_config_file {
+        true => Err(format!(
+            "{sync_key} is defined in {} - remove it there by hand",
+            config_file.display()
+        )),
+        false => Err(format!("No sync entry with key {sync_key}")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn expands_variables_and_env_placeholders() {
+        let variables = BTreeMap::from([
+            (String::from("GAMES"), String::from("/mnt/games")),
+            (String::from("HOME"), String::from("/custom/home")),
+            (String::from("NESTED"), String::from("{{GAMES}}/a")),
+        ]);
+        assert_eq!(
+            expand_config_placeholders("{{GAMES}}/Saves/{{HOME}}", &variables),
+            Ok(String::from("/mnt/games/Saves//custom/home"))
+        );
+        assert_eq!(
+            expand_config_placeholders("{{ENV:PATH}}", &BTreeMap::new()),
+            Ok(env::var("PATH").unwrap())
+        );
+
+        let missing = [
+            (
+                "{{ENV:LCGSYNC_SURELY_UNSET}}/Saves",
+                "LCGSYNC_SURELY_UNSET is not set",
+            ),
+            ("{{MISSING}}/Saves", "Unknown placeholder {{MISSING}}"),
+            ("{{NESTED}}", "used by variable NESTED"),
+            (
+                "{{PROTON_APPDATA}}/Saves",
+                "Unknown placeholder {{PROTON_APPDATA}}",
+            ),
+            ("/a/{{HOME", "Unterminated placeholder"),
+        ];
+        for (input, expected_error) in missing {
+            let error = expand_config_placeholders(input, &variables).err().unwrap();
+            assert!(error.contains(expected_error), "{error}");
+        }
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn expands_proton_placeholders_into_prefix() {
+        let expanded =
+            expand_config_placeholders("{{PROTON_APPDATA:620}}/Saves", &BTreeMap::new()).unwrap();
+        assert!(expanded.ends_with("/620/pfx/drive_c/users/steamuser/AppData/Roaming/Saves"));
+        assert!(
+            expand_config_placeholders("{{APPDATA}}/Saves", &BTreeMap::new())
+                .err()
+                .unwrap()
+                .contains("{{APPDATA}} has no value on this machine")
+        );
+    }
+
+    #[test]
+    fn config_file_override_skips_default_lookup() {
+        let existing = std::env::temp_dir();
+        assert_eq!(resolve_config_file(Some(&existing)), Ok(existing));
+
+        let missing = std::env::temp_dir().join("missing_sync_config.json");
+        assert!(
+            resolve_config_file(Some(&missing))
+                .err()
+                .unwrap()
+                .contains("Provided sync config file")
+        );
+    }
 }
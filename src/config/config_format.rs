@@ -0,0 +1,298 @@
+use crate::config::sync_options::SyncOptionsJson;
+use std::path::Path;
+use std::str::FromStr;
+
+/// Formats the global config can be written in - picked by the file extension.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfigFormat {
+    /// Plain JSON - comments are rejected.
+    Json,
+    /// JSON with `//` and `/* */` comments and trailing commas.
+    Jsonc,
+    Toml,
+}
+
+impl ConfigFormat {
+    /// Order in which the default config file is looked up.
+    pub const ALL: [ConfigFormat; 3] =
+        [ConfigFormat::Json, ConfigFormat::Jsonc, ConfigFormat::Toml];
+
+    /// Anything but `.jsonc` and `.toml` is read as plain JSON.
+    pub fn from_path(path: &Path) -> ConfigFormat {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("jsonc") => ConfigFormat::Jsonc,
+            Some("toml") => ConfigFormat::Toml,
+            _ => ConfigFormat::Json,
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ConfigFormat::Json => "json",
+            ConfigFormat::Jsonc => "jsonc",
+            ConfigFormat::Toml => "toml",
+        }
+    }
+
+    pub fn parse_sync_options(&self, content: &str) -> Result<SyncOptionsJson, String> {
+        let parsed = match self {
+            ConfigFormat::Json => serde_json::from_str(content).map_err(|e| e.to_string()),
+            ConfigFormat::Jsonc => {
+                serde_json::from_str(&strip_json_comments(content)).map_err(|e| e.to_string())
+            }
+            ConfigFormat::Toml => toml::from_str(content).map_err(|e| e.to_string()),
+        };
+        parsed.map_err(|e| format!("Error parsing config file\n{}", e))
+    }
+
+    /// Commented starting config written by `init-config` - None for plain JSON, which can't hold comments.
+    pub fn commented_template(&self) -> Option<&'static str> {
+        match self {
+            ConfigFormat::Json => None,
+            ConfigFormat::Jsonc => Some(JSONC_TEMPLATE),
+            ConfigFormat::Toml => Some(TOML_TEMPLATE),
+        }
+    }
+}
+
+impl FromStr for ConfigFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        ConfigFormat::ALL
+            .into_iter()
+            .find(|format| format.extension() == s)
+            .ok_or(format!(
+                "Unknown config format {s} - use json, jsonc or toml"
+            ))
+    }
+}
+
+/// Blanks out comments and trailing commas so serde_json accepts JSONC.
+/// Everything is replaced by spaces in place - so parse errors still point at the right line.
+pub fn strip_json_comments(input: &str) -> String {
+    let chars: Vec<char> = input.chars().collect();
+    let mut output = String::with_capacity(input.len());
+    let mut in_string = false;
+    // Byte offset in `output` of a comma not followed by any value yet.
+    let mut pending_comma: Option<usize> = None;
+
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        let next = chars.get(i + 1).copied();
+        if in_string {
+            output.push(c);
+            if c == '\\' && next.is_some() {
+                output.push(chars[i + 1]);
+                i += 2;
+                continue;
+            }
+            in_string = c != '"';
+            i += 1;
+            continue;
+        }
+
+        match (c, next) {
+            ('/', Some('/')) => {
+                while i < chars.len() && chars[i] != '\n' {
+                    output.push(' ');
+                    i += 1;
+                }
+            }
+            ('/', Some('*')) => {
+                output.push_str("  ");
+                i += 2;
+                while i < chars.len() && !(chars[i] == '*' && chars.get(i + 1) == Some(&'/')) {
+                    output.push(if chars[i] == '\n' { '\n' } else { ' ' });
+                    i += 1;
+                }
+                if i < chars.len() {
+                    output.push_str("  ");
+                    i += 2;
+                }
+            }
+            _ => {
+                match c {
+                    '}' | ']' => {
+                        if let Some(comma) = pending_comma.take() {
+                            output.replace_range(comma..comma + 1, " ");
+                        }
+                    }
+                    ',' => pending_comma = Some(output.len()),
+                    '"' => {
+                        in_string = true;
+                        pending_comma = None;
+                    }
+                    c if c.is_whitespace() => {}
+                    _ => pending_comma = None,
+                }
+                output.push(c);
+                i += 1;
+            }
+        }
+    }
+    output
+}
+
+const JSONC_TEMPLATE: &str = r#"// Global config - comments and trailing commas are allowed in this file.
+{
+  // Name of this device - recorded in the sync history.
+  "clientName": "",
+  // Host (or ~/.ssh/config alias) storing the saves.
+  "sshHost": "",
+  "sshPort": 22,
+  // Folder on the remote holding every save.
+  "remoteSyncRoot": "",
+
+  // Optional - defaults shown.
+  // "localHeadFolder": "<data folder>/local_cloud_game_sync/uploaded",
+  // "hashAlgorithm": "blake3", // keep "md5" while older clients sync the same remote
+  // "transferCompression": "none", // "zstd" requires zstd on the remote
+  // "encrypt": false, // see `keys init`
+  // "connectTimeoutSecs": 10,
+  // "operationTimeoutSecs": 120,
+  // "remoteRetries": 2,
+  // "daemonQuietSecs": 30,
+  // "daemonPollSecs": 300,
+
+  // Custom {{NAME}} placeholders for paths on this machine - e.g. a second drive.
+  // "variables": { "GAMES": "/mnt/games" },
+
+  // Games - or one file per game in entries.d, see `config add`.
+  "syncEntries": [
+    // {
+    //   "remoteSyncKey": "my-game",
+    //   "saveFolderPath": "{{HOME}}/.local/share/MyGame",
+    //   "saveIgnoreGlob": ["**/*.log"], // note why a pattern is ignored right next to it
+    // },
+  ],
+}
+"#;
+
+const TOML_TEMPLATE: &str = r#"# Global config.
+
+# Name of this device - recorded in the sync history.
+clientName = ""
+# Host (or ~/.ssh/config alias) storing the saves.
+sshHost = ""
+sshPort = 22
+# Folder on the remote holding every save.
+remoteSyncRoot = ""
+
+# Optional - defaults shown.
+# localHeadFolder = "<data folder>/local_cloud_game_sync/uploaded"
+# hashAlgorithm = "blake3" # keep "md5" while older clients sync the same remote
+# transferCompression = "none" # "zstd" requires zstd on the remote
+# encrypt = false # see `keys init`
+# connectTimeoutSecs = 10
+# operationTimeoutSecs = 120
+# remoteRetries = 2
+# daemonQuietSecs = 30
+# daemonPollSecs = 300
+
+# Custom {{NAME}} placeholders for paths on this machine - e.g. a second drive.
+# [variables]
+# GAMES = "/mnt/games"
+
+# Games - one [[syncEntries]] table each, or one file per game in entries.d, see `config add`.
+# [[syncEntries]]
+# remoteSyncKey = "my-game"
+# saveFolderPath = "{{HOME}}/.local/share/MyGame"
+# saveIgnoreGlob = ["**/*.log"] # note why a pattern is ignored right next to it
+"#;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn strips_comments_and_trailing_commas_outside_strings() {
+        let parsed = ConfigFormat::Jsonc
+            .parse_sync_options(
+                r#"{
+                    // The laptop
+                    "clientName": "laptop /* not a comment */",
+                    "sshHost": "host", /* inline */ "remoteSyncRoot": "/saves",
+                    "syncEntries": [
+                        {
+                            "remoteSyncKey": "a-game",
+                            "saveFolderPath": "C://Saves\\\"a",
+                            // crash dumps are huge
+                            "saveIgnoreGlob": ["*.dmp",],
+                        },
+                    ],
+                }"#,
+            )
+            .unwrap();
+        assert_eq!(parsed.client_name, "laptop /* not a comment */");
+        assert_eq!(parsed.remote_sync_root, "/saves");
+        assert_eq!(
+            parsed.sync_entries[0].save_folder_path.all_paths(),
+            vec!["C://Saves\\\"a"]
+        );
+        assert_eq!(parsed.sync_entries[0].save_ignore_glob, vec!["*.dmp"]);
+
+        // Plain JSON stays strict.
+        assert!(
+            ConfigFormat::Json
+                .parse_sync_options("{\"clientName\": \"a\", // no\n}")
+                .is_err()
+        );
+        // Line numbers survive stripping.
+        let error = ConfigFormat::Jsonc
+            .parse_sync_options("{\n/* a\nb */\n\"clientName\": 1\n}")
+            .err()
+            .unwrap();
+        assert!(error.contains("line 4"), "{error}");
+    }
+
+    #[test]
+    fn parses_toml_and_templates() {
+        let parsed = ConfigFormat::Toml
+            .parse_sync_options(
+                r#"
+                clientName = "deck"
+                sshHost = "host"
+                remoteSyncRoot = "/saves"
+                transferCompression = "zstd"
+
+                [[syncEntries]]
+                remoteSyncKey = "a-game"
+                saveFolderPath = { linux = ["/a", "/b"] }
+                saveIgnoreGlob = []
+                "#,
+            )
+            .unwrap();
+        assert_eq!(parsed.client_name, "deck");
+        assert_eq!(
+            parsed.sync_entries[0].save_folder_path.all_paths(),
+            vec!["/a", "/b"]
+        );
+
+        for format in ConfigFormat::ALL {
+            if let Some(template) = format.commented_template() {
+                let parsed = format.parse_sync_options(template).unwrap();
+                assert_eq!(parsed.ssh_port, Some(22));
+                assert!(parsed.sync_entries.is_empty());
+            }
+        }
+    }
+
+    #[test]
+    fn detects_format_by_extension() {
+        assert_eq!(
+            ConfigFormat::from_path(Path::new("a/sync_config.toml")),
+            ConfigFormat::Toml
+        );
+        assert_eq!(
+            ConfigFormat::from_path(Path::new("sync_config.jsonc")),
+            ConfigFormat::Jsonc
+        );
+        assert_eq!(
+            ConfigFormat::from_path(Path::new("my_config")),
+            ConfigFormat::Json
+        );
+        assert_eq!("toml".parse(), Ok(ConfigFormat::Toml));
+    }
+}
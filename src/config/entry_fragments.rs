@@ -0,0 +1,145 @@
+use crate::config::sync_options::SyncEntry;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// Folder next to the config file holding one `<key>.json` sync entry per game.
+pub const ENTRIES_DIR_NAME: &str = "entries.d";
+
+pub fn get_entries_dir(config_file: &Path) -> PathBuf {
+    config_file
+        .parent()
+        .unwrap_or(Path::new(""))
+        .join(ENTRIES_DIR_NAME)
+}
+
+/// A sync entry read from `entries.d`.
+pub struct EntryFragment {
+    pub file: PathBuf,
+    pub entry: SyncEntry,
+}
+
+/// Every `*.json` fragment in the entries folder - sorted by file name. A missing folder has no fragments.
+pub fn read_entry_fragments(entries_dir: &Path) -> Result<Vec<EntryFragment>, String> {
+    if !entries_dir.is_dir() {
+        return Ok(vec![]);
+    }
+    let mut files: Vec<PathBuf> = fs::read_dir(entries_dir)
+        .map_err(|e| format!("Unable to read {}\n{e}", entries_dir.display()))?
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.is_file() && path.extension().is_some_and(|ext| ext == "json"))
+        .collect();
+    files.sort();
+
+    let mut fragments = vec![];
+    for file in files {
+        let bytes =
+            fs::read(&file).map_err(|e| format!("Unable to read {}\n{e}", file.display()))?;
+        let entry: SyncEntry = serde_json::from_slice(&bytes)
+            .map_err(|e| format!("Error parsing sync entry {}\n{e}", file.display()))?;
+        fragments.push(EntryFragment {
+            file: file,
+            entry: entry,
+        });
+    }
+    Ok(fragments)
+}
+
+/// Appends the fragments to `entries` - failing on keys defined twice. `config_file` is only used for the error message.
+pub fn merge_entry_fragments(
+    config_file: &Path,
+    entries: &mut Vec<SyncEntry>,
+    fragments: Vec<EntryFragment>,
+) -> Result<(), String> {
+    let mut sources: Vec<PathBuf> = entries.iter().map(|_| config_file.to_path_buf()).collect();
+    for fragment in fragments {
+        if let Some(index) = entries
+            .iter()
+            .position(|entry| entry.remote_sync_key == fragment.entry.remote_sync_key)
+        {
+            return Err(format!(
+                "Duplicate remoteSyncKey \"{}\" - defined in both {} and {}",
+                fragment.entry.remote_sync_key,
+                sources[index].display(),
+                fragment.file.display()
+            ));
+        }
+        entries.push(fragment.entry);
+        sources.push(fragment.file);
+    }
+    Ok(())
+}
+
+/// Writes the entry as `<key>.json` - never overwriting an existing file.
+pub fn write_entry_fragment(entries_dir: &Path, entry: &SyncEntry) -> Result<PathBuf, String> {
+    fs::create_dir_all(entries_dir)
+        .map_err(|e| format!("Unable to create {}\n{e}", entries_dir.display()))?;
+    let file_path = entries_dir.join(format!("{}.json", entry.remote_sync_key));
+    let content = serde_json::to_string_pretty(entry)
+        .map_err(|e| format!("Unable to serialize sync entry\n{e}"))?;
+
+    let mut file = fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(&file_path)
+        .map_err(|e| format!("Unable to create {}\n{e}", file_path.display()))?;
+    file.write_all(content.as_bytes())
+        .map_err(|e| format!("Unable to write {}\n{e}", file_path.display()))?;
+    Ok(file_path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(key: &str) -> SyncEntry {
+        SyncEntry {
+            remote_sync_key: key.to_string(),
+            save_folder_path: String::from("/saves").into(),
+            save_ignore_glob: vec![],
+            display_name: None,
+            conflict_strategy: None,
+            remote_storage: None,
+            process_names: None,
+        }
+    }
+
+    #[test]
+    fn merges_fragments_and_rejects_duplicates() {
+        let entries_dir = std::env::temp_dir().join(format!(
+            "local_cloud_game_sync_fragments_test_{}",
+            std::process::id()
+        ));
+        let _ = fs::remove_dir_all(&entries_dir);
+        write_entry_fragment(&entries_dir, &entry("b-game")).unwrap();
+        write_entry_fragment(&entries_dir, &entry("a-game")).unwrap();
+        fs::write(entries_dir.join("notes.txt"), "not an entry").unwrap();
+        assert!(write_entry_fragment(&entries_dir, &entry("a-game")).is_err());
+
+        let config_file = Path::new("sync_config.json");
+        let mut entries = vec![entry("main-game")];
+        merge_entry_fragments(
+            config_file,
+            &mut entries,
+            read_entry_fragments(&entries_dir).unwrap(),
+        )
+        .unwrap();
+        let keys: Vec<&str> = entries
+            .iter()
+            .map(|entry| entry.remote_sync_key.as_str())
+            .collect();
+        assert_eq!(keys, vec!["main-game", "a-game", "b-game"]);
+
+        let mut entries = vec![entry("a-game")];
+        let error = merge_entry_fragments(
+            config_file,
+            &mut entries,
+            read_entry_fragments(&entries_dir).unwrap(),
+        )
+        .err()
+        .unwrap();
+        assert!(error.contains("\"a-game\" - defined in both sync_config.json and"));
+        fs::remove_dir_all(&entries_dir).unwrap();
+    }
+}
@@ -2,8 +2,16 @@ mod runtime_sync_config;
 mod sync_options;
 
 pub mod config_commons;
+pub mod config_format;
+pub mod entry_fragments;
+pub mod shared_entries;
 
 pub use config_commons::default_sync_config_path;
 pub use config_commons::init_default_config;
+pub use config_format::ConfigFormat;
 pub use runtime_sync_config::RuntimeSyncConfig;
-pub use sync_options::SyncOptionsJson;
+pub use sync_options::{
+    CURRENT_OS_NAME, ConflictStrategy, DaemonOptions, PathCandidates, RemoteStorageMode,
+    RemoteTimeouts, SaveFolderPath, SaveFolderPathPerOs, SyncEntry, SyncOptionsJson,
+    TransferCompression, ValidatedSyncOptions, build_ignore_globset, is_valid_remote_key,
+};
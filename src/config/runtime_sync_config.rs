@@ -1,9 +1,14 @@
-use crate::config::sync_options::SyncOptionsJson;
+use crate::config::sync_options::{
+    ConflictStrategy, RemoteStorageMode, RemoteTimeouts, SyncOptionsJson, TransferCompression,
+};
+use crate::encryption::{EncryptionKey, read_local_key};
+use crate::hashing::HashAlgorithm;
 use globset::GlobSet;
 use std::path::PathBuf;
 
 /// Runtime config which contains all the necessary values for performing sync actions -
 /// generated from the global and specific sync config key given.
+#[derive(Clone)]
 pub struct RuntimeSyncConfig {
     // Display names
     pub client_name: String,
@@ -17,7 +22,23 @@ pub struct RuntimeSyncConfig {
     /// The path to where to store the remote saves. Must be absolute.
     pub remote_sync_root: String,
     pub local_save_folder: PathBuf,
+    /// Which `saveFolderPath` candidate `local_save_folder` came from - e.g. `linux[1]`.
+    pub save_folder_variant: String,
     pub ignore_globset: GlobSet,
+    pub conflict_strategy: ConflictStrategy,
+    /// Algorithm used when hashing new HEADs. Existing HEADs are compared using their own recorded algorithm.
+    pub hash_algorithm: HashAlgorithm,
+    pub transfer_compression: TransferCompression,
+    pub remote_storage: RemoteStorageMode,
+    /// Game processes which must not be running while local saves are read or overwritten.
+    pub process_names: Vec<String>,
+    /// Set when encryption is enabled - files are encrypted before push and decrypted on pull.
+    pub encryption_key: Option<EncryptionKey>,
+    pub remote_timeouts: RemoteTimeouts,
+    /// Ignore the local hash cache and re-digest every file. Not part of the config file - set from CLI.
+    pub rehash: bool,
+    /// Accept plaintext remote files although encryption is enabled - to migrate saves pushed before it. Set from CLI.
+    pub allow_unencrypted_pull: bool,
 }
 
 impl RuntimeSyncConfig {
@@ -33,7 +54,14 @@ impl RuntimeSyncConfig {
                 sync_key
             ))?;
 
-        let validated_sync_entry = sync_entry.validate()?;
+        let validated_sync_entry = sync_entry.validate(&validated_options.variables)?;
+
+        let encryption_key = match validated_options.encrypt {
+            true => Some(read_local_key(&validated_options.local_head_folder)?.ok_or(
+                String::from("Encryption is enabled but no key was found - run `keys init` first"),
+            )?),
+            false => None,
+        };
 
         return Ok(RuntimeSyncConfig {
             client_name: validated_options.client_name,
@@ -41,11 +69,21 @@ impl RuntimeSyncConfig {
             ssh_port: validated_options.ssh_port,
             remote_sync_root: validated_options.remote_sync_root,
             local_head_folder: validated_options.local_head_folder,
+            hash_algorithm: validated_options.hash_algorithm,
+            transfer_compression: validated_options.transfer_compression,
+            encryption_key: encryption_key,
+            remote_timeouts: validated_options.remote_timeouts,
+            rehash: false,
+            allow_unencrypted_pull: false,
             // from entry
             remote_sync_key: validated_sync_entry.remote_sync_key,
             local_save_folder: validated_sync_entry.save_folder_path,
+            save_folder_variant: validated_sync_entry.save_folder_variant,
             ignore_globset: validated_sync_entry.save_ignore_glob,
             game_display_name: validated_sync_entry.display_name,
+            conflict_strategy: validated_sync_entry.conflict_strategy,
+            remote_storage: validated_sync_entry.remote_storage,
+            process_names: validated_sync_entry.process_names,
         });
     }
 }
@@ -0,0 +1,127 @@
+use crate::config::sync_options::SyncEntry;
+use crate::hashing::HashAlgorithm;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Local copy of the remote's shared entries - next to the config file. Edit it and `config push` to share changes.
+pub const SHARED_ENTRIES_FILE_NAME: &str = "shared_entries.json";
+/// Hash of the shared entries last pulled/pushed - inside the local head folder. Not a valid sync key so it can't clash.
+pub const SHARED_ENTRIES_HEAD_FILE_NAME: &str = "shared_entries.json.HEAD";
+
+pub fn get_shared_entries_path(config_file: &Path) -> PathBuf {
+    config_file
+        .parent()
+        .unwrap_or(Path::new(""))
+        .join(SHARED_ENTRIES_FILE_NAME)
+}
+
+pub fn get_shared_entries_head_path(local_head_folder: &Path) -> PathBuf {
+    local_head_folder.join(SHARED_ENTRIES_HEAD_FILE_NAME)
+}
+
+pub fn hash_shared_entries(content: &str) -> String {
+    HashAlgorithm::Blake3.digest_bytes(content.as_bytes())
+}
+
+/// Hash of the shared entries this device last pulled or pushed - None if it never did.
+pub fn read_shared_entries_head(local_head_folder: &Path) -> Result<Option<String>, String> {
+    let head_path = get_shared_entries_head_path(local_head_folder);
+    if !head_path.exists() {
+        return Ok(None);
+    }
+    let head = fs::read_to_string(&head_path)
+        .map_err(|e| format!("Unable to read {}\n{e}", head_path.display()))?;
+    Ok(Some(head.trim().to_string()))
+}
+
+pub fn write_shared_entries_head(local_head_folder: &Path, hash: &str) -> Result<(), String> {
+    let head_path = get_shared_entries_head_path(local_head_folder);
+    fs::write(&head_path, hash).map_err(|e| format!("Unable to write {}\n{e}", head_path.display()))
+}
+
+/// Parses a shared entries file - a JSON array of sync entries with unique keys.
+pub fn parse_shared_entries(content: &str) -> Result<Vec<SyncEntry>, String> {
+    let entries: Vec<SyncEntry> =
+        serde_json::from_str(content).map_err(|e| format!("Error parsing shared entries\n{e}"))?;
+    for (i, entry) in entries.iter().enumerate() {
+        if entries[..i]
+            .iter()
+            .any(|other| other.remote_sync_key == entry.remote_sync_key)
+        {
+            return Err(format!(
+                "Duplicate remoteSyncKey \"{}\" in shared entries",
+                entry.remote_sync_key
+            ));
+        }
+    }
+    Ok(entries)
+}
+
+/// Shared entries from the local copy - empty if they were never pulled.
+pub fn read_shared_entries(shared_entries_path: &Path) -> Result<Vec<SyncEntry>, String> {
+    if !shared_entries_path.exists() {
+        return Ok(vec![]);
+    }
+    let content = fs::read_to_string(shared_entries_path)
+        .map_err(|e| format!("Unable to read {}\n{e}", shared_entries_path.display()))?;
+    parse_shared_entries(&content).map_err(|e| format!("{e}\nin {}", shared_entries_path.display()))
+}
+
+/// Shared entries followed by the local ones - a local entry replaces the shared entry with the same key.
+pub fn merge_shared_entries(shared: Vec<SyncEntry>, local: Vec<SyncEntry>) -> Vec<SyncEntry> {
+    let mut merged: Vec<SyncEntry> = shared
+        .into_iter()
+        .filter(|entry| {
+            !local
+                .iter()
+                .any(|local_entry| local_entry.remote_sync_key == entry.remote_sync_key)
+        })
+        .collect();
+    merged.extend(local);
+    merged
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn local_entries_override_shared_ones() {
+        let shared = parse_shared_entries(
+            r#"[
+                {"remoteSyncKey": "a-game", "saveFolderPath": "/shared/a", "saveIgnoreGlob": []},
+                {"remoteSyncKey": "b-game", "saveFolderPath": {"linux": "/shared/b"}, "saveIgnoreGlob": ["*.log"]}
+            ]"#,
+        )
+        .unwrap();
+        let local = parse_shared_entries(
+            r#"[{"remoteSyncKey": "b-game", "saveFolderPath": "/local/b", "saveIgnoreGlob": []}]"#,
+        )
+        .unwrap();
+
+        let merged = merge_shared_entries(shared, local);
+        let paths: Vec<(&str, Vec<&str>)> = merged
+            .iter()
+            .map(|entry| {
+                (
+                    entry.remote_sync_key.as_str(),
+                    entry.save_folder_path.all_paths(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            paths,
+            vec![("a-game", vec!["/shared/a"]), ("b-game", vec!["/local/b"])]
+        );
+
+        assert!(
+            parse_shared_entries(
+                r#"[
+                    {"remoteSyncKey": "a-game", "saveFolderPath": "/a", "saveIgnoreGlob": []},
+                    {"remoteSyncKey": "a-game", "saveFolderPath": "/b", "saveIgnoreGlob": []}
+                ]"#
+            )
+            .is_err()
+        );
+    }
+}
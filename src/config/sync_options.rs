@@ -1,22 +1,214 @@
 use crate::config::config_commons::*;
+use crate::hashing::HashAlgorithm;
 use globset::GlobSet;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::path::{Path, PathBuf};
+use std::time::Duration;
 
 pub struct ValidatedSyncEntry {
     pub remote_sync_key: String,
     pub save_folder_path: PathBuf,
+    /// Which `saveFolderPath` candidate was picked - e.g. `linux[1]`.
+    pub save_folder_variant: String,
     pub save_ignore_glob: GlobSet,
     pub display_name: String,
+    pub conflict_strategy: ConflictStrategy,
+    pub remote_storage: RemoteStorageMode,
+    pub process_names: Vec<String>,
+}
+
+/// How to resolve a sync where both local and remote have changes.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum ConflictStrategy {
+    /// Always ask the user to pick local or remote.
+    #[default]
+    Manual,
+    /// Automatically merge if local and remote changed different files. Falls back to manual otherwise.
+    FileMerge,
+}
+
+/// Compression applied to saves while transferring them to/from the remote.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum TransferCompression {
+    #[default]
+    None,
+    /// zstd stream - requires the `zstd` binary on the remote when storing loose trees.
+    Zstd,
+}
+
+/// How saves are stored under `GameSaves/<key>` on the remote.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum RemoteStorageMode {
+    /// Loose file tree - same layout as the local save folder.
+    #[default]
+    Tree,
+    /// Single zstd-compressed tar archive plus a manifest. Pulls detect this automatically.
+    Archive,
+}
+
+/// Limits for talking to the remote - so an unreachable host fails fast instead of hanging on the OS TCP timeout.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RemoteTimeouts {
+    /// Max time to establish the SSH connection.
+    pub connect: Duration,
+    /// Max time for a single remote command. Streamed transfers are instead dropped once the connection stalls for this long.
+    pub operation: Duration,
+    /// Extra attempts for idempotent operations (reading HEAD, locking, snapshots) failing because the remote is unreachable.
+    pub retries: u32,
+}
+
+impl Default for RemoteTimeouts {
+    fn default() -> Self {
+        RemoteTimeouts {
+            connect: Duration::from_secs(DEFAULT_CONNECT_TIMEOUT_SECS),
+            operation: Duration::from_secs(DEFAULT_OPERATION_TIMEOUT_SECS),
+            retries: DEFAULT_REMOTE_RETRIES,
+        }
+    }
+}
+
+/// Timings of the `daemon` command.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DaemonOptions {
+    /// How long a save folder must stay untouched before its changes are pushed.
+    pub quiet_period: Duration,
+    /// How often remote HEADs are polled to pre-fetch changes from other devices.
+    pub poll_interval: Duration,
+}
+
+impl Default for DaemonOptions {
+    fn default() -> Self {
+        DaemonOptions {
+            quiet_period: Duration::from_secs(DEFAULT_DAEMON_QUIET_SECS),
+            poll_interval: Duration::from_secs(DEFAULT_DAEMON_POLL_SECS),
+        }
+    }
+}
+
+/// One path or several candidates - the first existing one is used.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(untagged)]
+pub enum PathCandidates {
+    Path(String),
+    Candidates(Vec<String>),
+}
+
+impl PathCandidates {
+    pub fn paths(&self) -> Vec<&str> {
+        match self {
+            PathCandidates::Path(path) => vec![path.as_str()],
+            PathCandidates::Candidates(paths) => paths.iter().map(String::as_str).collect(),
+        }
+    }
+
+    /// (variant label, path) pairs - the label is `name` for a single path and `name[i]` for candidates.
+    fn labelled(&self, name: &str) -> Vec<(String, String)> {
+        match self {
+            PathCandidates::Path(path) => vec![(name.to_string(), path.clone())],
+            PathCandidates::Candidates(paths) => paths
+                .iter()
+                .enumerate()
+                .map(|(i, path)| (format!("{name}[{i}]"), path.clone()))
+                .collect(),
+        }
+    }
+}
+
+/// Save folder paths per OS - so one entry works across Windows and Linux/Steam Deck machines.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct SaveFolderPathPerOs {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub windows: Option<PathCandidates>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub linux: Option<PathCandidates>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub macos: Option<PathCandidates>,
+    /// Hostname -> paths - tried before the OS paths on that machine.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub hosts: BTreeMap<String, PathCandidates>,
+}
+
+/// `saveFolderPath` - a path, a list of candidates or an object keyed by OS with optional hostname overrides.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(untagged)]
+pub enum SaveFolderPath {
+    Paths(PathCandidates),
+    PerOs(SaveFolderPathPerOs),
+}
+
+#[cfg(target_os = "windows")]
+pub const CURRENT_OS_NAME: &str = "windows";
+#[cfg(target_os = "linux")]
+pub const CURRENT_OS_NAME: &str = "linux";
+#[cfg(target_os = "macos")]
+pub const CURRENT_OS_NAME: &str = "macos";
+
+impl SaveFolderPath {
+    /// Every path written in the config - regardless of OS or host.
+    pub fn all_paths(&self) -> Vec<&str> {
+        match self {
+            SaveFolderPath::Paths(paths) => paths.paths(),
+            SaveFolderPath::PerOs(per_os) => [&per_os.windows, &per_os.linux, &per_os.macos]
+                .into_iter()
+                .flatten()
+                .chain(per_os.hosts.values())
+                .flat_map(PathCandidates::paths)
+                .collect(),
+        }
+    }
+
+    /// (variant label, path) pairs applying to this machine - in the order they should be tried.
+    pub fn candidates(&self, os: &str, hostname: &str) -> Vec<(String, String)> {
+        match self {
+            SaveFolderPath::Paths(paths) => paths.labelled("saveFolderPath"),
+            SaveFolderPath::PerOs(per_os) => {
+                let mut candidates = vec![];
+                for (host, paths) in per_os.hosts.iter() {
+                    if host.eq_ignore_ascii_case(hostname) {
+                        candidates.extend(paths.labelled(&format!("hosts.{host}")));
+                    }
+                }
+                let os_paths = match os {
+                    "windows" => &per_os.windows,
+                    "linux" => &per_os.linux,
+                    "macos" => &per_os.macos,
+                    _ => &None,
+                };
+                if let Some(paths) = os_paths {
+                    candidates.extend(paths.labelled(os));
+                }
+                candidates
+            }
+        }
+    }
+}
+
+impl From<String> for SaveFolderPath {
+    fn from(path: String) -> Self {
+        SaveFolderPath::Paths(PathCandidates::Path(path))
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct SyncEntry {
     pub remote_sync_key: String,
-    pub save_folder_path: String,
+    pub save_folder_path: SaveFolderPath,
     pub save_ignore_glob: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub display_name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub conflict_strategy: Option<ConflictStrategy>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub remote_storage: Option<RemoteStorageMode>,
+    /// Game process names (e.g. "Game.exe") - syncs wait or refuse to pull while one is running.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub process_names: Option<Vec<String>>,
 }
 
 pub struct ValidatedSyncOptions {
@@ -25,6 +217,12 @@ pub struct ValidatedSyncOptions {
     pub ssh_port: u32,
     pub remote_sync_root: String,
     pub local_head_folder: PathBuf,
+    pub hash_algorithm: HashAlgorithm,
+    pub transfer_compression: TransferCompression,
+    pub encrypt: bool,
+    pub remote_timeouts: RemoteTimeouts,
+    pub daemon: DaemonOptions,
+    pub variables: BTreeMap<String, String>,
     pub sync_entries: Vec<SyncEntry>,
 }
 
@@ -36,8 +234,27 @@ pub struct SyncOptionsJson {
     pub ssh_port: Option<u32>,
     pub remote_sync_root: String,
     pub local_head_folder: Option<String>,
+    /// Algorithm for hashing new HEADs ("md5" | "blake3"). Keep md5 while older clients still sync the same remote.
+    pub hash_algorithm: Option<HashAlgorithm>,
+    pub transfer_compression: Option<TransferCompression>,
+    /// Encrypt saves client-side before pushing. Requires a key - see `keys init`.
+    pub encrypt: Option<bool>,
+    pub connect_timeout_secs: Option<u64>,
+    pub operation_timeout_secs: Option<u64>,
+    /// Retries for idempotent remote operations when the remote is unreachable.
+    pub remote_retries: Option<u32>,
+    /// Seconds a save folder must stay untouched before the daemon pushes it.
+    pub daemon_quiet_secs: Option<u64>,
+    /// Seconds between the daemon's remote HEAD polls.
+    pub daemon_poll_secs: Option<u64>,
+    /// Custom `{{NAME}}` placeholders for this machine - a variable replaces the built-in placeholder of the same name.
+    #[serde(default)]
+    pub variables: BTreeMap<String, String>,
+    /// Entries from `entries.d` are merged in when loading.
+    #[serde(default)]
     pub sync_entries: Vec<SyncEntry>,
 }
 
 #[path = "./sync_options_validator.rs"]
 mod sync_options_validator;
+pub use sync_options_validator::{build_ignore_globset, is_valid_remote_key};
@@ -1,6 +1,7 @@
 use globset::{GlobBuilder, GlobSetBuilder};
 
 use super::*;
+use crate::hashing::PREFERRED_HASH_ALGORITHM;
 use crate::utils::generate_display_name_from_key;
 
 fn validate_remote_root_path(save_key: &str) -> bool {
@@ -65,7 +66,52 @@ impl SyncOptionsJson {
             ));
         }
 
-        // 5. Do NOT validate sync entries. Validate sync entries will be validated when runtime config is created.
+        // 5. Validate timeouts - zero would fail every remote call.
+        if self.connect_timeout_secs == Some(0) || self.operation_timeout_secs == Some(0) {
+            return Err(format!(
+                "connectTimeoutSecs and operationTimeoutSecs must be greater than 0"
+            ));
+        }
+        let remote_timeouts = RemoteTimeouts {
+            connect: Duration::from_secs(
+                self.connect_timeout_secs
+                    .unwrap_or(DEFAULT_CONNECT_TIMEOUT_SECS),
+            ),
+            operation: Duration::from_secs(
+                self.operation_timeout_secs
+                    .unwrap_or(DEFAULT_OPERATION_TIMEOUT_SECS),
+            ),
+            retries: self.remote_retries.unwrap_or(DEFAULT_REMOTE_RETRIES),
+        };
+
+        // 6. Validate daemon timings - zero would make the daemon spin.
+        if self.daemon_quiet_secs == Some(0) || self.daemon_poll_secs == Some(0) {
+            return Err(format!(
+                "daemonQuietSecs and daemonPollSecs must be greater than 0"
+            ));
+        }
+        let daemon = DaemonOptions {
+            quiet_period: Duration::from_secs(
+                self.daemon_quiet_secs.unwrap_or(DEFAULT_DAEMON_QUIET_SECS),
+            ),
+            poll_interval: Duration::from_secs(
+                self.daemon_poll_secs.unwrap_or(DEFAULT_DAEMON_POLL_SECS),
+            ),
+        };
+
+        // 7. Validate variables - names are used as `{{NAME}}` placeholders.
+        for (name, value) in self.variables.iter() {
+            if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+                return Err(format!(
+                    "Invalid variable name \"{name}\" - must only contains [A-Za-z0-9_]"
+                ));
+            }
+            if value.is_empty() {
+                return Err(format!("Variable {name} must not be empty"));
+            }
+        }
+
+        // 8. Do NOT validate sync entries. Validate sync entries will be validated when runtime config is created.
         // This is intended - so that a misconfigured sync entry from one game does not break others.
         Ok(ValidatedSyncOptions {
             client_name: self.client_name,
@@ -73,12 +119,33 @@ impl SyncOptionsJson {
             ssh_port: self.ssh_port.unwrap_or(DEFAULT_SSH_PORT),
             remote_sync_root: self.remote_sync_root,
             local_head_folder: local_head_folder,
+            hash_algorithm: self.hash_algorithm.unwrap_or(PREFERRED_HASH_ALGORITHM),
+            transfer_compression: self.transfer_compression.unwrap_or_default(),
+            encrypt: self.encrypt.unwrap_or(false),
+            remote_timeouts: remote_timeouts,
+            daemon: daemon,
+            variables: self.variables,
             sync_entries: self.sync_entries,
         })
     }
 }
 
-fn validate_remote_key(save_key: &str) -> bool {
+/// Compiles `saveIgnoreGlob` - `*` does not cross folder separators.
+pub fn build_ignore_globset(patterns: &[String]) -> Result<GlobSet, String> {
+    let mut builder = GlobSetBuilder::new();
+    for pat in patterns {
+        let pattern = GlobBuilder::new(pat)
+            .literal_separator(true)
+            .build()
+            .map_err(|e| format!("Invalid glob pattern: {}", e))?;
+        builder.add(pattern);
+    }
+    builder
+        .build()
+        .map_err(|e| format!("Unable to build globset\n{}", e))
+}
+
+pub fn is_valid_remote_key(save_key: &str) -> bool {
     for c in save_key.chars() {
         if !(c.is_ascii_alphanumeric() || c == '-' || c == '_') {
             return false;
@@ -88,37 +155,63 @@ fn validate_remote_key(save_key: &str) -> bool {
 }
 
 impl SyncEntry {
-    pub fn validate(&self) -> Result<ValidatedSyncEntry, String> {
+    /// `variables` are the global config ones - used to expand `saveFolderPath`.
+    pub fn validate(
+        &self,
+        variables: &BTreeMap<String, String>,
+    ) -> Result<ValidatedSyncEntry, String> {
         // 1. Validate Sync Key
-        if !validate_remote_key(&self.remote_sync_key) {
+        if !is_valid_remote_key(&self.remote_sync_key) {
             return Err(format!(
                 "Invalid JSON configuration - remoteSyncKey given \"{}\" - must only contains [A-Za-z0-9_-]",
                 self.remote_sync_key
             ));
         }
 
-        // 2. Validate save folder path
-        let expanded_save_path_str = &expand_config_placeholders(&self.save_folder_path);
-        let expanded_save_path = Path::new(expanded_save_path_str);
-        if !expanded_save_path.exists() {
+        // 2. Validate save folder path - first existing candidate for this machine wins.
+        let hostname = gethostname::gethostname().to_string_lossy().to_string();
+        let candidates = self.save_folder_path.candidates(CURRENT_OS_NAME, &hostname);
+        if candidates.is_empty() {
             return Err(format!(
-                "Invalid JSON configuration - saveFolderPath given \"{}\" - does not exist! Verify location or Launch game first to create save folder location",
-                expanded_save_path.display()
+                "Invalid JSON configuration - saveFolderPath of \"{}\" has no path for {CURRENT_OS_NAME} (host {hostname})",
+                self.remote_sync_key
             ));
         }
+        // Candidates whose placeholders have no value on this machine are skipped - e.g. `{{APPDATA}}` on Linux.
+        // Only when none resolves is that an error - naming what is missing.
+        let mut expanded_candidates: Vec<(&String, PathBuf)> = vec![];
+        let mut unresolved: Vec<String> = vec![];
+        for (variant, path) in candidates.iter() {
+            match expand_config_placeholders(path, variables) {
+                Ok(expanded) => expanded_candidates.push((variant, PathBuf::from(expanded))),
+                Err(e) => unresolved.push(format!("{variant}: {e}")),
+            }
+        }
+        if expanded_candidates.is_empty() {
+            return Err(format!(
+                "Invalid JSON configuration - saveFolderPath of \"{}\" has no path resolving on this machine:\n{}",
+                self.remote_sync_key,
+                unresolved.join("\n")
+            ));
+        }
+        let (save_folder_variant, expanded_save_path) = expanded_candidates
+            .iter()
+            .find(|(_, path)| path.exists())
+            .cloned()
+            .ok_or_else(|| {
+                let tried: Vec<String> = expanded_candidates
+                    .iter()
+                    .map(|(variant, path)| format!("{variant}: {}", path.display()))
+                    .chain(unresolved.iter().cloned())
+                    .collect();
+                format!(
+                    "Invalid JSON configuration - saveFolderPath given does not exist! Verify location or Launch game first to create save folder location. Tried:\n{}",
+                    tried.join("\n")
+                )
+            })?;
 
         // 3. Validate globs
-        let mut builder = GlobSetBuilder::new();
-        for pat in &self.save_ignore_glob {
-            let pattern = GlobBuilder::new(&pat)
-                .literal_separator(true)
-                .build()
-                .map_err(|e| format!("Invalid glob pattern: {}", e))?;
-            builder.add(pattern);
-        }
-        let ignore_globset = builder
-            .build()
-            .map_err(|e| format!("Unable to build globset\n{}", e))?;
+        let ignore_globset = build_ignore_globset(&self.save_ignore_glob)?;
 
         // 4. Create display name from remote key if not specified
         let display: String = self
@@ -126,11 +219,24 @@ impl SyncEntry {
             .clone()
             .unwrap_or_else(|| generate_display_name_from_key(&self.remote_sync_key));
 
+        // 5. Validate process names
+        let process_names = self.process_names.clone().unwrap_or_default();
+        if process_names.iter().any(|name| name.trim().is_empty()) {
+            return Err(format!(
+                "Invalid JSON configuration - processNames of \"{}\" must not contain empty names",
+                self.remote_sync_key
+            ));
+        }
+
         Ok(ValidatedSyncEntry {
             remote_sync_key: self.remote_sync_key.clone(),
-            save_folder_path: expanded_save_path.to_path_buf(),
+            save_folder_path: expanded_save_path,
+            save_folder_variant: save_folder_variant.clone(),
             save_ignore_glob: ignore_globset,
             display_name: display,
+            conflict_strategy: self.conflict_strategy.unwrap_or_default(),
+            remote_storage: self.remote_storage.unwrap_or_default(),
+            process_names: process_names,
         })
     }
 }
@@ -150,4 +256,98 @@ mod tests {
         assert!(!validate_remote_root_path("/media/*other/"));
         assert!(!validate_remote_root_path("/media#1/*other/"));
     }
+
+    fn entry_with_path(save_folder_path: serde_json::Value) -> SyncEntry {
+        serde_json::from_value(serde_json::json!({
+            "remoteSyncKey": "test-key",
+            "saveFolderPath": save_folder_path,
+            "saveIgnoreGlob": [],
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn picks_first_existing_save_folder_candidate() {
+        let existing = std::env::temp_dir().to_string_lossy().to_string();
+        let validated = entry_with_path(serde_json::json!(["/definitely/missing", existing]))
+            .validate(&BTreeMap::new())
+            .unwrap();
+        assert_eq!(validated.save_folder_variant, "saveFolderPath[1]");
+        assert_eq!(validated.save_folder_path, PathBuf::from(&existing));
+
+        let error = entry_with_path(serde_json::json!("/definitely/missing"))
+            .validate(&BTreeMap::new())
+            .err()
+            .unwrap();
+        assert!(error.contains("saveFolderPath: /definitely/missing"));
+    }
+
+    #[test]
+    fn unresolved_placeholders_skip_candidates() {
+        let existing = std::env::temp_dir().to_string_lossy().to_string();
+        let variables = BTreeMap::from([(String::from("TMP_SAVES"), existing.clone())]);
+        let validated = entry_with_path(serde_json::json!("{{TMP_SAVES}}"))
+            .validate(&variables)
+            .unwrap();
+        assert_eq!(validated.save_folder_path, PathBuf::from(&existing));
+
+        // A candidate for another OS does not break the ones resolving here.
+        let validated = entry_with_path(serde_json::json!([
+            "{{ENV:LCGSYNC_SURELY_UNSET}}/Saves",
+            "{{TMP_SAVES}}"
+        ]))
+        .validate(&variables)
+        .unwrap();
+        assert_eq!(validated.save_folder_variant, "saveFolderPath[1]");
+
+        let error = entry_with_path(serde_json::json!([
+            "{{ENV:LCGSYNC_SURELY_UNSET}}/Saves",
+            "{{LCGSYNC_MISSING}}/Saves"
+        ]))
+        .validate(&variables)
+        .err()
+        .unwrap();
+        assert!(error.contains("has no path resolving"), "{error}");
+        assert!(error.contains("LCGSYNC_SURELY_UNSET is not set"), "{error}");
+        assert!(
+            error.contains("Unknown placeholder {{LCGSYNC_MISSING}}"),
+            "{error}"
+        );
+    }
+
+    #[test]
+    fn per_os_save_folder_prefers_hostname_override() {
+        let existing = std::env::temp_dir().to_string_lossy().to_string();
+        let hostname = gethostname::gethostname().to_string_lossy().to_string();
+        let per_os = serde_json::json!({
+            "windows": "/definitely/missing",
+            "linux": ["/definitely/missing", existing],
+            "macos": existing,
+            "hosts": { hostname.clone(): existing, "other-host": "/definitely/missing" },
+        });
+        let entry = entry_with_path(per_os);
+        assert_eq!(
+            entry.save_folder_path.candidates("linux", "unknown-host"),
+            vec![
+                (
+                    String::from("linux[0]"),
+                    String::from("/definitely/missing")
+                ),
+                (String::from("linux[1]"), existing.clone()),
+            ]
+        );
+        assert_eq!(
+            entry
+                .validate(&BTreeMap::new())
+                .unwrap()
+                .save_folder_variant,
+            format!("hosts.{hostname}")
+        );
+
+        let error = entry_with_path(serde_json::json!({ "hosts": { "other-host": existing } }))
+            .validate(&BTreeMap::new())
+            .err()
+            .unwrap();
+        assert!(error.contains("has no path for"));
+    }
 }
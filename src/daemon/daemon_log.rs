@@ -0,0 +1,7 @@
+use std::path::{Path, PathBuf};
+
+pub const DAEMON_LOG_FILE_NAME: &str = "daemon.log";
+
+pub fn get_daemon_log_filepath(local_head_folder: &Path) -> PathBuf {
+    return local_head_folder.join(DAEMON_LOG_FILE_NAME);
+}
@@ -0,0 +1,334 @@
+use std::collections::HashSet;
+use std::path::Path;
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::time::{Duration, Instant};
+
+use notify::event::ModifyKind;
+use notify::{Event, EventKind, RecursiveMode, Watcher};
+
+use crate::cancellation::CancellationToken;
+use crate::commands::{CheckSyncResult, sync_command};
+use crate::config::{RuntimeSyncConfig, ValidatedSyncOptions};
+use crate::daemon::daemon_log::get_daemon_log_filepath;
+use crate::daemon::daemon_state::DaemonState;
+use crate::daemon::debounce::Debouncer;
+use crate::game_process::{find_running_game_processes, format_running_processes};
+use crate::log_file::LogFile;
+use crate::pending_sync::{PendingReason, mark_pending_sync};
+use crate::utils::get_unix_timestamp_secs;
+
+/// How often the loop wakes up to check for due keys and cancellation when no events arrive.
+const DAEMON_TICK: Duration = Duration::from_secs(1);
+
+type WatchEvent = notify::Result<Event>;
+
+/// Why a sync was started by the daemon.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum SyncTrigger {
+    /// The save folder changed and then stayed quiet.
+    LocalChange,
+    /// Periodic remote HEAD poll.
+    Poll,
+}
+
+fn describe_sync_result(check_sync_result: &CheckSyncResult) -> &'static str {
+    match check_sync_result {
+        CheckSyncResult::UpToDate => "up to date",
+        CheckSyncResult::FastForwardLocal => "pulled remote changes",
+        CheckSyncResult::FastForwardRemote | CheckSyncResult::RemoteEmpty => "pushed local changes",
+        CheckSyncResult::AutoMerge { .. } => "merged local and remote changes",
+        CheckSyncResult::Conflict { .. } => "conflict",
+    }
+}
+
+/// Whether the event can change save contents - reads and metadata-only changes are ignored.
+fn is_content_event(event: &Event) -> bool {
+    match event.kind {
+        EventKind::Modify(ModifyKind::Metadata(_)) => false,
+        EventKind::Create(_) | EventKind::Modify(_) | EventKind::Remove(_) => true,
+        _ => false,
+    }
+}
+
+/// Sync key whose save folder contains the path - the most specific folder wins. None for ignored files.
+fn find_sync_key_for_path<'a>(configs: &'a [RuntimeSyncConfig], path: &Path) -> Option<&'a str> {
+    let config = configs
+        .iter()
+        .filter(|config| path.starts_with(&config.local_save_folder))
+        .max_by_key(|config| config.local_save_folder.components().count())?;
+
+    let rel = path.strip_prefix(&config.local_save_folder).ok()?;
+    if !rel.as_os_str().is_empty() && config.ignore_globset.is_match(rel) {
+        return None;
+    }
+    Some(&config.remote_sync_key)
+}
+
+struct Daemon<'a> {
+    options: &'a ValidatedSyncOptions,
+    configs: Vec<RuntimeSyncConfig>,
+    debouncer: Debouncer,
+    state: DaemonState,
+    log: LogFile,
+    cancel: &'a CancellationToken,
+    /// Keys whose game was seen running - logged once until it closes.
+    waiting_for_game: HashSet<String>,
+}
+
+impl Daemon<'_> {
+    fn write_state(&mut self) {
+        if let Err(e) = self.state.write(&self.options.local_head_folder) {
+            self.log.log(&e);
+        }
+    }
+
+    /// Records the changed keys of the event - except `own_key` whose changes were made by the daemon itself.
+    fn handle_event(&mut self, event: Event, own_key: Option<&str>) {
+        if !is_content_event(&event) {
+            return;
+        }
+
+        let now = Instant::now();
+        let mut changed_keys: Vec<String> = event
+            .paths
+            .iter()
+            .filter_map(|path| find_sync_key_for_path(&self.configs, path))
+            .filter(|sync_key| Some(*sync_key) != own_key)
+            .map(str::to_string)
+            .collect();
+        changed_keys.dedup();
+
+        for sync_key in changed_keys {
+            if !self.debouncer.is_dirty(&sync_key) {
+                self.log.log(&format!(
+                    "{sync_key}: change detected - pushing once quiet for {}s",
+                    self.options.daemon.quiet_period.as_secs()
+                ));
+            }
+            self.debouncer.record_change(&sync_key, now);
+            self.state.key_mut(&sync_key).last_change_at = Some(get_unix_timestamp_secs());
+            self.write_state();
+        }
+    }
+
+    /// Whether the key's game is running - such keys are neither pushed nor pulled until it closes.
+    fn is_game_running(&mut self, config_index: usize) -> bool {
+        let config = &self.configs[config_index];
+        let sync_key = config.remote_sync_key.clone();
+        let running = match find_running_game_processes(config) {
+            Ok(running) => running,
+            // Treated as running - logged once like a running game.
+            Err(e) => {
+                if self.waiting_for_game.insert(sync_key.clone()) {
+                    self.log.log(&format!("{sync_key}: not syncing\n{e}"));
+                    let key_state = self.state.key_mut(&sync_key);
+                    key_state.last_action = Some(String::from("failed"));
+                    key_state.last_error = Some(e);
+                    self.write_state();
+                }
+                return true;
+            }
+        };
+        if running.is_empty() {
+            if self.waiting_for_game.remove(&sync_key) {
+                self.log.log(&format!("{sync_key}: game closed"));
+            }
+            return false;
+        }
+
+        if self.waiting_for_game.insert(sync_key.clone()) {
+            self.log.log(&format!(
+                "{sync_key}: waiting for game to close - {}",
+                format_running_processes(&running)
+            ));
+            self.state.key_mut(&sync_key).last_action = Some(String::from("waiting for game"));
+            self.write_state();
+        }
+        return true;
+    }
+
+    /// Handles queued events - dropping those of the key as they were caused by the daemon writing its save folder.
+    fn discard_own_events(&mut self, rx: &Receiver<WatchEvent>, sync_key: &str) {
+        let queued: Vec<WatchEvent> = rx.try_iter().collect();
+        for event in queued {
+            match event {
+                Ok(event) => self.handle_event(event, Some(sync_key)),
+                Err(e) => self.log.log(&format!("Watcher error\n{e}")),
+            }
+        }
+        self.debouncer.forget(sync_key);
+    }
+
+    fn sync_key(&mut self, rx: &Receiver<WatchEvent>, config_index: usize, trigger: SyncTrigger) {
+        let config = &self.configs[config_index];
+        let sync_key = config.remote_sync_key.clone();
+        let res = sync_command(config, |_| {}, self.cancel);
+
+        let key_state = self.state.key_mut(&sync_key);
+        key_state.last_sync_at = Some(get_unix_timestamp_secs());
+        match &res {
+            Ok(check_sync_result) => {
+                key_state.last_action = Some(describe_sync_result(check_sync_result).to_string());
+                key_state.last_error = None;
+            }
+            Err(e) => {
+                key_state.last_action = Some(String::from("failed"));
+                key_state.last_error = Some(e.clone());
+            }
+        }
+
+        match res {
+            Ok(CheckSyncResult::UpToDate) if trigger == SyncTrigger::Poll => {}
+            Ok(check_sync_result) => {
+                self.log.log(&format!(
+                    "{sync_key}: {}",
+                    describe_sync_result(&check_sync_result)
+                ));
+                if matches!(
+                    check_sync_result,
+                    CheckSyncResult::FastForwardLocal | CheckSyncResult::AutoMerge { .. }
+                ) {
+                    self.discard_own_events(rx, &sync_key);
+                }
+            }
+            Err(e) => {
+                self.log.log(&format!("{sync_key}: sync failed\n{e}"));
+                // Local changes that could not be pushed are retried on the next poll - and show up in `pending`.
+                if trigger == SyncTrigger::LocalChange {
+                    let head_folder = &self.options.local_head_folder;
+                    if let Err(e) = mark_pending_sync(
+                        head_folder,
+                        &sync_key,
+                        PendingReason::PushFailed,
+                        Some(&e),
+                    ) {
+                        self.log.log(&e);
+                    }
+                }
+            }
+        }
+        self.write_state();
+    }
+
+    /// Syncs every key without recent local changes - pre-fetching remote updates and pushing anything left behind.
+    /// Keys with recent changes or a running game are skipped.
+    fn poll(&mut self, rx: &Receiver<WatchEvent>) {
+        for config_index in 0..self.configs.len() {
+            if self.cancel.is_cancelled() {
+                return;
+            }
+            if self
+                .debouncer
+                .is_dirty(&self.configs[config_index].remote_sync_key)
+                || self.is_game_running(config_index)
+            {
+                continue;
+            }
+            self.sync_key(rx, config_index, SyncTrigger::Poll);
+        }
+        self.state.last_poll_at = Some(get_unix_timestamp_secs());
+        self.write_state();
+    }
+}
+
+/// Watches every configured save folder - pushing changes once the folder is quiet and periodically
+/// polling the remote to pre-fetch changes from other devices. Runs until cancelled.
+pub fn daemon_main<L>(
+    options: &ValidatedSyncOptions,
+    load_config: L,
+    cancel: &CancellationToken,
+) -> Result<(), String>
+where
+    L: Fn(&str) -> Result<RuntimeSyncConfig, String>,
+{
+    let log = LogFile::open(&get_daemon_log_filepath(&options.local_head_folder))?;
+    log.log(&format!(
+        "Daemon starting - quiet period {}s, poll interval {}s",
+        options.daemon.quiet_period.as_secs(),
+        options.daemon.poll_interval.as_secs()
+    ));
+
+    // 1. Load every entry - a misconfigured entry only disables itself.
+    let mut configs = vec![];
+    for entry in options.sync_entries.iter() {
+        match load_config(&entry.remote_sync_key) {
+            Ok(config) => configs.push(config),
+            Err(e) => log.log(&format!("{}: skipped\n{e}", entry.remote_sync_key)),
+        }
+    }
+    if configs.is_empty() {
+        return Err(String::from("No valid sync entries to watch"));
+    }
+
+    // 2. Watch every save folder. Folders that cannot be watched are still polled.
+    let (tx, rx) = mpsc::channel::<WatchEvent>();
+    let mut watcher = notify::recommended_watcher(tx)
+        .map_err(|e| format!("Unable to create file watcher\n{e}"))?;
+    let mut state = DaemonState::new();
+    for config in configs.iter() {
+        let watch_res = watcher.watch(&config.local_save_folder, RecursiveMode::Recursive);
+        if let Err(e) = &watch_res {
+            log.log(&format!(
+                "{}: not watching {}\n{e}",
+                config.remote_sync_key,
+                config.local_save_folder.display()
+            ));
+        }
+        let key_state = state.key_mut(&config.remote_sync_key);
+        key_state.save_folder = config.local_save_folder.display().to_string();
+        key_state.watching = watch_res.is_ok();
+    }
+
+    let mut daemon = Daemon {
+        options: options,
+        configs: configs,
+        debouncer: Debouncer::new(options.daemon.quiet_period),
+        state: state,
+        log: log,
+        cancel: cancel,
+        waiting_for_game: HashSet::new(),
+    };
+    daemon.write_state();
+
+    // 3. Main loop - the first poll runs right away to catch up with changes made while not running.
+    let mut next_poll = Instant::now();
+    while !cancel.is_cancelled() {
+        match rx.recv_timeout(DAEMON_TICK) {
+            Ok(Ok(event)) => daemon.handle_event(event, None),
+            Ok(Err(e)) => daemon.log.log(&format!("Watcher error\n{e}")),
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => {
+                return Err(String::from("File watcher stopped unexpectedly"));
+            }
+        }
+
+        for sync_key in daemon.debouncer.take_due(Instant::now()) {
+            if cancel.is_cancelled() {
+                break;
+            }
+            let Some(config_index) = daemon
+                .configs
+                .iter()
+                .position(|config| config.remote_sync_key == sync_key)
+            else {
+                continue;
+            };
+            // Deferred - pushed once the game closed and the folder is quiet again.
+            if daemon.is_game_running(config_index) {
+                daemon.debouncer.record_change(&sync_key, Instant::now());
+                continue;
+            }
+            daemon.sync_key(&rx, config_index, SyncTrigger::LocalChange);
+        }
+
+        if Instant::now() >= next_poll {
+            daemon.poll(&rx);
+            next_poll = Instant::now() + options.daemon.poll_interval;
+        }
+    }
+
+    daemon.log.log("Daemon stopped");
+    daemon.state.stopped = true;
+    daemon.write_state();
+    Ok(())
+}
@@ -0,0 +1,121 @@
+use crate::common::format_unix_timestamp;
+use crate::utils::get_unix_timestamp_secs;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+pub const DAEMON_STATE_FILE_NAME: &str = "daemon_state.json";
+
+/// What the daemon last did for a sync key.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct DaemonKeyState {
+    pub save_folder: String,
+    pub watching: bool,
+    /// Last local change seen (unix secs).
+    pub last_change_at: Option<u64>,
+    /// Last sync attempt (unix secs).
+    pub last_sync_at: Option<u64>,
+    pub last_action: Option<String>,
+    pub last_error: Option<String>,
+}
+
+/// Snapshot of the running (or last run) daemon - rewritten after every change so it can be inspected with `daemon --status`.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct DaemonState {
+    pub pid: u32,
+    pub started_at: u64,
+    pub updated_at: u64,
+    pub last_poll_at: Option<u64>,
+    pub stopped: bool,
+    pub keys: BTreeMap<String, DaemonKeyState>,
+}
+
+pub fn get_daemon_state_filepath(local_head_folder: &Path) -> PathBuf {
+    return local_head_folder.join(DAEMON_STATE_FILE_NAME);
+}
+
+pub fn read_daemon_state(local_head_folder: &Path) -> Result<Option<DaemonState>, String> {
+    let state_path = get_daemon_state_filepath(local_head_folder);
+    if !state_path.exists() {
+        return Ok(None);
+    }
+
+    let statestr =
+        fs::read_to_string(&state_path).map_err(|e| format!("Unable to read daemon state\n{e}"))?;
+    let state = serde_json::from_str(&statestr)
+        .map_err(|e| format!("Unable to parse daemon state\n{e}"))?;
+    Ok(Some(state))
+}
+
+fn format_optional_timestamp(timestamp: Option<u64>) -> String {
+    match timestamp {
+        Some(timestamp) => format_unix_timestamp(timestamp, "%a %e %b %Y at %H:%M:%S"),
+        None => String::from("never"),
+    }
+}
+
+impl DaemonState {
+    pub fn new() -> DaemonState {
+        let now = get_unix_timestamp_secs();
+        DaemonState {
+            pid: std::process::id(),
+            started_at: now,
+            updated_at: now,
+            last_poll_at: None,
+            stopped: false,
+            keys: BTreeMap::new(),
+        }
+    }
+
+    pub fn key_mut(&mut self, sync_key: &str) -> &mut DaemonKeyState {
+        self.keys.entry(sync_key.to_string()).or_default()
+    }
+
+    pub fn write(&mut self, local_head_folder: &Path) -> Result<(), String> {
+        self.updated_at = get_unix_timestamp_secs();
+        let statestr = serde_json::to_string_pretty(self)
+            .map_err(|e| format!("Unable to serialize daemon state\n{e}"))?;
+        fs::write(get_daemon_state_filepath(local_head_folder), statestr)
+            .map_err(|e| format!("Unable to write daemon state\n{e}"))?;
+        Ok(())
+    }
+
+    pub fn print(&self) {
+        let status = match self.stopped {
+            true => "stopped",
+            false => "running",
+        };
+        println!(
+            "Daemon (pid {}) {status} - started {}, updated {}, last poll {}",
+            self.pid,
+            format_optional_timestamp(Some(self.started_at)),
+            format_optional_timestamp(Some(self.updated_at)),
+            format_optional_timestamp(self.last_poll_at)
+        );
+        for (sync_key, key_state) in self.keys.iter() {
+            let watching = match key_state.watching {
+                true => "watching",
+                false => "not watching",
+            };
+            println!("* {sync_key} ({watching} {})", key_state.save_folder);
+            println!(
+                "    last change: {}",
+                format_optional_timestamp(key_state.last_change_at)
+            );
+            println!(
+                "    last sync:   {} - {}",
+                format_optional_timestamp(key_state.last_sync_at),
+                key_state.last_action.as_deref().unwrap_or("-")
+            );
+            if let Some(error) = &key_state.last_error {
+                println!(
+                    "    last error:  {}",
+                    error.lines().next().unwrap_or_default()
+                );
+            }
+        }
+    }
+}
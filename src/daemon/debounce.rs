@@ -0,0 +1,77 @@
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+/// Tracks the last change seen per sync key. A key becomes due once no change was seen for the quiet period.
+pub struct Debouncer {
+    quiet_period: Duration,
+    last_change: HashMap<String, Instant>,
+}
+
+impl Debouncer {
+    pub fn new(quiet_period: Duration) -> Debouncer {
+        Debouncer {
+            quiet_period: quiet_period,
+            last_change: HashMap::new(),
+        }
+    }
+
+    pub fn record_change(&mut self, sync_key: &str, at: Instant) {
+        self.last_change.insert(sync_key.to_string(), at);
+    }
+
+    /// Whether the key has changes which are not due yet - e.g. the game is still writing.
+    pub fn is_dirty(&self, sync_key: &str) -> bool {
+        self.last_change.contains_key(sync_key)
+    }
+
+    /// Drops recorded changes - used for changes caused by the daemon itself (pulls).
+    pub fn forget(&mut self, sync_key: &str) {
+        self.last_change.remove(sync_key);
+    }
+
+    /// Removes and returns every key that has been quiet for at least the quiet period - sorted.
+    pub fn take_due(&mut self, now: Instant) -> Vec<String> {
+        let mut due: Vec<String> = self
+            .last_change
+            .iter()
+            .filter(|(_, last)| now.saturating_duration_since(**last) >= self.quiet_period)
+            .map(|(sync_key, _)| sync_key.clone())
+            .collect();
+        due.sort();
+        for sync_key in due.iter() {
+            self.last_change.remove(sync_key);
+        }
+        return due;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keys_are_due_once_quiet() {
+        let start = Instant::now();
+        let mut debouncer = Debouncer::new(Duration::from_secs(30));
+
+        debouncer.record_change("game1", start);
+        debouncer.record_change("game2", start);
+        debouncer.record_change("game1", start + Duration::from_secs(10));
+
+        assert_eq!(
+            debouncer.take_due(start + Duration::from_secs(35)),
+            vec![String::from("game2")]
+        );
+        assert!(debouncer.is_dirty("game1"));
+        assert!(!debouncer.is_dirty("game2"));
+        assert_eq!(
+            debouncer.take_due(start + Duration::from_secs(40)),
+            vec![String::from("game1")]
+        );
+        assert!(
+            debouncer
+                .take_due(start + Duration::from_secs(100))
+                .is_empty()
+        );
+    }
+}
@@ -0,0 +1,4 @@
+pub mod daemon_log;
+pub mod daemon_main;
+pub mod daemon_state;
+pub mod debounce;
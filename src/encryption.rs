@@ -0,0 +1,343 @@
+use argon2::Argon2;
+use base64::{Engine, engine::general_purpose::STANDARD};
+use chacha20poly1305::aead::rand_core::RngCore;
+use chacha20poly1305::aead::{Aead, OsRng, Payload};
+use chacha20poly1305::{AeadCore, KeyInit, XChaCha20Poly1305, XNonce};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::config::config_commons::LOCAL_ENCRYPTION_KEY_FILE_NAME;
+use crate::manifest::Manifest;
+
+/// Prefix of every encrypted file - lets pulls tell encrypted from plaintext remote files.
+pub const ENCRYPTED_FILE_MAGIC: &[u8; 6] = b"LCGE1\0";
+const KEY_LEN: usize = 32;
+const NONCE_LEN: usize = 24;
+const SALT_LEN: usize = 16;
+/// Known plaintext encrypted with the key and stored on the remote - detects a wrong passphrase on `keys init`.
+const KEY_CHECK_PLAINTEXT: &[u8] = b"local_cloud_game_sync key check";
+const KEY_CHECK_CONTEXT: &str = ".keycheck";
+
+/// Symmetric key used to encrypt saves client-side. Never leaves the device except through `keys export`.
+#[derive(Clone, PartialEq, Eq)]
+pub struct EncryptionKey([u8; KEY_LEN]);
+
+impl EncryptionKey {
+    /// Derives the key from a passphrase with Argon2id - the salt is shared by every device through the remote.
+    pub fn derive(passphrase: &str, salt: &[u8]) -> Result<EncryptionKey, String> {
+        if passphrase.is_empty() {
+            return Err(String::from("Passphrase must not be empty"));
+        }
+        let mut key = [0u8; KEY_LEN];
+        Argon2::default()
+            .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+            .map_err(|e| format!("Unable to derive encryption key\n{e}"))?;
+        Ok(EncryptionKey(key))
+    }
+
+    pub fn export(&self) -> String {
+        return STANDARD.encode(self.0);
+    }
+
+    pub fn import(exported: &str) -> Result<EncryptionKey, String> {
+        let bytes = STANDARD
+            .decode(exported.trim())
+            .map_err(|e| format!("Invalid exported key\n{e}"))?;
+        let key: [u8; KEY_LEN] = bytes
+            .try_into()
+            .map_err(|_| format!("Invalid exported key - expected {KEY_LEN} bytes"))?;
+        Ok(EncryptionKey(key))
+    }
+
+    /// Encrypts a file's contents. `context` (the relative path) is authenticated - so files cannot be swapped around.
+    pub fn encrypt(&self, context: &str, plaintext: &[u8]) -> Result<Vec<u8>, String> {
+        let cipher = XChaCha20Poly1305::new(&self.0.into());
+        let nonce = XChaCha20Poly1305::generate_nonce(&mut OsRng);
+        let ciphertext = cipher
+            .encrypt(
+                &nonce,
+                Payload {
+                    msg: plaintext,
+                    aad: context.as_bytes(),
+                },
+            )
+            .map_err(|_| format!("Unable to encrypt {context}"))?;
+
+        let mut out = Vec::with_capacity(ENCRYPTED_FILE_MAGIC.len() + NONCE_LEN + ciphertext.len());
+        out.extend_from_slice(ENCRYPTED_FILE_MAGIC);
+        out.extend_from_slice(&nonce);
+        out.extend_from_slice(&ciphertext);
+        Ok(out)
+    }
+
+    pub fn decrypt(&self, context: &str, data: &[u8]) -> Result<Vec<u8>, String> {
+        let header_len = ENCRYPTED_FILE_MAGIC.len() + NONCE_LEN;
+        if !is_encrypted(data) || data.len() < header_len {
+            return Err(format!("{context} is not an encrypted file"));
+        }
+
+        let cipher = XChaCha20Poly1305::new(&self.0.into());
+        let nonce: [u8; NONCE_LEN] = data[ENCRYPTED_FILE_MAGIC.len()..header_len]
+            .try_into()
+            .map_err(|_| format!("{context} has an invalid encryption header"))?;
+        cipher
+            .decrypt(
+                &XNonce::from(nonce),
+                Payload {
+                    msg: &data[header_len..],
+                    aad: context.as_bytes(),
+                },
+            )
+            .map_err(|_| {
+                format!("Unable to decrypt {context} - wrong encryption key or corrupted file")
+            })
+    }
+}
+
+pub fn is_encrypted(data: &[u8]) -> bool {
+    return data.starts_with(ENCRYPTED_FILE_MAGIC);
+}
+
+/// Decrypts a pulled file. Encrypted files without a key are refused - storing ciphertext as a save would corrupt it.
+/// With a key, plaintext files are refused too - otherwise a tampered remote could swap in any file.
+/// `allow_plaintext` accepts them - to migrate saves pushed before encryption was enabled.
+pub fn decrypt_pulled_file(
+    key: Option<&EncryptionKey>,
+    allow_plaintext: bool,
+    relpath: &str,
+    data: Vec<u8>,
+) -> Result<Vec<u8>, String> {
+    if !is_encrypted(&data) {
+        if key.is_some() && !allow_plaintext {
+            return Err(format!(
+                "Remote file {relpath} is not encrypted although encryption is enabled - if it was pushed before enabling encryption, pull once with --allow-unencrypted"
+            ));
+        }
+        return Ok(data);
+    }
+    match key {
+        Some(key) => key.decrypt(relpath, &data),
+        None => Err(format!(
+            "Remote file {relpath} is encrypted - enable \"encrypt\" in the config and run `keys init`"
+        )),
+    }
+}
+
+/// Manifest as stored on the remote. Encrypted with a key - its per-file digests would reveal known save contents.
+/// `sync_key` is authenticated - so manifests cannot be swapped between games.
+pub fn encrypt_remote_manifest(
+    key: Option<&EncryptionKey>,
+    sync_key: &str,
+    manifest: &Manifest,
+) -> Result<Vec<u8>, String> {
+    let serialized = manifest.serialize()?;
+    match key {
+        Some(key) => key.encrypt(&format!("{sync_key}.MANIFEST"), serialized.as_bytes()),
+        None => Ok(serialized.into_bytes()),
+    }
+}
+
+/// Reads a manifest written by `encrypt_remote_manifest` - plaintext ones follow the rules of `decrypt_pulled_file`.
+pub fn decrypt_remote_manifest(
+    key: Option<&EncryptionKey>,
+    allow_plaintext: bool,
+    sync_key: &str,
+    data: Vec<u8>,
+) -> Result<Manifest, String> {
+    let data = decrypt_pulled_file(key, allow_plaintext, &format!("{sync_key}.MANIFEST"), data)?;
+    let manifeststr =
+        String::from_utf8(data).map_err(|e| format!("Unable to read file MANIFEST {}", e))?;
+    Manifest::deserialize(manifeststr.trim())
+}
+
+/// Key derivation parameters stored on the remote - shared by every device of the same remote.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct RemoteKeyParams {
+    salt: String,
+    key_check: String,
+}
+
+impl RemoteKeyParams {
+    /// Generates a fresh random salt - only used when the remote has no parameters yet.
+    pub fn generate_salt() -> Vec<u8> {
+        let mut salt = vec![0u8; SALT_LEN];
+        OsRng.fill_bytes(&mut salt);
+        return salt;
+    }
+
+    pub fn new(salt: &[u8], key: &EncryptionKey) -> Result<RemoteKeyParams, String> {
+        Ok(RemoteKeyParams {
+            salt: STANDARD.encode(salt),
+            key_check: STANDARD.encode(key.encrypt(KEY_CHECK_CONTEXT, KEY_CHECK_PLAINTEXT)?),
+        })
+    }
+
+    pub fn salt(&self) -> Result<Vec<u8>, String> {
+        STANDARD
+            .decode(&self.salt)
+            .map_err(|e| format!("Invalid salt in remote key parameters\n{e}"))
+    }
+
+    /// Whether the key is the one these parameters were created with.
+    pub fn verify(&self, key: &EncryptionKey) -> Result<bool, String> {
+        let check = STANDARD
+            .decode(&self.key_check)
+            .map_err(|e| format!("Invalid key check in remote key parameters\n{e}"))?;
+        Ok(key
+            .decrypt(KEY_CHECK_CONTEXT, &check)
+            .is_ok_and(|plaintext| plaintext == KEY_CHECK_PLAINTEXT))
+    }
+
+    pub fn serialize(&self) -> Result<String, String> {
+        serde_json::to_string(self).map_err(|e| format!("Unable to serialize key parameters\n{e}"))
+    }
+
+    pub fn deserialize(input: &str) -> Result<RemoteKeyParams, String> {
+        serde_json::from_str(input.trim())
+            .map_err(|e| format!("Unable to parse remote key parameters\n{e}"))
+    }
+}
+
+/// One key per local head folder - shared by every entry of the global config using it.
+pub fn get_local_key_filepath(local_head_folder: &Path) -> PathBuf {
+    return local_head_folder.join(LOCAL_ENCRYPTION_KEY_FILE_NAME);
+}
+
+pub fn read_local_key(local_head_folder: &Path) -> Result<Option<EncryptionKey>, String> {
+    let key_path = get_local_key_filepath(local_head_folder);
+    if !key_path.exists() {
+        return Ok(None);
+    }
+
+    let keystr = fs::read_to_string(&key_path)
+        .map_err(|e| format!("Unable to read encryption key {}\n{e}", key_path.display()))?;
+    Ok(Some(EncryptionKey::import(&keystr)?))
+}
+
+/// Stores the key next to the local HEADs - readable by the current user only.
+pub fn write_local_key(local_head_folder: &Path, key: &EncryptionKey) -> Result<(), String> {
+    let key_path = get_local_key_filepath(local_head_folder);
+    fs::write(&key_path, format!("{}\n", key.export()))
+        .map_err(|e| format!("Unable to store encryption key\n{e}"))?;
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(&key_path, fs::Permissions::from_mode(0o600))
+            .map_err(|e| format!("Unable to restrict encryption key permissions\n{e}"))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_key() -> EncryptionKey {
+        EncryptionKey::derive("correct horse battery staple", b"0123456789abcdef").unwrap()
+    }
+
+    #[test]
+    fn encrypt_roundtrip() {
+        let key = test_key();
+        let encrypted = key.encrypt("saves/slot1.sav", b"save data").unwrap();
+
+        assert!(is_encrypted(&encrypted));
+        assert!(!encrypted.windows(9).any(|w| w == b"save data"));
+        assert_eq!(
+            key.decrypt("saves/slot1.sav", &encrypted).unwrap(),
+            b"save data"
+        );
+    }
+
+    #[test]
+    fn derivation_is_deterministic_per_salt() {
+        assert!(test_key() == test_key());
+        let other_salt = EncryptionKey::derive("correct horse battery staple", b"fedcba9876543210");
+        assert!(other_salt.unwrap() != test_key());
+        assert!(EncryptionKey::derive("", b"0123456789abcdef").is_err());
+    }
+
+    #[test]
+    fn decrypt_rejects_wrong_key_and_swapped_files() {
+        let key = test_key();
+        let other = EncryptionKey::derive("wrong", b"0123456789abcdef").unwrap();
+        let encrypted = key.encrypt("slot1.sav", b"save data").unwrap();
+
+        assert!(other.decrypt("slot1.sav", &encrypted).is_err());
+        assert!(key.decrypt("slot2.sav", &encrypted).is_err());
+    }
+
+    #[test]
+    fn pulled_plaintext_needs_explicit_migration() {
+        let key = test_key();
+        let encrypted = key.encrypt("slot1.sav", b"save data").unwrap();
+
+        let error = decrypt_pulled_file(Some(&key), false, "slot1.sav", b"plain".to_vec())
+            .err()
+            .unwrap();
+        assert!(error.contains("is not encrypted"), "{error}");
+        assert_eq!(
+            decrypt_pulled_file(Some(&key), true, "slot1.sav", b"plain".to_vec()).unwrap(),
+            b"plain"
+        );
+        assert_eq!(
+            decrypt_pulled_file(None, false, "slot1.sav", b"plain".to_vec()).unwrap(),
+            b"plain"
+        );
+        assert_eq!(
+            decrypt_pulled_file(Some(&key), false, "slot1.sav", encrypted.clone()).unwrap(),
+            b"save data"
+        );
+        assert!(decrypt_pulled_file(None, true, "slot1.sav", encrypted).is_err());
+    }
+
+    #[test]
+    fn remote_manifest_is_encrypted_per_sync_key() {
+        let key = test_key();
+        let mut manifest = Manifest::new(crate::hashing::HashAlgorithm::Blake3);
+        manifest
+            .files
+            .insert(String::from("slot1.sav"), String::from("abcdef0123456789"));
+
+        let encrypted = encrypt_remote_manifest(Some(&key), "game-a", &manifest).unwrap();
+        assert!(is_encrypted(&encrypted));
+        assert!(
+            !encrypted
+                .windows(9)
+                .any(|window| window == b"slot1.sav" || window == b"abcdef012")
+        );
+        assert_eq!(
+            decrypt_remote_manifest(Some(&key), false, "game-a", encrypted.clone())
+                .unwrap()
+                .files,
+            manifest.files
+        );
+        // Swapped in from another game.
+        assert!(decrypt_remote_manifest(Some(&key), false, "game-b", encrypted).is_err());
+
+        let plaintext = encrypt_remote_manifest(None, "game-a", &manifest).unwrap();
+        assert!(decrypt_remote_manifest(Some(&key), false, "game-a", plaintext.clone()).is_err());
+        assert!(decrypt_remote_manifest(None, false, "game-a", plaintext).is_ok());
+    }
+
+    #[test]
+    fn key_export_and_params_roundtrip() {
+        let key = test_key();
+        assert!(EncryptionKey::import(&key.export()).unwrap() == key);
+        assert!(EncryptionKey::import("c2hvcnQ=").is_err());
+
+        let salt = RemoteKeyParams::generate_salt();
+        let params = RemoteKeyParams::new(&salt, &key).unwrap();
+        let parsed = RemoteKeyParams::deserialize(&params.serialize().unwrap()).unwrap();
+        assert_eq!(parsed.salt().unwrap(), salt);
+        assert!(parsed.verify(&key).unwrap());
+        assert!(
+            !parsed
+                .verify(&EncryptionKey::derive("wrong", &salt).unwrap())
+                .unwrap()
+        );
+    }
+}
@@ -0,0 +1,253 @@
+use crate::cancellation::CancellationToken;
+use crate::config::RuntimeSyncConfig;
+use std::fmt;
+use std::process::Command;
+use std::thread;
+use std::time::Duration;
+
+/// Linux truncates `/proc/<pid>/comm` to 15 bytes.
+const PROC_COMM_MAX_LEN: usize = 15;
+const GAME_PROCESS_POLL_INTERVAL: Duration = Duration::from_secs(2);
+const CANCEL_CHECK_INTERVAL: Duration = Duration::from_millis(200);
+
+/// A running process matching one of the entry's `processNames`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RunningGameProcess {
+    pub pid: u32,
+    /// The configured process name it matched.
+    pub name: String,
+}
+
+impl fmt::Display for RunningGameProcess {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} (pid {})", self.name, self.pid)
+    }
+}
+
+pub fn format_running_processes(running: &[RunningGameProcess]) -> String {
+    running
+        .iter()
+        .map(|process| process.to_string())
+        .collect::<Vec<String>>()
+        .join(", ")
+}
+
+/// Last component of a Linux or Windows (Proton/Wine) path.
+fn path_basename(path: &str) -> &str {
+    path.rsplit(['/', '\\']).next().unwrap_or(path)
+}
+
+/// Configured name matched by a process - compared case-insensitively against its `comm` and the basename of argv[0].
+fn match_process_name<'a>(
+    process_names: &'a [String],
+    comm: &str,
+    argv0: &str,
+) -> Option<&'a String> {
+    let exe_name = path_basename(argv0);
+    process_names.iter().find(|name| {
+        let truncated: String = name.chars().take(PROC_COMM_MAX_LEN).collect();
+        name.eq_ignore_ascii_case(exe_name) || truncated.eq_ignore_ascii_case(comm)
+    })
+}
+
+#[cfg(target_os = "linux")]
+fn list_matching_processes(process_names: &[String]) -> Result<Vec<RunningGameProcess>, String> {
+    use std::fs;
+
+    let proc_entries =
+        fs::read_dir("/proc").map_err(|e| format!("Unable to list running processes\n{e}"))?;
+    let own_pid = std::process::id();
+
+    let mut running = vec![];
+    for entry in proc_entries.flatten() {
+        let Some(pid) = entry
+            .file_name()
+            .to_str()
+            .and_then(|s| s.parse::<u32>().ok())
+        else {
+            continue;
+        };
+        if pid == own_pid {
+            continue;
+        }
+        // Processes may exit while being read - those are skipped.
+        let comm = fs::read_to_string(entry.path().join("comm")).unwrap_or_default();
+        let cmdline = fs::read(entry.path().join("cmdline")).unwrap_or_default();
+        let argv0 = cmdline.split(|b| *b == 0).next().unwrap_or_default();
+        let argv0 = String::from_utf8_lossy(argv0);
+
+        if let Some(name) = match_process_name(process_names, comm.trim_end(), &argv0) {
+            running.push(RunningGameProcess {
+                pid: pid,
+                name: name.clone(),
+            });
+        }
+    }
+    running.sort_by_key(|process| process.pid);
+    return Ok(running);
+}
+
+// TODO: Process detection is only implemented through /proc for now.
+// Errors instead of reporting nothing as running - syncs would go through while the game writes its saves.
+#[cfg(not(target_os = "linux"))]
+fn list_matching_processes(_process_names: &[String]) -> Result<Vec<RunningGameProcess>, String> {
+    return Err(String::from(
+        "\"processNames\" is only supported on Linux - remove it from the entry to sync on this device",
+    ));
+}
+
+/// Running processes matching the entry's `processNames` - empty when none are configured.
+/// Errors where running processes can't be detected.
+pub fn find_running_game_processes(
+    sync_config: &RuntimeSyncConfig,
+) -> Result<Vec<RunningGameProcess>, String> {
+    if sync_config.process_names.is_empty() {
+        return Ok(vec![]);
+    }
+    return list_matching_processes(&sync_config.process_names);
+}
+
+/// Errors while the game is running - for operations reading or overwriting saves it may still be writing.
+/// `action` names the operation - e.g. "pulling".
+pub fn ensure_game_not_running(
+    sync_config: &RuntimeSyncConfig,
+    action: &str,
+) -> Result<(), String> {
+    let running = find_running_game_processes(sync_config)?;
+    if !running.is_empty() {
+        return Err(format!(
+            "{} is still running: {} - close the game before {action} saves",
+            sync_config.game_display_name,
+            format_running_processes(&running)
+        ));
+    }
+    Ok(())
+}
+
+/// Blocks until no matching game process is running. `on_waiting` is called once if it has to wait.
+pub fn wait_for_game_to_close<F>(
+    sync_config: &RuntimeSyncConfig,
+    cancel: &CancellationToken,
+    on_waiting: F,
+) -> Result<(), String>
+where
+    F: FnOnce(&[RunningGameProcess]),
+{
+    let mut on_waiting = Some(on_waiting);
+    loop {
+        let running = find_running_game_processes(sync_config)?;
+        if running.is_empty() {
+            return Ok(());
+        }
+        if let Some(on_waiting) = on_waiting.take() {
+            on_waiting(&running);
+        }
+
+        let mut waited = Duration::ZERO;
+        while waited < GAME_PROCESS_POLL_INTERVAL {
+            cancel.check()?;
+            thread::sleep(CANCEL_CHECK_INTERVAL);
+            waited += CANCEL_CHECK_INTERVAL;
+        }
+    }
+}
+
+/// Exit code of a `waitpid` status - shells report signals as 128 + signal.
+#[cfg(target_os = "linux")]
+fn wait_status_to_exit_code(status: i32) -> i32 {
+    if libc::WIFEXITED(status) {
+        return libc::WEXITSTATUS(status);
+    }
+    if libc::WIFSIGNALED(status) {
+        return 128 + libc::WTERMSIG(status);
+    }
+    return 1;
+}
+
+/// Runs the command and waits for it and every process it started - launchers often exit right after starting the game.
+/// Returns the exit code of the command itself.
+#[cfg(target_os = "linux")]
+pub fn run_and_wait_for_process_tree(command: &mut Command) -> Result<i32, String> {
+    use std::io;
+
+    // Orphaned descendants get re-parented to this process instead of init - so they can be waited for.
+    if unsafe { libc::prctl(libc::PR_SET_CHILD_SUBREAPER, 1, 0, 0, 0) } != 0 {
+        return Err(format!(
+            "Unable to become child subreaper\n{}",
+            io::Error::last_os_error()
+        ));
+    }
+
+    let child = command
+        .spawn()
+        .map_err(|e| format!("Unable to launch game\n{e}"))?;
+    let game_pid = child.id() as i32;
+
+    let mut game_exit_code = None;
+    loop {
+        let mut status = 0;
+        let pid = unsafe { libc::waitpid(-1, &mut status, 0) };
+        if pid == -1 {
+            let e = io::Error::last_os_error();
+            match e.raw_os_error() {
+                Some(libc::EINTR) => continue,
+                Some(libc::ECHILD) => break, // no descendants left
+                _ => return Err(format!("Unable to wait for game processes\n{e}")),
+            }
+        }
+        if pid == game_pid {
+            game_exit_code = Some(wait_status_to_exit_code(status));
+        }
+    }
+
+    return game_exit_code.ok_or(String::from("Game process exit status was lost"));
+}
+
+// TODO: Descendants are only tracked on Linux for now - elsewhere use `processNames` to wait for them.
+#[cfg(not(target_os = "linux"))]
+pub fn run_and_wait_for_process_tree(command: &mut Command) -> Result<i32, String> {
+    let status = command
+        .spawn()
+        .map_err(|e| format!("Unable to launch game\n{e}"))?
+        .wait()
+        .map_err(|e| format!("Unable to wait for game\n{e}"))?;
+    return Ok(status.code().unwrap_or(1));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_comm_and_windows_executables() {
+        let names = vec![
+            String::from("Game.exe"),
+            String::from("averyveryverylongname"),
+        ];
+
+        assert_eq!(
+            match_process_name(&names, "wine64-preload", "Z:\\Games\\Foo\\game.exe"),
+            Some(&names[0])
+        );
+        assert_eq!(
+            match_process_name(&names, "averyveryverylo", "/opt/bin/launcher"),
+            Some(&names[1])
+        );
+        assert_eq!(match_process_name(&names, "bash", "/usr/bin/bash"), None);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn finds_running_processes_through_proc() {
+        let mut child = std::process::Command::new("sleep")
+            .arg("5")
+            .spawn()
+            .unwrap();
+
+        let running = list_matching_processes(&[String::from("sleep")]).unwrap();
+        child.kill().unwrap();
+        child.wait().unwrap();
+
+        assert!(running.iter().any(|process| process.pid == child.id()));
+    }
+}
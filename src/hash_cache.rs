@@ -0,0 +1,159 @@
+use crate::hashing::HashAlgorithm;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs::Metadata;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Files modified this recently are not cached - they might still change within the same mtime tick.
+const RACY_MTIME_WINDOW: Duration = Duration::from_secs(2);
+
+/// Identity of a file version - if none of these changed, the contents are assumed unchanged.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct FileStamp {
+    pub size: u64,
+    pub mtime_secs: u64,
+    pub mtime_nanos: u32,
+    /// 0 where not available (Windows).
+    pub inode: u64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct HashCacheEntry {
+    pub stamp: FileStamp,
+    pub algorithm: HashAlgorithm,
+    pub digest: String,
+}
+
+/// Per-key cache of file digests - keyed by path relative to the save folder.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct HashCache {
+    pub entries: BTreeMap<String, HashCacheEntry>,
+}
+
+impl FileStamp {
+    pub fn from_metadata(metadata: &Metadata) -> Option<FileStamp> {
+        let mtime = metadata.modified().ok()?.duration_since(UNIX_EPOCH).ok()?;
+        Some(FileStamp {
+            size: metadata.len(),
+            mtime_secs: mtime.as_secs(),
+            mtime_nanos: mtime.subsec_nanos(),
+            inode: inode_of(metadata),
+        })
+    }
+
+    fn is_racy(&self) -> bool {
+        let mtime = UNIX_EPOCH + Duration::new(self.mtime_secs, self.mtime_nanos);
+        SystemTime::now()
+            .duration_since(mtime)
+            .map_or(true, |age| age < RACY_MTIME_WINDOW)
+    }
+}
+
+#[cfg(unix)]
+fn inode_of(metadata: &Metadata) -> u64 {
+    use std::os::unix::fs::MetadataExt;
+    metadata.ino()
+}
+
+#[cfg(not(unix))]
+fn inode_of(_metadata: &Metadata) -> u64 {
+    0
+}
+
+impl HashCache {
+    /// Returns the cached digest if the file is unchanged since it was cached.
+    pub fn get(&self, relpath: &str, stamp: &FileStamp, algorithm: HashAlgorithm) -> Option<&str> {
+        self.entries
+            .get(relpath)
+            .filter(|entry| entry.algorithm == algorithm && entry.stamp == *stamp)
+            .map(|entry| entry.digest.as_str())
+    }
+
+    pub fn insert(
+        &mut self,
+        relpath: String,
+        stamp: FileStamp,
+        algorithm: HashAlgorithm,
+        digest: String,
+    ) {
+        if stamp.is_racy() {
+            return;
+        }
+        self.entries.insert(
+            relpath,
+            HashCacheEntry {
+                stamp,
+                algorithm,
+                digest,
+            },
+        );
+    }
+
+    pub fn serialize(&self) -> Result<String, String> {
+        serde_json::to_string(self).map_err(|e| format!("Unable to serialize hash cache\n{e}"))
+    }
+
+    pub fn deserialize(s: &str) -> Result<Self, String> {
+        serde_json::from_str(s).map_err(|e| format!("Invalid hash cache\n{e}"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stamp(size: u64, mtime_secs: u64) -> FileStamp {
+        FileStamp {
+            size,
+            mtime_secs,
+            mtime_nanos: 0,
+            inode: 42,
+        }
+    }
+
+    #[test]
+    fn cached_digest_requires_same_stamp_and_algorithm() {
+        let mut cache = HashCache::default();
+        cache.insert(
+            "slot1.sav".to_string(),
+            stamp(10, 1000),
+            HashAlgorithm::Blake3,
+            "abc".to_string(),
+        );
+
+        assert_eq!(
+            cache.get("slot1.sav", &stamp(10, 1000), HashAlgorithm::Blake3),
+            Some("abc")
+        );
+        assert_eq!(
+            cache.get("slot1.sav", &stamp(11, 1000), HashAlgorithm::Blake3),
+            None
+        );
+        assert_eq!(
+            cache.get("slot1.sav", &stamp(10, 1001), HashAlgorithm::Blake3),
+            None
+        );
+        assert_eq!(
+            cache.get("slot1.sav", &stamp(10, 1000), HashAlgorithm::Md5),
+            None
+        );
+        assert_eq!(
+            cache.get("slot2.sav", &stamp(10, 1000), HashAlgorithm::Blake3),
+            None
+        );
+    }
+
+    #[test]
+    fn recently_modified_files_are_not_cached() {
+        let mut cache = HashCache::default();
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap();
+        cache.insert(
+            "slot1.sav".to_string(),
+            stamp(10, now.as_secs()),
+            HashAlgorithm::Blake3,
+            "abc".to_string(),
+        );
+
+        assert!(cache.entries.is_empty());
+    }
+}
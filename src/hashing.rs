@@ -0,0 +1,176 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufReader, Read};
+use std::path::Path;
+
+const CHECKSUM_BUFFER_MB: usize = 5;
+
+/// Algorithm used to digest save files - recorded on every HEAD and manifest.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum HashAlgorithm {
+    /// Legacy algorithm - anything without a recorded algorithm was hashed with it.
+    #[default]
+    Md5,
+    Blake3,
+}
+
+/// Algorithm used for new HEADs unless configured otherwise.
+pub const PREFERRED_HASH_ALGORITHM: HashAlgorithm = HashAlgorithm::Blake3;
+
+impl fmt::Display for HashAlgorithm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HashAlgorithm::Md5 => write!(f, "md5"),
+            HashAlgorithm::Blake3 => write!(f, "blake3"),
+        }
+    }
+}
+
+impl HashAlgorithm {
+    pub fn digest_bytes(&self, data: &[u8]) -> String {
+        match self {
+            HashAlgorithm::Md5 => format!("{:x}", md5::compute(data)),
+            HashAlgorithm::Blake3 => blake3::hash(data).to_hex().to_string(),
+        }
+    }
+
+    /// Incremental digest - yields the same digest as `digest_file` once fed the whole file.
+    pub fn stream_digest(&self) -> StreamDigest {
+        match self {
+            HashAlgorithm::Md5 => StreamDigest::Md5 {
+                chunk: md5::Context::new(),
+                chunk_len: 0,
+                chunk_digests: Vec::new(),
+            },
+            HashAlgorithm::Blake3 => StreamDigest::Blake3(Box::new(blake3::Hasher::new())),
+        }
+    }
+
+    /// Computes a deterministic digest of the file contents.
+    pub fn digest_file(&self, path: &Path) -> io::Result<String> {
+        match self {
+            HashAlgorithm::Md5 => digest_file_md5(path),
+            HashAlgorithm::Blake3 => {
+                let mut hasher = blake3::Hasher::new();
+                hasher.update_reader(File::open(path)?)?;
+                Ok(hasher.finalize().to_hex().to_string())
+            }
+        }
+    }
+}
+
+/// Digest of data fed in pieces - see `HashAlgorithm::stream_digest`.
+pub enum StreamDigest {
+    Md5 {
+        chunk: md5::Context,
+        chunk_len: usize,
+        chunk_digests: Vec<u8>,
+    },
+    Blake3(Box<blake3::Hasher>),
+}
+
+impl StreamDigest {
+    pub fn update(&mut self, mut data: &[u8]) {
+        match self {
+            StreamDigest::Md5 {
+                chunk,
+                chunk_len,
+                chunk_digests,
+            } => {
+                while !data.is_empty() {
+                    let take = data
+                        .len()
+                        .min(CHECKSUM_BUFFER_MB * 1024 * 1024 - *chunk_len);
+                    chunk.consume(&data[..take]);
+                    *chunk_len += take;
+                    data = &data[take..];
+                    if *chunk_len == CHECKSUM_BUFFER_MB * 1024 * 1024 {
+                        let full_chunk = std::mem::replace(chunk, md5::Context::new());
+                        chunk_digests.extend_from_slice(&full_chunk.finalize().0);
+                        *chunk_len = 0;
+                    }
+                }
+            }
+            StreamDigest::Blake3(hasher) => {
+                hasher.update(data);
+            }
+        }
+    }
+
+    pub fn finalize(self) -> String {
+        match self {
+            StreamDigest::Md5 {
+                chunk,
+                chunk_len,
+                mut chunk_digests,
+            } => {
+                if chunk_len > 0 {
+                    chunk_digests.extend_from_slice(&chunk.finalize().0);
+                }
+                format!("{:x}", md5::compute(chunk_digests))
+            }
+            StreamDigest::Blake3(hasher) => hasher.finalize().to_hex().to_string(),
+        }
+    }
+}
+
+/// Computes a deterministic hash by calculating the md5 of each 5mb chunks of file
+fn digest_file_md5(path: &Path) -> io::Result<String> {
+    let file = File::open(path)?;
+    let mut reader = BufReader::new(file);
+    let mut buffer = vec![0u8; CHECKSUM_BUFFER_MB * 1024 * 1024];
+    let mut chunk_digests: Vec<u8> = Vec::new();
+
+    loop {
+        let n = reader.read(&mut buffer)?;
+        if n == 0 {
+            break;
+        }
+        let digest = md5::compute(&buffer[..n]);
+        chunk_digests.extend_from_slice(&digest.0); // append raw 16 bytes
+    }
+
+    // Hash the concatenated digests to get a final checksum
+    let final_digest = md5::compute(&chunk_digests);
+    Ok(format!("{:x}", final_digest))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn digests_differ_per_algorithm() {
+        let md5 = HashAlgorithm::Md5.digest_bytes(b"save");
+        let blake3 = HashAlgorithm::Blake3.digest_bytes(b"save");
+
+        assert_eq!(md5.len(), 32);
+        assert_eq!(blake3.len(), 64);
+        assert_eq!(md5, HashAlgorithm::Md5.digest_bytes(b"save"));
+        assert_eq!(blake3, HashAlgorithm::Blake3.digest_bytes(b"save"));
+    }
+
+    #[test]
+    fn stream_digest_matches_file_digest() {
+        let path = std::env::temp_dir().join(format!(
+            "local_cloud_game_sync_stream_digest_test_{}",
+            std::process::id()
+        ));
+        // Spans several md5 chunks - fed in pieces not aligned to them.
+        let data: Vec<u8> = (0..CHECKSUM_BUFFER_MB * 1024 * 1024 * 2 + 1234)
+            .map(|i| (i % 251) as u8)
+            .collect();
+        std::fs::write(&path, &data).unwrap();
+
+        for algorithm in [HashAlgorithm::Md5, HashAlgorithm::Blake3] {
+            let mut digest = algorithm.stream_digest();
+            for piece in data.chunks(1_000_003) {
+                digest.update(piece);
+            }
+            assert_eq!(digest.finalize(), algorithm.digest_file(&path).unwrap());
+        }
+        std::fs::remove_file(&path).unwrap();
+    }
+}
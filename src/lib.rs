@@ -1,8 +1,22 @@
+pub mod cancellation;
 pub mod commands;
 pub mod common;
 pub mod config;
+pub mod daemon;
+pub mod encryption;
+pub mod game_process;
+pub mod hash_cache;
+pub mod hashing;
 pub mod local_head;
+pub mod log_file;
+pub mod manifest;
+pub mod pending_sync;
+pub mod progress;
+pub mod proton;
 pub mod remote_save_client;
+pub mod save_database;
+pub mod steam;
+pub mod sync_history;
 pub mod tree_utils;
 pub mod ui;
 pub mod utils;
@@ -1,9 +1,14 @@
 use std::fs;
 use std::path::{Path, PathBuf};
 
-use crate::common::Revision;
+use crate::common::{Revision, deserialize_revision_log};
 use crate::config::RuntimeSyncConfig;
-use crate::tree_utils::tree_folder_hash;
+use crate::hash_cache::HashCache;
+use crate::hashing::HashAlgorithm;
+use crate::manifest::Manifest;
+use crate::sync_history::{SyncEvent, deserialize_sync_history};
+use crate::tree_utils::tree_folder_manifest_cached;
+use std::io::Write;
 
 pub fn get_local_head_filepath(sync_config: &RuntimeSyncConfig) -> PathBuf {
     return sync_config
@@ -38,15 +43,214 @@ pub fn read_local_head(sync_config: &RuntimeSyncConfig) -> Result<Option<Revisio
     Ok(Some(rev))
 }
 
+/// Rewrites the local head in the current format if it was written in a legacy format.
+/// Returns whether it was rewritten.
+pub fn migrate_local_head(sync_config: &RuntimeSyncConfig) -> Result<bool, String> {
+    let local_head_path = get_local_head_filepath(sync_config);
+    if !local_head_path.exists() {
+        return Ok(false);
+    }
+
+    let headstr = fs::read_to_string(local_head_path)
+        .map_err(|e| format!("Unable to read local head hash\n{e}"))?;
+    if !Revision::is_legacy_format(&headstr) {
+        return Ok(false);
+    }
+
+    write_local_head(sync_config, &Revision::deserialize(&headstr)?)?;
+    Ok(true)
+}
+
+pub fn get_local_manifest_filepath(sync_config: &RuntimeSyncConfig) -> PathBuf {
+    return sync_config
+        .local_head_folder
+        .join(format!("{}.MANIFEST", &sync_config.remote_sync_key));
+}
+
+/// Stores the manifest of the last synced revision - used as merge base. None clears it.
+pub fn write_local_manifest(
+    sync_config: &RuntimeSyncConfig,
+    manifest: Option<&Manifest>,
+) -> Result<(), String> {
+    let local_manifest_path = get_local_manifest_filepath(sync_config);
+
+    let Some(manifest) = manifest else {
+        if local_manifest_path.exists() {
+            fs::remove_file(local_manifest_path)
+                .map_err(|e| format!("Unable to clear local manifest\n{e}"))?;
+        }
+        return Ok(());
+    };
+
+    fs::write(local_manifest_path, manifest.serialize()?)
+        .map_err(|e| format!("Unable to update local manifest\n{e}"))?;
+    Ok(())
+}
+
+pub fn read_local_manifest(sync_config: &RuntimeSyncConfig) -> Result<Option<Manifest>, String> {
+    let local_manifest_path = get_local_manifest_filepath(sync_config);
+    if !local_manifest_path.exists() {
+        return Ok(None);
+    }
+
+    let manifeststr = fs::read_to_string(local_manifest_path)
+        .map_err(|e| format!("Unable to read local manifest\n{e}"))?;
+
+    Ok(Some(Manifest::deserialize(&manifeststr)?))
+}
+
+pub fn get_local_history_filepath(sync_config: &RuntimeSyncConfig) -> PathBuf {
+    return sync_config
+        .local_head_folder
+        .join(format!("{}.LOG", &sync_config.remote_sync_key));
+}
+
+/// Stores a local copy of the remote revision log - so history is available offline.
+pub fn write_local_history(
+    sync_config: &RuntimeSyncConfig,
+    history: &[Revision],
+) -> Result<(), String> {
+    let local_history_path = get_local_history_filepath(sync_config);
+    let contents: String = history
+        .iter()
+        .map(|rev| format!("{}\n", rev.serialize_log_entry()))
+        .collect();
+
+    fs::write(local_history_path, contents)
+        .map_err(|e| format!("Unable to update local revision log\n{e}"))?;
+    Ok(())
+}
+
+pub fn read_local_history(sync_config: &RuntimeSyncConfig) -> Result<Vec<Revision>, String> {
+    let local_history_path = get_local_history_filepath(sync_config);
+    if !local_history_path.exists() {
+        return Ok(vec![]);
+    }
+
+    let historystr = fs::read_to_string(local_history_path)
+        .map_err(|e| format!("Unable to read local revision log\n{e}"))?;
+
+    deserialize_revision_log(&historystr)
+}
+
+pub fn get_local_journal_filepath(sync_config: &RuntimeSyncConfig) -> PathBuf {
+    return sync_config
+        .local_head_folder
+        .join(format!("{}.JOURNAL", &sync_config.remote_sync_key));
+}
+
+/// Appends a sync event performed by this device to the local journal.
+pub fn append_local_journal(
+    sync_config: &RuntimeSyncConfig,
+    event: &SyncEvent,
+) -> Result<(), String> {
+    let local_journal_path = get_local_journal_filepath(sync_config);
+    let mut file = fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(local_journal_path)
+        .map_err(|e| format!("Unable to open local journal\n{e}"))?;
+
+    writeln!(file, "{}", event.serialize()?)
+        .map_err(|e| format!("Unable to update local journal\n{e}"))?;
+    Ok(())
+}
+
+pub fn read_local_journal(sync_config: &RuntimeSyncConfig) -> Result<Vec<SyncEvent>, String> {
+    let local_journal_path = get_local_journal_filepath(sync_config);
+    if !local_journal_path.exists() {
+        return Ok(vec![]);
+    }
+
+    let journalstr = fs::read_to_string(local_journal_path)
+        .map_err(|e| format!("Unable to read local journal\n{e}"))?;
+
+    deserialize_sync_history(&journalstr)
+}
+
+/// Hashes the current local files with the configured algorithm.
 pub fn generate_current_head(
     path: &Path,
     sync_config: &RuntimeSyncConfig,
 ) -> Result<Revision, String> {
-    let (hash, unix_ts) = tree_folder_hash(path, &sync_config.ignore_globset)?;
+    let (head, _manifest) =
+        generate_current_head_with_manifest(path, sync_config, sync_config.hash_algorithm)?;
+    Ok(head)
+}
+
+pub fn get_local_hash_cache_filepath(sync_config: &RuntimeSyncConfig) -> PathBuf {
+    return sync_config
+        .local_head_folder
+        .join(format!("{}.HASHCACHE", &sync_config.remote_sync_key));
+}
 
-    return Ok(Revision {
-        hash: hash,
+/// Reads the hash cache - empty if missing, unreadable or rehashing was requested.
+pub fn read_local_hash_cache(sync_config: &RuntimeSyncConfig) -> HashCache {
+    let local_cache_path = get_local_hash_cache_filepath(sync_config);
+    if sync_config.rehash || !local_cache_path.exists() {
+        return HashCache::default();
+    }
+
+    // A broken cache only costs a full rehash - never fail because of it.
+    fs::read_to_string(local_cache_path)
+        .map_err(|e| format!("Unable to read hash cache\n{e}"))
+        .and_then(|cachestr| HashCache::deserialize(&cachestr))
+        .unwrap_or_else(|e| {
+            eprintln!("Warning: Ignoring local hash cache.\n{e}");
+            HashCache::default()
+        })
+}
+
+pub fn write_local_hash_cache(
+    sync_config: &RuntimeSyncConfig,
+    cache: &HashCache,
+) -> Result<(), String> {
+    let local_cache_path = get_local_hash_cache_filepath(sync_config);
+
+    fs::write(local_cache_path, cache.serialize()?)
+        .map_err(|e| format!("Unable to update hash cache\n{e}"))?;
+    Ok(())
+}
+
+/// Hashes the current local files - algorithm should match the HEAD it will be compared against.
+/// Unchanged files reuse their digest from the local hash cache.
+pub fn generate_current_head_with_manifest(
+    path: &Path,
+    sync_config: &RuntimeSyncConfig,
+    algorithm: HashAlgorithm,
+) -> Result<(Revision, Manifest), String> {
+    let cache = read_local_hash_cache(sync_config);
+    let (manifest, unix_ts, updated_cache) =
+        tree_folder_manifest_cached(path, &sync_config.ignore_globset, algorithm, &cache)?;
+    if let Err(e) = write_local_hash_cache(sync_config, &updated_cache) {
+        eprintln!("Warning: {e}");
+    }
+
+    let head = Revision {
+        hash: manifest.tree_hash(),
         timestamp: unix_ts,
         author: sync_config.client_name.clone(),
-    });
+        parent: None,
+        hash_algorithm: algorithm,
+    };
+    Ok((head, manifest))
+}
+
+/// Whether `head` describes the same files as `current_head`.
+/// HEADs hashed with another algorithm are compared by re-hashing the local files with it.
+pub fn matches_current_files(
+    head: &Revision,
+    current_head: &Revision,
+    sync_config: &RuntimeSyncConfig,
+) -> Result<bool, String> {
+    if head.hash_algorithm == current_head.hash_algorithm {
+        return Ok(head == current_head);
+    }
+
+    let (rehashed, _manifest) = generate_current_head_with_manifest(
+        &sync_config.local_save_folder,
+        sync_config,
+        head.hash_algorithm,
+    )?;
+    Ok(*head == rehashed)
 }
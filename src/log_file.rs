@@ -0,0 +1,29 @@
+use chrono::Local;
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+
+/// Timestamped log lines - printed to stdout and appended to a log file.
+pub struct LogFile {
+    file: File,
+}
+
+impl LogFile {
+    pub fn open(log_path: &Path) -> Result<LogFile, String> {
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(log_path)
+            .map_err(|e| format!("Unable to open log file {}\n{e}", log_path.display()))?;
+        Ok(LogFile { file: file })
+    }
+
+    pub fn log(&self, message: &str) {
+        let line = format!("[{}] {message}", Local::now().format("%Y-%m-%d %H:%M:%S"));
+        println!("{line}");
+        // Logging must never stop the caller - a failed write is only reported on stderr.
+        if let Err(e) = writeln!(&self.file, "{line}") {
+            eprintln!("Unable to write log file\n{e}");
+        }
+    }
+}
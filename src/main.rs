@@ -1,8 +1,22 @@
-use std::{path::PathBuf, process::ExitCode};
+use std::{
+    env,
+    ffi::OsString,
+    io::{self, Write},
+    path::PathBuf,
+    process::{self, ExitCode},
+};
 
 use clap::{Parser, Subcommand};
 use local_cloud_game_sync::{
-    commands, config::config_commons::load_config, ui::ui_loop_main::ui_loop_main,
+    cancellation::CancellationToken,
+    commands,
+    config::{
+        ConfigFormat, PathCandidates, RuntimeSyncConfig, SaveFolderPath, SyncEntry,
+        config_commons::{load_config, load_global_options},
+    },
+    daemon::{daemon_main::daemon_main, daemon_state::read_daemon_state},
+    progress::render_cli_progress,
+    ui::ui_loop_main::ui_loop_main,
 };
 
 const RED_ANSI_ESCAPE: &str = "\x1b[31m";
@@ -19,6 +33,14 @@ struct LocalGameSyncCli {
     /// An optional global config file override - uses default global config location if not specified.
     #[arg(long)]
     config: Option<PathBuf>,
+
+    /// Ignores the local hash cache and re-reads every file. Use if files were modified without updating their mtime.
+    #[arg(long)]
+    rehash: bool,
+
+    /// Accepts unencrypted remote files while encryption is enabled - only to pull saves pushed before enabling it.
+    #[arg(long)]
+    allow_unencrypted: bool,
 }
 
 #[derive(Subcommand)]
@@ -41,6 +63,7 @@ enum Commands {
 
     /// Perform uni-directional push process for the given game key. Uploads the current local save overwriting the remote.
     /// Because push can be a destructive action - a snapshot is always triggered on the remote before overwriting.
+    /// Refused while the game (`processNames`) is running.
     Push {
         sync_key: String,
         /// Helper scripting option to only perform the push operation if the observed remote head is the one provided.
@@ -48,39 +71,347 @@ enum Commands {
         #[arg(long)]
         if_head: Option<String>,
     },
+    /// Merges remote changes into local and pushes the result. Only possible when local and remote changed different files.
+    /// Requires the entry to use `"conflictStrategy": "file-merge"` so that file manifests are tracked.
+    Merge {
+        sync_key: String,
+        /// Helper scripting option to only perform the merge operation if the observed remote head is the one provided.
+        /// Allows to perform atomical operations.
+        #[arg(long)]
+        if_head: Option<String>,
+    },
+    /// Performs the bi-directional sync for the given key without UI. Conflicts must be resolved through the UI.
+    Sync {
+        #[arg(required_unless_present = "pending", conflicts_with = "pending")]
+        sync_key: Option<String>,
+
+        /// Syncs every key with local progress that was never pushed (see `pending`) instead.
+        #[arg(long)]
+        pending: bool,
+    },
+    /// Lists keys with local progress that was never pushed - e.g. after continuing offline.
+    Pending,
+    /// Watches every configured save folder - pushing changes once the folder is quiet (`daemonQuietSecs`)
+    /// and polling the remote every `daemonPollSecs` to pre-fetch changes from other devices.
+    /// Keys whose game (`processNames`) is running are left alone until it closes.
+    /// Logs to `daemon.log` and records what it did in `daemon_state.json` - both in the local head folder.
+    Daemon {
+        /// Prints the state recorded by the running (or last) daemon instead.
+        #[arg(long)]
+        status: bool,
+    },
+    /// Syncs (with UI), launches the game, waits for it and every process it started to exit, then syncs again.
+    /// Exits with the game's exit code. Logs to `<key>.run.log` in the local head folder.
+    /// Example Steam launch options: `lcgsync run testsynckey -- %command%`
+    Run {
+        sync_key: String,
+
+        /// Launches the given Windows executable through Proton - like Steam does for non-Steam games.
+        /// Steam, reaper, the Steam Linux Runtime and the app id are detected automatically.
+        #[arg(long)]
+        proton: bool,
+
+        /// Proton folder name under steamapps/common (e.g. "Proton 9.0 (Beta)") - newest installed if omitted.
+        #[arg(long, requires = "proton")]
+        proton_version: Option<String>,
+
+        /// The game command (or the .exe with --proton) and its arguments.
+        #[arg(last = true, required = true)]
+        command: Vec<String>,
+    },
+    /// Rewrites remote and local HEAD files still in the legacy format of older versions.
+    /// Pushes and pulls already write the current format - older versions can't read a key once it was synced.
+    Migrate { sync_key: String },
+    /// Shows the sync history (pushes/pulls from every device) of the given key - newest first.
+    Log {
+        sync_key: String,
+
+        /// Maximum amount of entries to display - newest first.
+        #[arg(long)]
+        limit: Option<usize>,
+
+        /// Prints the history as JSON instead.
+        #[arg(long)]
+        json: bool,
+    },
     /// Shows the files tracked and ignored for a given game key. Useful for verifying game configs.
     Files { sync_key: String },
-    /// Opens the default config file
+    /// Manages the client-side encryption key used when `"encrypt": true` is set in the config.
+    Keys {
+        #[command(subcommand)]
+        action: KeysAction,
+    },
+    /// Helpers for Steam games - e.g. finding installed games and their Proton prefixes.
+    Steam {
+        #[command(subcommand)]
+        action: SteamAction,
+    },
+    /// Finds games from the local save-location database (`save_locations.yaml` in the data folder) whose save folders
+    /// exist on this machine - including inside Proton prefixes - and offers to add them to `syncEntries`.
+    Discover {
+        /// Only games whose title contains this text - or with this Steam app id.
+        query: Option<String>,
+
+        /// Uses this database file instead of the default one.
+        #[arg(long)]
+        database: Option<PathBuf>,
+
+        /// Adds every discovered game without asking.
+        #[arg(long)]
+        yes: bool,
+    },
+    /// Edits the per-game entries in `entries.d` next to the config file - or shares entries through the remote.
+    Config {
+        #[command(subcommand)]
+        action: ConfigAction,
+    },
+    /// Opens the config file in use - `sync_config.json`, `.jsonc` or `.toml`, whichever exists.
     OpenConfig,
     /// Ensures that the configs folder exists to start placing save sync configurations.
-    InitConfig,
+    InitConfig {
+        /// Format of the generated config - json, jsonc or toml. Only jsonc and toml templates hold comments.
+        #[arg(long, default_value = "jsonc")]
+        format: ConfigFormat,
+    },
     // Performs the bi-directional sync-process for the given key - with a helper UI to resolve sync conflicts.
     UI {
         sync_key: String,
 
         /// Should be used to specify that the UI is being executed after game execution.
-        /// This currently has no effect on any actual logic (inteded - for simplicity). But it does have some visual changes
+        /// Syncing always waits for the entry's `processNames` to exit first - this flag only changes visuals and marks failed uploads as pending.
         #[arg(long)]
         after_game: bool,
     },
 }
 
+#[derive(Subcommand)]
+enum KeysAction {
+    /// Derives the encryption key from a passphrase and stores it locally.
+    /// The passphrase is read from LCGSYNC_PASSPHRASE or prompted for. Every device must use the same passphrase.
+    Init {
+        /// Stores a key printed by `keys export` instead of deriving one from a passphrase.
+        #[arg(long)]
+        import: Option<String>,
+    },
+    /// Prints the local encryption key - keep it somewhere safe, saves can't be recovered without it.
+    Export,
+}
+
+#[derive(Subcommand)]
+enum ConfigAction {
+    /// Adds a sync entry as `entries.d/<key>.json`. The save folder must exist on this machine.
+    Add {
+        sync_key: String,
+
+        /// Save folder - repeat to give candidates, the first existing one is used. Placeholders are supported.
+        #[arg(long, required = true)]
+        path: Vec<String>,
+
+        /// Glob of files to ignore - repeatable.
+        #[arg(long)]
+        ignore: Vec<String>,
+
+        #[arg(long)]
+        display_name: Option<String>,
+
+        /// Game process name to wait for before syncing - repeatable.
+        #[arg(long)]
+        process_name: Vec<String>,
+    },
+    /// Deletes the entry's file from `entries.d`. Saves (local and remote) are kept.
+    Remove { sync_key: String },
+    /// Downloads the entries shared by every device (`.cloudmeta/entries.json` on the remote) into `shared_entries.json`.
+    /// Local entries with the same key take precedence over shared ones.
+    Pull {
+        /// Discards local edits to `shared_entries.json` that were never pushed.
+        #[arg(long)]
+        force: bool,
+    },
+    /// Validates the global options and every sync entry, and checks that the remote is reachable and set up.
+    /// Prints a pass/warn/fail table - exits non-zero if any check failed.
+    Check {
+        /// Skips the SSH checks - e.g. while offline.
+        #[arg(long)]
+        skip_remote: bool,
+    },
+    /// Uploads `shared_entries.json` for every other device to pull. Fails if the remote changed since the last pull.
+    Push {
+        /// Overwrites remote changes this device has not pulled.
+        #[arg(long)]
+        force: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum SteamAction {
+    /// Lists installed apps with their app ids and Proton prefixes - across every Steam library.
+    Scan {
+        /// Prints draft sync entries (JSON) to copy into `syncEntries` instead.
+        #[arg(long)]
+        entries: bool,
+
+        /// Only include these app ids.
+        app_ids: Vec<String>,
+    },
+}
+
+const PASSPHRASE_ENV_VAR: &str = "LCGSYNC_PASSPHRASE";
+
+fn read_passphrase() -> Result<String, String> {
+    if let Ok(passphrase) = env::var(PASSPHRASE_ENV_VAR) {
+        return Ok(passphrase);
+    }
+
+    // NOTE: input is echoed - use the environment variable if that is a concern.
+    print!("Encryption passphrase: ");
+    io::stdout().flush().map_err(|e| e.to_string())?;
+    let mut passphrase = String::new();
+    io::stdin()
+        .read_line(&mut passphrase)
+        .map_err(|e| format!("Unable to read passphrase\n{e}"))?;
+    Ok(passphrase.trim_end_matches(['\r', '\n']).to_string())
+}
+
+/// First Ctrl+C cancels the running sync (releasing the remote lock), a second one exits immediately.
+fn cancel_on_ctrl_c() -> Result<CancellationToken, String> {
+    let cancel = CancellationToken::new();
+    let handler_cancel = cancel.clone();
+    ctrlc::set_handler(move || {
+        if handler_cancel.is_cancelled() {
+            process::exit(130);
+        }
+        eprintln!("\nCancelling - press Ctrl+C again to exit immediately");
+        handler_cancel.cancel();
+    })
+    .map_err(|e| format!("Unable to set Ctrl+C handler\n{e}"))?;
+    Ok(cancel)
+}
+
 fn handle_command(args: LocalGameSyncCli) -> Result<(), String> {
+    let load = |sync_key: &str| {
+        load_config(sync_key, args.config.as_deref()).map(|sync_config| RuntimeSyncConfig {
+            rehash: args.rehash,
+            allow_unencrypted_pull: args.allow_unencrypted,
+            ..sync_config
+        })
+    };
+
     let command_res: Result<(), String> = match args.command {
         Commands::CheckSync { sync_key } => {
-            let sync_config = load_config(&sync_key, args.config.as_deref())?;
+            let sync_config = load(&sync_key)?;
             commands::check_sync_command(&sync_config).map(|_| ())
         }
         Commands::Push { sync_key, if_head } => {
-            let sync_config = load_config(&sync_key, args.config.as_deref())?;
-            commands::push_command(&sync_config, if_head.as_deref())
+            let sync_config = load(&sync_key)?;
+            commands::push_command_with_update_callback(
+                &sync_config,
+                if_head.as_deref(),
+                render_cli_progress,
+                &cancel_on_ctrl_c()?,
+            )
         }
         Commands::Pull { sync_key, if_head } => {
-            let sync_config = load_config(&sync_key, args.config.as_deref())?;
-            commands::pull_command(&sync_config, if_head.as_deref())
+            let sync_config = load(&sync_key)?;
+            commands::pull_command_with_update_callback(
+                &sync_config,
+                if_head.as_deref(),
+                render_cli_progress,
+                &cancel_on_ctrl_c()?,
+            )
         }
-        Commands::InitConfig => commands::init_command(),
-        Commands::OpenConfig => commands::open_default_config_file(),
+        Commands::Merge { sync_key, if_head } => {
+            let sync_config = load(&sync_key)?;
+            commands::merge_command_with_update_callback(
+                &sync_config,
+                if_head.as_deref(),
+                render_cli_progress,
+                &cancel_on_ctrl_c()?,
+            )
+        }
+        Commands::Sync {
+            sync_key: Some(sync_key),
+            ..
+        } => {
+            let sync_config = load(&sync_key)?;
+            commands::sync_command(&sync_config, render_cli_progress, &cancel_on_ctrl_c()?)
+                .map(|_| ())
+        }
+        Commands::Sync { sync_key: None, .. } => {
+            let options = load_global_options(args.config.as_deref())?;
+            commands::sync_pending_command(
+                &options.local_head_folder,
+                load,
+                render_cli_progress,
+                &cancel_on_ctrl_c()?,
+            )
+        }
+        Commands::Pending => {
+            let options = load_global_options(args.config.as_deref())?;
+            commands::pending_command(&options.local_head_folder)
+        }
+        Commands::Daemon { status: true } => {
+            let options = load_global_options(args.config.as_deref())?;
+            match read_daemon_state(&options.local_head_folder)? {
+                Some(state) => state.print(),
+                None => println!("<daemon has never run>"),
+            }
+            Ok(())
+        }
+        Commands::Daemon { status: false } => {
+            let options = load_global_options(args.config.as_deref())?;
+            daemon_main(&options, load, &cancel_on_ctrl_c()?)
+        }
+        Commands::Run {
+            sync_key,
+            proton,
+            proton_version,
+            command,
+        } => {
+            let options = load_global_options(args.config.as_deref())?;
+            let launch_mode = match proton {
+                true => commands::LaunchMode::Proton {
+                    proton_version: proton_version,
+                },
+                false => commands::LaunchMode::Native,
+            };
+            let mut global_args: Vec<OsString> = vec![];
+            if let Some(config) = &args.config {
+                global_args.push(OsString::from("--config"));
+                global_args.push(config.clone().into_os_string());
+            }
+            if args.rehash {
+                global_args.push(OsString::from("--rehash"));
+            }
+            if args.allow_unencrypted {
+                global_args.push(OsString::from("--allow-unencrypted"));
+            }
+
+            let exit_code = commands::run_command(
+                &sync_key,
+                &command,
+                &launch_mode,
+                &global_args,
+                &options.local_head_folder,
+            )?;
+            if exit_code != 0 {
+                process::exit(exit_code);
+            }
+            Ok(())
+        }
+        Commands::Migrate { sync_key } => {
+            let sync_config = load(&sync_key)?;
+            commands::migrate_command(&sync_config)
+        }
+        Commands::Log {
+            sync_key,
+            limit,
+            json,
+        } => {
+            let sync_config = load(&sync_key)?;
+            commands::log_command(&sync_config, limit, json)
+        }
+        Commands::InitConfig { format } => commands::init_command(format),
+        Commands::OpenConfig => commands::open_config_file(args.config.as_deref()),
         Commands::UI {
             sync_key,
             after_game,
@@ -89,9 +420,81 @@ fn handle_command(args: LocalGameSyncCli) -> Result<(), String> {
             Ok(())
         }
         Commands::Files { sync_key } => {
-            let sync_config = load_config(&sync_key, args.config.as_deref())?;
+            let sync_config = load(&sync_key)?;
             commands::files_command(&sync_config)
         }
+        Commands::Steam {
+            action: SteamAction::Scan { entries, app_ids },
+        } => commands::steam_scan_command(entries, &app_ids),
+        Commands::Discover {
+            query,
+            database,
+            yes,
+        } => {
+            let options = load_global_options(args.config.as_deref())?;
+            commands::discover_command(
+                &options,
+                args.config.as_deref(),
+                database.as_deref(),
+                query.as_deref(),
+                yes,
+            )
+        }
+        Commands::Config {
+            action:
+                ConfigAction::Add {
+                    sync_key,
+                    mut path,
+                    ignore,
+                    display_name,
+                    process_name,
+                },
+        } => {
+            let save_folder_path = match path.len() {
+                1 => SaveFolderPath::Paths(PathCandidates::Path(path.remove(0))),
+                _ => SaveFolderPath::Paths(PathCandidates::Candidates(path)),
+            };
+            let entry = SyncEntry {
+                remote_sync_key: sync_key,
+                save_folder_path: save_folder_path,
+                save_ignore_glob: ignore,
+                display_name: display_name,
+                conflict_strategy: None,
+                remote_storage: None,
+                process_names: (!process_name.is_empty()).then_some(process_name),
+            };
+            commands::config_add_command(args.config.as_deref(), entry)
+        }
+        Commands::Config {
+            action: ConfigAction::Remove { sync_key },
+        } => commands::config_remove_command(args.config.as_deref(), &sync_key),
+        Commands::Config {
+            action: ConfigAction::Check { skip_remote },
+        } => commands::config_check_command(args.config.as_deref(), skip_remote),
+        Commands::Config {
+            action: ConfigAction::Pull { force },
+        } => {
+            let options = load_global_options(args.config.as_deref())?;
+            commands::config_pull_command(&options, args.config.as_deref(), force)
+        }
+        Commands::Config {
+            action: ConfigAction::Push { force },
+        } => {
+            let options = load_global_options(args.config.as_deref())?;
+            commands::config_push_command(&options, args.config.as_deref(), force)
+        }
+        Commands::Keys { action } => {
+            let options = load_global_options(args.config.as_deref())?;
+            match action {
+                KeysAction::Init {
+                    import: Some(exported),
+                } => commands::keys_import_command(&options, &exported),
+                KeysAction::Init { import: None } => {
+                    commands::keys_init_command(&options, &read_passphrase()?)
+                }
+                KeysAction::Export => commands::keys_export_command(&options),
+            }
+        }
     };
 
     return command_res;
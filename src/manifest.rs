@@ -0,0 +1,207 @@
+use crate::hashing::HashAlgorithm;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Per-file digests of a save folder revision - keyed by the path relative to the save folder.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct Manifest {
+    /// Algorithm the file digests (and tree hash) were computed with.
+    #[serde(default)]
+    pub algorithm: HashAlgorithm,
+    pub files: BTreeMap<String, String>,
+    /// File sizes in bytes - only used for progress reporting. Missing in manifests from older versions.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub sizes: BTreeMap<String, u64>,
+}
+
+/// Files that must be brought over from the remote to merge it into local.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MergePlan {
+    /// Files changed or added on the remote only - should be downloaded.
+    pub pull_files: Vec<String>,
+    /// Files deleted on the remote only - should be deleted locally.
+    pub delete_files: Vec<String>,
+}
+
+impl MergePlan {
+    pub fn is_empty(&self) -> bool {
+        self.pull_files.is_empty() && self.delete_files.is_empty()
+    }
+}
+
+impl Manifest {
+    pub fn new(algorithm: HashAlgorithm) -> Self {
+        Manifest {
+            algorithm,
+            files: BTreeMap::new(),
+            sizes: BTreeMap::new(),
+        }
+    }
+
+    /// Total size of all files - None if sizes weren't recorded for every file.
+    pub fn total_size(&self) -> Option<u64> {
+        self.files
+            .keys()
+            .map(|file| self.sizes.get(file))
+            .sum::<Option<u64>>()
+    }
+
+    /// Computes the folder hash - for md5 this must match the legacy `tree_folder_hash` format.
+    pub fn tree_hash(&self) -> String {
+        let combined = self
+            .files
+            .iter()
+            .map(|(name, hash)| format!("{}:{}", name, hash))
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        self.algorithm.digest_bytes(combined.as_bytes())
+    }
+
+    pub fn serialize(&self) -> Result<String, String> {
+        serde_json::to_string(self).map_err(|e| format!("Unable to serialize manifest\n{e}"))
+    }
+
+    pub fn deserialize(s: &str) -> Result<Self, String> {
+        serde_json::from_str(s).map_err(|e| format!("Invalid manifest\n{e}"))
+    }
+
+    /// Returns every file that was added, modified or deleted compared to `base`.
+    fn changed_since(&self, base: &Manifest) -> BTreeSet<String> {
+        let all_files: BTreeSet<&String> = self.files.keys().chain(base.files.keys()).collect();
+        all_files
+            .into_iter()
+            .filter(|f| self.files.get(*f) != base.files.get(*f))
+            .cloned()
+            .collect()
+    }
+}
+
+/// Three-way merge of file changes. Returns None if the same file was changed differently on both sides.
+/// Also None if the manifests were hashed with different algorithms - digests wouldn't be comparable.
+pub fn plan_file_merge(base: &Manifest, local: &Manifest, remote: &Manifest) -> Option<MergePlan> {
+    if base.algorithm != local.algorithm || local.algorithm != remote.algorithm {
+        return None;
+    }
+
+    let local_changes = local.changed_since(base);
+    let remote_changes = remote.changed_since(base);
+
+    let mut plan = MergePlan::default();
+    for file in remote_changes {
+        if local_changes.contains(&file) {
+            // Both sides touched it - only fine if they ended up with the exact same contents.
+            if local.files.get(&file) != remote.files.get(&file) {
+                return None;
+            }
+            continue;
+        }
+
+        match remote.files.contains_key(&file) {
+            true => plan.pull_files.push(file),
+            false => plan.delete_files.push(file),
+        }
+    }
+
+    Some(plan)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn manifest(files: &[(&str, &str)]) -> Manifest {
+        Manifest {
+            algorithm: HashAlgorithm::Blake3,
+            files: files
+                .iter()
+                .map(|(f, h)| (f.to_string(), h.to_string()))
+                .collect(),
+            sizes: BTreeMap::new(),
+        }
+    }
+
+    #[test]
+    fn md5_tree_hash_matches_legacy_format() {
+        let mut legacy = manifest(&[("a.sav", "x"), ("b.sav", "y")]);
+        legacy.algorithm = HashAlgorithm::Md5;
+        assert_eq!(
+            legacy.tree_hash(),
+            format!("{:x}", md5::compute(b"a.sav:x\nb.sav:y"))
+        );
+    }
+
+    #[test]
+    fn mixed_algorithms_are_not_mergeable() {
+        let base = manifest(&[("slot1.sav", "a")]);
+        let local = manifest(&[("slot1.sav", "a2")]);
+        let mut remote = manifest(&[("slot1.sav", "a"), ("slot2.sav", "b")]);
+        remote.algorithm = HashAlgorithm::Md5;
+
+        assert!(plan_file_merge(&base, &local, &remote).is_none());
+    }
+
+    #[test]
+    fn disjoint_slot_changes_are_mergeable() {
+        let base = manifest(&[("slot1.sav", "a"), ("slot3.sav", "b")]);
+        let local = manifest(&[("slot1.sav", "a2"), ("slot3.sav", "b")]);
+        let remote = manifest(&[("slot1.sav", "a"), ("slot3.sav", "b2")]);
+
+        let plan = plan_file_merge(&base, &local, &remote).expect("Expected mergeable");
+        assert_eq!(plan.pull_files, vec!["slot3.sav".to_string()]);
+        assert!(plan.delete_files.is_empty());
+    }
+
+    #[test]
+    fn same_file_changed_on_both_sides_conflicts() {
+        let base = manifest(&[("slot1.sav", "a")]);
+        let local = manifest(&[("slot1.sav", "a2")]);
+        let remote = manifest(&[("slot1.sav", "a3")]);
+
+        assert!(plan_file_merge(&base, &local, &remote).is_none());
+    }
+
+    #[test]
+    fn remote_deletions_and_additions_are_merged() {
+        let base = manifest(&[("slot1.sav", "a"), ("slot2.sav", "b")]);
+        let local = manifest(&[("slot1.sav", "a2"), ("slot2.sav", "b")]);
+        let remote = manifest(&[("slot1.sav", "a"), ("slot4.sav", "d")]);
+
+        let plan = plan_file_merge(&base, &local, &remote).expect("Expected mergeable");
+        assert_eq!(plan.pull_files, vec!["slot4.sav".to_string()]);
+        assert_eq!(plan.delete_files, vec!["slot2.sav".to_string()]);
+    }
+
+    #[test]
+    fn local_deleted_remote_modified_conflicts() {
+        let base = manifest(&[("slot1.sav", "a")]);
+        let local = manifest(&[]);
+        let remote = manifest(&[("slot1.sav", "a2")]);
+
+        assert!(plan_file_merge(&base, &local, &remote).is_none());
+    }
+
+    #[test]
+    fn identical_change_on_both_sides_is_not_a_conflict() {
+        let base = manifest(&[("slot1.sav", "a")]);
+        let local = manifest(&[("slot1.sav", "a2"), ("slot2.sav", "x")]);
+        let remote = manifest(&[("slot1.sav", "a2")]);
+
+        let plan = plan_file_merge(&base, &local, &remote).expect("Expected mergeable");
+        assert!(plan.is_empty());
+    }
+
+    #[test]
+    fn total_size_requires_every_file() {
+        let mut sized = manifest(&[("slot1.sav", "a"), ("slot2.sav", "b")]);
+        assert_eq!(sized.total_size(), None);
+
+        sized.sizes.insert("slot1.sav".to_string(), 10);
+        sized.sizes.insert("slot2.sav".to_string(), 5);
+        assert_eq!(sized.total_size(), Some(15));
+
+        // Older manifests without sizes still parse.
+        let legacy = Manifest::deserialize(r#"{"algorithm":"md5","files":{"a":"x"}}"#).unwrap();
+        assert_eq!(legacy.total_size(), None);
+    }
+}
@@ -0,0 +1,169 @@
+use crate::common::format_unix_timestamp;
+use crate::utils::get_unix_timestamp_secs;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const PENDING_FILE_EXTENSION: &str = "PENDING";
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum PendingReason {
+    /// Remote was not reachable before playing - the game was played without pulling first.
+    ContinuedOffline,
+    /// Saves could not be pushed after playing.
+    PushFailed,
+}
+
+impl fmt::Display for PendingReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PendingReason::ContinuedOffline => write!(f, "continued offline"),
+            PendingReason::PushFailed => write!(f, "push failed"),
+        }
+    }
+}
+
+/// Marker for a key whose local progress has not reached the remote yet. Cleared by the next successful sync.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct PendingSync {
+    pub sync_key: String,
+    pub reason: PendingReason,
+    /// When local progress first went un-synced (unix secs) - kept when marked again.
+    pub since: u64,
+    pub error: Option<String>,
+}
+
+impl PendingSync {
+    pub fn since_display_str(&self) -> String {
+        format_unix_timestamp(self.since, "%a %e %b %Y at %H:%M:%S")
+    }
+}
+
+impl fmt::Display for PendingSync {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} - {} since {}",
+            self.sync_key,
+            self.reason,
+            self.since_display_str()
+        )
+    }
+}
+
+pub fn get_pending_sync_filepath(local_head_folder: &Path, sync_key: &str) -> PathBuf {
+    return local_head_folder.join(format!("{sync_key}.{PENDING_FILE_EXTENSION}"));
+}
+
+pub fn read_pending_sync(
+    local_head_folder: &Path,
+    sync_key: &str,
+) -> Result<Option<PendingSync>, String> {
+    let pending_path = get_pending_sync_filepath(local_head_folder, sync_key);
+    if !pending_path.exists() {
+        return Ok(None);
+    }
+
+    let pendingstr = fs::read_to_string(&pending_path)
+        .map_err(|e| format!("Unable to read pending sync marker\n{e}"))?;
+    let pending = serde_json::from_str(&pendingstr)
+        .map_err(|e| format!("Unable to parse pending sync marker\n{e}"))?;
+    Ok(Some(pending))
+}
+
+/// Records that the key has local progress not pushed to the remote.
+pub fn mark_pending_sync(
+    local_head_folder: &Path,
+    sync_key: &str,
+    reason: PendingReason,
+    error: Option<&str>,
+) -> Result<(), String> {
+    // An unreadable previous marker is simply replaced.
+    let since = match read_pending_sync(local_head_folder, sync_key) {
+        Ok(Some(previous)) => previous.since,
+        _ => get_unix_timestamp_secs(),
+    };
+    let pending = PendingSync {
+        sync_key: sync_key.to_string(),
+        reason: reason,
+        since: since,
+        error: error.map(str::to_string),
+    };
+
+    let pendingstr = serde_json::to_string(&pending)
+        .map_err(|e| format!("Unable to serialize pending sync marker\n{e}"))?;
+    fs::write(
+        get_pending_sync_filepath(local_head_folder, sync_key),
+        pendingstr,
+    )
+    .map_err(|e| format!("Unable to write pending sync marker\n{e}"))?;
+    Ok(())
+}
+
+pub fn clear_pending_sync(local_head_folder: &Path, sync_key: &str) -> Result<(), String> {
+    let pending_path = get_pending_sync_filepath(local_head_folder, sync_key);
+    if pending_path.exists() {
+        fs::remove_file(pending_path)
+            .map_err(|e| format!("Unable to clear pending sync marker\n{e}"))?;
+    }
+    Ok(())
+}
+
+/// Every pending key in the local head folder - oldest first.
+pub fn list_pending_syncs(local_head_folder: &Path) -> Result<Vec<PendingSync>, String> {
+    let entries = fs::read_dir(local_head_folder)
+        .map_err(|e| format!("Unable to read local head folder\n{e}"))?;
+
+    let mut pending = vec![];
+    for entry in entries {
+        let path = entry
+            .map_err(|e| format!("Unable to read local head folder entry\n{e}"))?
+            .path();
+        if path
+            .extension()
+            .is_none_or(|ext| ext != PENDING_FILE_EXTENSION)
+        {
+            continue;
+        }
+        let Some(sync_key) = path.file_stem().and_then(|stem| stem.to_str()) else {
+            continue;
+        };
+        if let Some(marker) = read_pending_sync(local_head_folder, sync_key)? {
+            pending.push(marker);
+        }
+    }
+    pending.sort_by_key(|marker| marker.since);
+    Ok(pending)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn marks_keep_first_timestamp_and_clear() {
+        let folder = std::env::temp_dir().join(format!(
+            "local_cloud_game_sync_pending_test_{}",
+            std::process::id()
+        ));
+        fs::create_dir_all(&folder).unwrap();
+        fs::write(folder.join("game1.HEAD"), "").unwrap();
+
+        mark_pending_sync(&folder, "game1", PendingReason::ContinuedOffline, None).unwrap();
+        let first = read_pending_sync(&folder, "game1").unwrap().unwrap();
+        mark_pending_sync(&folder, "game1", PendingReason::PushFailed, Some("offline")).unwrap();
+        let second = read_pending_sync(&folder, "game1").unwrap().unwrap();
+
+        assert_eq!(second.since, first.since);
+        assert_eq!(second.reason, PendingReason::PushFailed);
+        assert_eq!(second.error.as_deref(), Some("offline"));
+        assert_eq!(list_pending_syncs(&folder).unwrap(), vec![second]);
+
+        clear_pending_sync(&folder, "game1").unwrap();
+        assert!(list_pending_syncs(&folder).unwrap().is_empty());
+        fs::remove_dir_all(&folder).unwrap();
+    }
+}
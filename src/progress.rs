@@ -0,0 +1,263 @@
+use serde::Serialize;
+use std::cell::{Cell, RefCell};
+use std::io::{self, IsTerminal, Read, Write};
+use std::time::{Duration, Instant};
+
+use crate::utils::format_bytes;
+
+/// Byte updates are coalesced - callbacks (e.g. webview messages) shouldn't fire for every chunk.
+const PROGRESS_EMIT_INTERVAL: Duration = Duration::from_millis(100);
+const CLI_PROGRESS_BAR_WIDTH: usize = 30;
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SyncPhase {
+    Locking,
+    Comparing,
+    Snapshotting,
+    Uploading,
+    Downloading,
+    Merging,
+    Finalizing,
+    Done,
+}
+
+/// Progress of a sync command - sent for every step and repeatedly while files are transferred.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct ProgressEvent {
+    pub phase: SyncPhase,
+    /// Human readable description of the current step.
+    pub message: String,
+    pub files_done: u64,
+    pub files_total: Option<u64>,
+    pub bytes_done: u64,
+    pub bytes_total: Option<u64>,
+    pub current_file: Option<String>,
+    pub eta_secs: Option<u64>,
+}
+
+impl ProgressEvent {
+    /// A step without any transfer progress.
+    pub fn step(phase: SyncPhase, message: impl Into<String>) -> ProgressEvent {
+        ProgressEvent {
+            phase,
+            message: message.into(),
+            files_done: 0,
+            files_total: None,
+            bytes_done: 0,
+            bytes_total: None,
+            current_file: None,
+            eta_secs: None,
+        }
+    }
+
+    /// Completed fraction (0..=1) - by bytes if known, otherwise by files.
+    pub fn fraction(&self) -> Option<f64> {
+        let (done, total) = match (self.bytes_total, self.files_total) {
+            (Some(bytes_total), _) if bytes_total > 0 => (self.bytes_done, bytes_total),
+            (_, Some(files_total)) if files_total > 0 => (self.files_done, files_total),
+            _ => return None,
+        };
+        Some((done as f64 / total as f64).min(1.0))
+    }
+}
+
+/// Tracks a running transfer and reports it through the callback. Remote clients update it as data flows.
+pub struct TransferProgress<'a> {
+    callback: &'a dyn Fn(ProgressEvent),
+    event: RefCell<ProgressEvent>,
+    started_at: Instant,
+    last_emitted_at: Cell<Instant>,
+}
+
+impl<'a> TransferProgress<'a> {
+    pub fn new(
+        callback: &'a dyn Fn(ProgressEvent),
+        phase: SyncPhase,
+        message: impl Into<String>,
+        files_total: Option<u64>,
+        bytes_total: Option<u64>,
+    ) -> TransferProgress<'a> {
+        let progress = TransferProgress {
+            callback,
+            event: RefCell::new(ProgressEvent {
+                files_total,
+                bytes_total,
+                ..ProgressEvent::step(phase, message)
+            }),
+            started_at: Instant::now(),
+            last_emitted_at: Cell::new(Instant::now()),
+        };
+        progress.emit();
+        progress
+    }
+
+    pub fn start_file(&self, relpath: &str) {
+        self.event.borrow_mut().current_file = Some(relpath.to_string());
+        self.emit_throttled();
+    }
+
+    pub fn add_bytes(&self, bytes: u64) {
+        self.event.borrow_mut().bytes_done += bytes;
+        self.emit_throttled();
+    }
+
+    pub fn finish_file(&self) {
+        self.event.borrow_mut().files_done += 1;
+        self.emit_throttled();
+    }
+
+    /// Reports the final state - regardless of throttling.
+    pub fn finish(&self) {
+        self.event.borrow_mut().current_file = None;
+        self.emit();
+    }
+
+    pub fn snapshot(&self) -> ProgressEvent {
+        let mut event = self.event.borrow().clone();
+        let elapsed = self.started_at.elapsed().as_secs_f64();
+        event.eta_secs = event
+            .fraction()
+            .filter(|fraction| *fraction > 0.0)
+            .map(|fraction| (elapsed / fraction - elapsed).round() as u64);
+        event
+    }
+
+    fn emit_throttled(&self) {
+        if self.last_emitted_at.get().elapsed() >= PROGRESS_EMIT_INTERVAL {
+            self.emit();
+        }
+    }
+
+    fn emit(&self) {
+        self.last_emitted_at.set(Instant::now());
+        (self.callback)(self.snapshot());
+    }
+}
+
+/// Reader adapter that counts everything read through it as transferred bytes.
+pub struct ProgressReader<'p, 'a, R> {
+    inner: R,
+    progress: &'p TransferProgress<'a>,
+}
+
+impl<'p, 'a, R> ProgressReader<'p, 'a, R> {
+    pub fn new(inner: R, progress: &'p TransferProgress<'a>) -> Self {
+        ProgressReader { inner, progress }
+    }
+}
+
+impl<R: Read> Read for ProgressReader<'_, '_, R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read = self.inner.read(buf)?;
+        self.progress.add_bytes(read as u64);
+        Ok(read)
+    }
+}
+
+fn format_eta(eta_secs: u64) -> String {
+    match eta_secs {
+        0..60 => format!("{eta_secs}s"),
+        60..3600 => format!("{}m{:02}s", eta_secs / 60, eta_secs % 60),
+        _ => format!("{}h{:02}m", eta_secs / 3600, (eta_secs % 3600) / 60),
+    }
+}
+
+/// Single line describing the progress - e.g. `[#####-----] 50% 1/2 files 1.0 MB/2.0 MB ETA 3s`.
+pub fn format_progress_line(event: &ProgressEvent) -> String {
+    let Some(fraction) = event.fraction() else {
+        return event.message.clone();
+    };
+
+    let filled = (fraction * CLI_PROGRESS_BAR_WIDTH as f64).round() as usize;
+    let mut line = format!(
+        "[{}{}] {:>3.0}%",
+        "#".repeat(filled),
+        "-".repeat(CLI_PROGRESS_BAR_WIDTH - filled),
+        fraction * 100.0
+    );
+    if let Some(files_total) = event.files_total {
+        line.push_str(&format!(" {}/{} files", event.files_done, files_total));
+    }
+    match event.bytes_total {
+        Some(bytes_total) => line.push_str(&format!(
+            " {}/{}",
+            format_bytes(event.bytes_done),
+            format_bytes(bytes_total)
+        )),
+        None => line.push_str(&format!(" {}", format_bytes(event.bytes_done))),
+    }
+    if let Some(eta_secs) = event.eta_secs {
+        line.push_str(&format!(" ETA {}", format_eta(eta_secs)));
+    }
+    line
+}
+
+/// Renders progress events as a progress bar on stderr. Only transfers get a bar - steps are printed as lines.
+/// Does nothing if stderr isn't a terminal - so scripted output stays clean.
+pub fn render_cli_progress(event: ProgressEvent) {
+    let mut stderr = io::stderr();
+    if !stderr.is_terminal() {
+        return;
+    }
+
+    let _ = match event.fraction() {
+        // \x1b[K clears leftovers of a previous longer line.
+        Some(_) => write!(stderr, "\r{}\x1b[K", format_progress_line(&event)),
+        None => writeln!(stderr, "\r{}\x1b[K", event.message),
+    };
+    let _ = stderr.flush();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn transfer_progress_tracks_files_and_bytes() {
+        let events = RefCell::new(vec![]);
+        let callback = |event: ProgressEvent| events.borrow_mut().push(event);
+        let progress = TransferProgress::new(
+            &callback,
+            SyncPhase::Uploading,
+            "Uploading",
+            Some(2),
+            Some(100),
+        );
+
+        progress.start_file("slot1.sav");
+        io::copy(
+            &mut ProgressReader::new(&[0u8; 40][..], &progress),
+            &mut io::sink(),
+        )
+        .unwrap();
+        progress.finish_file();
+        progress.finish();
+
+        let last = events.borrow().last().cloned().unwrap();
+        assert_eq!(last.files_done, 1);
+        assert_eq!(last.bytes_done, 40);
+        assert_eq!(last.current_file, None);
+        assert_eq!(last.fraction(), Some(0.4));
+        assert!(last.eta_secs.is_some());
+        // Initial event is always reported - even before anything was transferred.
+        assert_eq!(events.borrow()[0].bytes_done, 0);
+    }
+
+    #[test]
+    fn fraction_falls_back_to_files() {
+        let mut event = ProgressEvent::step(SyncPhase::Downloading, "Downloading");
+        assert_eq!(event.fraction(), None);
+
+        event.files_total = Some(4);
+        event.files_done = 1;
+        assert_eq!(event.fraction(), Some(0.25));
+        assert!(format_progress_line(&event).contains("1/4 files"));
+    }
+
+    #[test]
+    fn eta_formatting() {
+        assert_eq!(format_eta(5), "5s");
+        assert_eq!(format_eta(65), "1m05s");
+        assert_eq!(format_eta(3720), "1h02m");
+    }
+}
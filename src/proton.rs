@@ -0,0 +1,182 @@
+use crate::steam::library::find_app_compat_data;
+use crate::utils::{get_steam_common, get_steam_compatdata, get_steam_path};
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+const STEAM_RUNTIME_ENTRY_POINT: &str = "SteamLinuxRuntime_sniper/_v2-entry-point";
+/// Set by Steam for non-Steam shortcuts - contains the 10 digit shortcut id.
+const STEAM_COMPAT_MEDIA_PATH_ENV: &str = "STEAM_COMPAT_MEDIA_PATH";
+const STEAM_APP_ID_ENV: &str = "SteamAppId";
+
+/// Paths needed to launch a Windows executable like Steam does.
+#[derive(Debug, Clone)]
+pub struct ProtonLaunch {
+    pub app_id: String,
+    pub steam_path: PathBuf,
+    pub reaper: PathBuf,
+    pub steam_runtime: PathBuf,
+    pub proton: PathBuf,
+    pub compat_data: PathBuf,
+}
+
+/// Shortcut id of a non-Steam game - the first 10 digit component of `STEAM_COMPAT_MEDIA_PATH`.
+fn extract_shortcut_id(compat_media_path: &str) -> Option<String> {
+    compat_media_path
+        .split('/')
+        .find(|component| component.len() == 10 && component.chars().all(|c| c.is_ascii_digit()))
+        .map(str::to_string)
+}
+
+/// Steam app/shortcut id of the game being launched - provided by Steam through the environment.
+fn detect_app_id() -> Result<String, String> {
+    if let Some(shortcut_id) = env::var(STEAM_COMPAT_MEDIA_PATH_ENV)
+        .ok()
+        .and_then(|path| extract_shortcut_id(&path))
+    {
+        return Ok(shortcut_id);
+    }
+    match env::var(STEAM_APP_ID_ENV) {
+        Ok(app_id) if !app_id.is_empty() && app_id != "0" => Ok(app_id),
+        _ => Err(format!(
+            "Unable to detect the Steam app id - neither {STEAM_COMPAT_MEDIA_PATH_ENV} nor {STEAM_APP_ID_ENV} are set. Launch through Steam."
+        )),
+    }
+}
+
+/// Version of a Proton folder name (e.g. "Proton 9.0 (Beta)" -> [9, 0]) - None for non-numbered builds like Experimental.
+fn parse_proton_version(folder_name: &str) -> Option<Vec<u32>> {
+    let version = folder_name.strip_prefix("Proton ")?;
+    let numbers: Vec<u32> = version
+        .split(|c: char| !c.is_ascii_digit())
+        .take_while(|part| !part.is_empty())
+        .map(|part| part.parse().unwrap_or_default())
+        .collect();
+    match numbers.is_empty() {
+        true => None,
+        false => Some(numbers),
+    }
+}
+
+/// Picks the newest numbered Proton build - falling back to any other build (e.g. Experimental).
+fn pick_newest_proton(folder_names: &[String]) -> Option<&String> {
+    folder_names
+        .iter()
+        .filter(|name| parse_proton_version(name).is_some())
+        .max_by_key(|name| parse_proton_version(name))
+        .or_else(|| folder_names.iter().find(|name| name.starts_with("Proton")))
+}
+
+fn find_proton(steam_common: &Path, proton_version: Option<&str>) -> Result<PathBuf, String> {
+    if let Some(proton_version) = proton_version {
+        let proton = steam_common.join(proton_version).join("proton");
+        if !proton.exists() {
+            return Err(format!("Proton not found at {}", proton.display()));
+        }
+        return Ok(proton);
+    }
+
+    let entries = fs::read_dir(steam_common)
+        .map_err(|e| format!("Unable to read {}\n{e}", steam_common.display()))?;
+    let installed: Vec<String> = entries
+        .flatten()
+        .filter(|entry| entry.path().join("proton").exists())
+        .filter_map(|entry| entry.file_name().to_str().map(str::to_string))
+        .collect();
+    let newest = pick_newest_proton(&installed).ok_or(format!(
+        "No Proton installation found in {}",
+        steam_common.display()
+    ))?;
+    Ok(steam_common.join(newest).join("proton"))
+}
+
+fn ensure_exists(path: PathBuf, what: &str) -> Result<PathBuf, String> {
+    if !path.exists() {
+        return Err(format!("{what} not found at {}", path.display()));
+    }
+    Ok(path)
+}
+
+impl ProtonLaunch {
+    /// Auto-detects reaper, the Steam Linux Runtime and Proton (newest installed unless `proton_version` names its folder).
+    pub fn detect(proton_version: Option<&str>) -> Result<ProtonLaunch, String> {
+        let steam_path = get_steam_path()?;
+        let steam_common = get_steam_common()?;
+        let app_id = detect_app_id()?;
+
+        Ok(ProtonLaunch {
+            reaper: ensure_exists(
+                steam_path.join("ubuntu12_32").join("reaper"),
+                "Steam reaper",
+            )?,
+            steam_runtime: ensure_exists(
+                steam_common.join(STEAM_RUNTIME_ENTRY_POINT),
+                "Steam Linux Runtime",
+            )?,
+            proton: find_proton(&steam_common, proton_version)?,
+            // Shortcuts always use the main library - Steam games may live in any library.
+            compat_data: find_app_compat_data(&steam_path, &app_id)
+                .unwrap_or(get_steam_compatdata()?.join(&app_id)),
+            app_id: app_id,
+            steam_path: steam_path,
+        })
+    }
+
+    /// Same invocation as Steam: `reaper SteamLaunch AppId=<id> -- <runtime> --verb=waitforexitandrun -- <proton> waitforexitandrun <exe>`.
+    pub fn build_command(&self, game_exe: &Path, game_args: &[String]) -> Command {
+        let mut command = Command::new(&self.reaper);
+        command
+            .arg("SteamLaunch")
+            .arg(format!("AppId={}", self.app_id))
+            .arg("--")
+            .arg(&self.steam_runtime)
+            .arg("--verb=waitforexitandrun")
+            .arg("--")
+            .arg(&self.proton)
+            .arg("waitforexitandrun")
+            .arg(game_exe)
+            .args(game_args)
+            .env("STEAM_COMPAT_CLIENT_INSTALL_PATH", &self.steam_path)
+            .env("STEAM_COMPAT_DATA_PATH", &self.compat_data);
+        // Games often expect to be started from their own folder.
+        if let Some(game_dir) = game_exe.parent() {
+            command.current_dir(game_dir);
+        }
+        return command;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn extracts_shortcut_id_from_media_path() {
+        assert_eq!(
+            extract_shortcut_id(
+                "/home/user/.local/share/Steam/userdata/123/gamerecordings/3012345678/media"
+            ),
+            Some(String::from("3012345678"))
+        );
+        assert_eq!(extract_shortcut_id("/home/user/media"), None);
+    }
+
+    #[test]
+    fn picks_newest_numbered_proton() {
+        let installed = vec![
+            String::from("Proton 9.0 (Beta)"),
+            String::from("Proton - Experimental"),
+            String::from("Proton 10.0"),
+            String::from("Proton 8.0"),
+        ];
+        assert_eq!(
+            pick_newest_proton(&installed),
+            Some(&String::from("Proton 10.0"))
+        );
+        assert_eq!(
+            pick_newest_proton(&[String::from("Proton - Experimental")]),
+            Some(&String::from("Proton - Experimental"))
+        );
+    }
+}
@@ -1,7 +1,13 @@
+mod offline;
 mod remote_lock;
 mod remote_save_client;
 mod ssh_save_client;
 
+pub use offline::{OFFLINE_ERROR_PREFIX, is_offline_error, retry_with_backoff};
 pub use remote_lock::RemoteLock;
 pub use remote_save_client::RemoteSaveClient;
 pub use remote_save_client::get_default_remote_save_client;
+pub use ssh_save_client::{
+    RemoteLayout, acquire_options_lock, init_remote_key_params, read_remote_key_params,
+    read_remote_layout, read_remote_shared_entries, write_remote_shared_entries,
+};
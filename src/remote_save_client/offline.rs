@@ -0,0 +1,72 @@
+use std::thread;
+use std::time::Duration;
+
+/// Prefix of every error caused by the remote being unreachable - see `is_offline_error`.
+pub const OFFLINE_ERROR_PREFIX: &str = "Remote unreachable";
+const RETRY_BASE_DELAY: Duration = Duration::from_secs(1);
+
+/// Whether the error means the remote could not be reached at all (as opposed to the remote failing the operation).
+/// Matches anywhere in the message - as callers may add context around it.
+pub fn is_offline_error(error: &str) -> bool {
+    return error.contains(OFFLINE_ERROR_PREFIX);
+}
+
+/// Runs `operation` retrying with exponential backoff (1s, 2s, 4s...) while it fails because the remote is unreachable.
+/// Only meant for idempotent operations. Other errors are returned right away.
+pub fn retry_with_backoff<T, F>(retries: u32, mut operation: F) -> Result<T, String>
+where
+    F: FnMut() -> Result<T, String>,
+{
+    let mut attempt = 0;
+    loop {
+        match operation() {
+            Err(e) if is_offline_error(&e) && attempt < retries => {
+                let delay = RETRY_BASE_DELAY * 2u32.pow(attempt);
+                attempt += 1;
+                eprintln!(
+                    "{e}\nRetrying in {}s (attempt {attempt}/{retries})",
+                    delay.as_secs()
+                );
+                thread::sleep(delay);
+            }
+            res => return res,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+
+    #[test]
+    fn only_offline_errors_are_retried() {
+        let calls = Cell::new(0);
+        let res: Result<(), String> = retry_with_backoff(3, || {
+            calls.set(calls.get() + 1);
+            Err(String::from("Permission denied"))
+        });
+        assert!(res.is_err());
+        assert_eq!(calls.get(), 1);
+
+        let calls = Cell::new(0);
+        let res = retry_with_backoff(1, || {
+            calls.set(calls.get() + 1);
+            match calls.get() {
+                1 => Err(format!("{OFFLINE_ERROR_PREFIX} - Connection refused")),
+                _ => Ok(calls.get()),
+            }
+        });
+        assert_eq!(res, Ok(2));
+    }
+
+    #[test]
+    fn offline_errors_are_detected_within_context() {
+        assert!(is_offline_error(&format!(
+            "Unable to read remote HEAD\n{OFFLINE_ERROR_PREFIX} - timed out"
+        )));
+        assert!(!is_offline_error(
+            "SSH Connection Error:\nPermission denied"
+        ));
+    }
+}
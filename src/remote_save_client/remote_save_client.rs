@@ -1,8 +1,12 @@
+use crate::cancellation::CancellationToken;
 use crate::common::Revision;
 use crate::config::RuntimeSyncConfig;
+use crate::manifest::Manifest;
+use crate::progress::TransferProgress;
 use crate::remote_save_client::remote_lock::RemoteLock;
 use crate::remote_save_client::ssh_save_client::SshSaveClient;
-use crate::tree_utils::UploadTempFolder;
+use crate::sync_history::SyncEvent;
+use std::path::Path;
 
 pub trait RemoteSaveClient<'c> {
     fn init(config: &'c RuntimeSyncConfig) -> Self
@@ -12,6 +16,22 @@ pub trait RemoteSaveClient<'c> {
     /// Gets the current remote HEAD. This is best - effort so be wary of race-conditions.
     fn get_remote_head(&self) -> Result<Option<Revision>, String>;
 
+    /// Rewrites the remote HEAD in the current format if it was written in a legacy format.
+    /// Returns whether it was rewritten. Caller must be holding the remote lock.
+    fn migrate_remote_head(&self) -> Result<bool, String>;
+
+    /// Gets the revision log of the remote - oldest first. Empty if nothing was ever pushed with history tracking.
+    fn get_remote_history(&self) -> Result<Vec<Revision>, String>;
+
+    /// Gets the sync history of the remote (pushes/pulls from every device) - oldest first.
+    fn get_remote_sync_history(&self) -> Result<Vec<SyncEvent>, String>;
+
+    /// Appends a sync event to the remote sync history.
+    fn append_remote_sync_history(&self, event: &SyncEvent) -> Result<(), String>;
+
+    /// Gets the per-file manifest of the current remote HEAD. None if the remote HEAD was pushed without one.
+    fn get_remote_manifest(&self) -> Result<Option<Manifest>, String>;
+
     /// Triggers a remote snapshot process for the current save key configuration.
     /// Should only be triggered if there is something to snapshot. (If HEAD exists)
     fn remote_snapshot(&self) -> Result<(), String>;
@@ -20,14 +40,36 @@ pub trait RemoteSaveClient<'c> {
     /// This should promise that any other clients of the sample implementation will not conflict and modify the remote repo while the lock is held.
     fn acquire_lock<'l>(&'l self) -> Result<impl RemoteLock<'l>, String>;
 
-    /// Pushes to the remote save repository - overwriting the destination and updating the remote HEAD.
+    /// Pushes to the remote save repository - overwriting the destination and updating the remote HEAD and manifest.
+    /// The new HEAD is also appended to the remote revision log.
+    /// Exactly the files listed in the manifest are uploaded from `src_root` - streamed, without any local temporary copy.
     /// This function should implement a mirror functionality - deleting any existing files present in dst but not in src.
-    fn push(&self, path: &UploadTempFolder, new_head_hash: &Revision) -> Result<(), String>;
+    /// Transferred files and bytes must be reported through `progress` as they are sent.
+    /// If `cancel` triggers mid-upload the transfer is stopped and the partial upload discarded - leaving the remote as it was.
+    fn push(
+        &self,
+        src_root: &Path,
+        new_head_hash: &Revision,
+        manifest: &Manifest,
+        progress: &TransferProgress,
+        cancel: &CancellationToken,
+    ) -> Result<(), String>;
 
     /// Pulls from the remote save repository - overwriting the local folder.
     /// Does NOT update local HEAD.
     /// This function should NOT implement a mirror functionality - existing files present in dst should be kept regardless.
-    fn pull(&self) -> Result<(), String>;
+    /// Received files and bytes must be reported through `progress` as they arrive.
+    /// If `cancel` triggers mid-download the local folder must be left untouched.
+    fn pull(&self, progress: &TransferProgress, cancel: &CancellationToken) -> Result<(), String>;
+
+    /// Same as pull - but downloads the remote save repository into the given folder instead of the local save folder.
+    /// `dst` may hold a partial download if cancelled.
+    fn pull_into(
+        &self,
+        dst: &Path,
+        progress: &TransferProgress,
+        cancel: &CancellationToken,
+    ) -> Result<(), String>;
 }
 
 pub fn get_default_remote_save_client<'c>(
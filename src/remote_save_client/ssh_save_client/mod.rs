@@ -1,5 +1,13 @@
+mod ssh_key_params;
+mod ssh_remote_layout;
 mod ssh_remote_lock;
 mod ssh_save_client;
+mod ssh_shared_entries;
 mod ssh_utils;
 
+pub use ssh_key_params::{init_remote_key_params, read_remote_key_params};
+pub use ssh_remote_layout::{RemoteLayout, read_remote_layout};
 pub use ssh_save_client::SshSaveClient;
+pub use ssh_shared_entries::{
+    acquire_options_lock, read_remote_shared_entries, write_remote_shared_entries,
+};
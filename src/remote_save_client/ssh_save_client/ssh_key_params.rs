@@ -0,0 +1,69 @@
+use crate::config::ValidatedSyncOptions;
+use crate::config::config_commons::{REMOTE_HEAD_FOLDER_NAME, REMOTE_KEY_PARAMS_FILE_NAME};
+use crate::encryption::RemoteKeyParams;
+use crate::remote_save_client::ssh_save_client::ssh_utils::{ssh_command, ssh_command_with_stdin};
+
+/// Reads the encryption key parameters shared by every device of the remote. None if no key was created yet.
+pub fn read_remote_key_params(
+    options: &ValidatedSyncOptions,
+) -> Result<Option<RemoteKeyParams>, String> {
+    let read_command = format!(
+        "cd {dir} 2>/dev/null || exit 100; \
+        [ -r {REMOTE_HEAD_FOLDER_NAME}/{REMOTE_KEY_PARAMS_FILE_NAME} ] && cat {REMOTE_HEAD_FOLDER_NAME}/{REMOTE_KEY_PARAMS_FILE_NAME} && exit 0; \
+        [ -e {REMOTE_HEAD_FOLDER_NAME}/{REMOTE_KEY_PARAMS_FILE_NAME} ] && exit 1; \
+        exit 2",
+        dir = options.remote_sync_root,
+    );
+
+    let res = ssh_command(
+        &options.ssh_host,
+        options.ssh_port,
+        &options.remote_timeouts,
+        &read_command,
+    )?;
+    return match res.code.code() {
+        Some(0) => {
+            let filestr = String::from_utf8(res.stdout)
+                .map_err(|e| format!("Unable to read remote key parameters {}", e))?;
+            Ok(Some(RemoteKeyParams::deserialize(&filestr)?))
+        }
+        Some(1) => Err(String::from("Remote key parameters file is not readable")),
+        Some(2) | Some(100) => Ok(None),
+        Some(_) | None => Err(format!(
+            "Error ocurred reading remote key parameters - Exit Code:{}\n{}",
+            res.code_display(),
+            res.output_lossy()
+        )),
+    };
+}
+
+/// Stores the encryption key parameters on the remote - never overwriting existing ones.
+/// Returns false if another device created them first.
+pub fn init_remote_key_params(
+    options: &ValidatedSyncOptions,
+    params: &RemoteKeyParams,
+) -> Result<bool, String> {
+    // noclobber makes the redirection fail if the file already exists - atomic create.
+    let init_command = format!(
+        "mkdir -p {dir}/{REMOTE_HEAD_FOLDER_NAME} || exit 100; \
+        set -C; cat > {dir}/{REMOTE_HEAD_FOLDER_NAME}/{REMOTE_KEY_PARAMS_FILE_NAME} 2>/dev/null || exit 2",
+        dir = options.remote_sync_root,
+    );
+
+    let res = ssh_command_with_stdin(
+        &options.ssh_host,
+        options.ssh_port,
+        &options.remote_timeouts,
+        &init_command,
+        format!("{}\n", params.serialize()?).as_bytes(),
+    )?;
+    return match res.code.code() {
+        Some(0) => Ok(true),
+        Some(2) => Ok(false),
+        Some(_) | None => Err(format!(
+            "Error ocurred storing remote key parameters - Exit Code:{}\n{}",
+            res.code_display(),
+            res.output_lossy()
+        )),
+    };
+}
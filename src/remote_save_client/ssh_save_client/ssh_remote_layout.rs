@@ -0,0 +1,57 @@
+use crate::config::ValidatedSyncOptions;
+use crate::config::config_commons::REMOTE_HEAD_FOLDER_NAME;
+use crate::remote_save_client::ssh_save_client::ssh_utils::ssh_command;
+
+/// What the remote offers for syncing - see `read_remote_layout`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RemoteLayout {
+    pub root_exists: bool,
+    pub root_writable: bool,
+    /// `.cloudmeta/restic_password` - required to snapshot before overwriting remote saves.
+    pub restic_password: bool,
+    pub restic_installed: bool,
+    pub tar_installed: bool,
+    pub zstd_installed: bool,
+}
+
+/// Inspects `remoteSyncRoot` and the tools syncing relies on - in a single SSH call.
+/// Errors if the remote can't be reached at all.
+pub fn read_remote_layout(options: &ValidatedSyncOptions) -> Result<RemoteLayout, String> {
+    let layout_command = format!(
+        "cd {dir} 2>/dev/null || exit 100; \
+        [ -w . ] && echo writable; \
+        [ -r {REMOTE_HEAD_FOLDER_NAME}/restic_password ] && echo restic_password; \
+        command -v restic >/dev/null && echo restic; \
+        command -v tar >/dev/null && echo tar; \
+        command -v zstd >/dev/null && echo zstd; \
+        exit 0",
+        dir = options.remote_sync_root,
+    );
+
+    let res = ssh_command(
+        &options.ssh_host,
+        options.ssh_port,
+        &options.remote_timeouts,
+        &layout_command,
+    )?;
+    return match res.code.code() {
+        Some(0) => {
+            let stdout = String::from_utf8_lossy(&res.stdout);
+            let found: Vec<&str> = stdout.lines().map(str::trim).collect();
+            Ok(RemoteLayout {
+                root_exists: true,
+                root_writable: found.contains(&"writable"),
+                restic_password: found.contains(&"restic_password"),
+                restic_installed: found.contains(&"restic"),
+                tar_installed: found.contains(&"tar"),
+                zstd_installed: found.contains(&"zstd"),
+            })
+        }
+        Some(100) => Ok(RemoteLayout::default()),
+        Some(_) | None => Err(format!(
+            "Error ocurred inspecting the remote - Exit Code:{}\n{}",
+            res.code_display(),
+            res.output_lossy()
+        )),
+    };
+}
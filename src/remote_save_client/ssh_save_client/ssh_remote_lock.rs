@@ -1,7 +1,8 @@
 use std::time::{SystemTime, UNIX_EPOCH};
 
-use crate::config::RuntimeSyncConfig;
+use crate::config::{RemoteTimeouts, RuntimeSyncConfig};
 use crate::remote_save_client::remote_lock::RemoteLock;
+use crate::remote_save_client::retry_with_backoff;
 use crate::remote_save_client::ssh_save_client::ssh_utils::ssh_command;
 use crate::utils::get_unix_timestamp_secs;
 
@@ -10,82 +11,125 @@ pub const STALE_TIMEOUT_SECS: u64 = 300; // 5 mins
 
 /// Struct to represent a remote lock, ensuring cleanup
 pub struct SshRemoteLock<'c> {
-    config: &'c RuntimeSyncConfig,
+    ssh_host: &'c str,
+    ssh_port: u32,
+    remote_timeouts: RemoteTimeouts,
+    /// Written into the lock folder - so a retried acquire and the release only ever touch our own lock.
+    owner: String,
     acquired: bool,
 }
 
 impl<'c> RemoteLock<'c> for SshRemoteLock<'c> {
     /// Attempt to acquire the lock
     fn acquire(config: &'c RuntimeSyncConfig) -> Result<Self, String> {
-        // Check if lock exists
-        let test_lock_cmd = format!("[ -d {} ]", LOCK_FOLDER);
-        let lock_exists = ssh_command(&config.ssh_host, config.ssh_port, &test_lock_cmd)?
-            .code
-            .success();
-
-        if lock_exists {
-            // Read timestamp
-            let read_ts_cmd = format!("cat {}/timestamp", LOCK_FOLDER);
-            if let Ok(output) = ssh_command(&config.ssh_host, config.ssh_port, &read_ts_cmd) {
-                if let Ok(ts_str) = String::from_utf8(output.stdout) {
-                    if let Ok(ts) = ts_str.trim().parse::<u64>() {
-                        let now = get_unix_timestamp_secs();
-
-                        let expiry_timestamp = ts + STALE_TIMEOUT_SECS;
+        Self::acquire_on(&config.ssh_host, config.ssh_port, config.remote_timeouts)
+    }
 
-                        if expiry_timestamp < now {
-                            let rm_cmd = format!("rm -rf {}", LOCK_FOLDER);
-                            ssh_command(&config.ssh_host, config.ssh_port, &rm_cmd)?;
-                        }
-                    }
-                }
-            }
-        }
+    /// Returns whether we successfully acquired the lock
+    fn is_acquired(&self) -> bool {
+        self.acquired
+    }
+}
 
-        // Try to create the lock directory atomically
-        let mkdir_cmd = format!("mkdir {}", LOCK_FOLDER);
-        let status = ssh_command(&config.ssh_host, config.ssh_port, &mkdir_cmd)?;
+impl<'c> SshRemoteLock<'c> {
+    /// Same lock as `acquire` - for operations not bound to a sync key (e.g. shared config).
+    /// Retried while the remote is unreachable - see `try_acquire` for why that is safe.
+    pub fn acquire_on(
+        ssh_host: &'c str,
+        ssh_port: u32,
+        remote_timeouts: RemoteTimeouts,
+    ) -> Result<Self, String> {
+        let owner = new_owner_token();
+        retry_with_backoff(remote_timeouts.retries, || {
+            Self::try_acquire(ssh_host, ssh_port, remote_timeouts, &owner)
+        })
+    }
 
-        if status.code.success() {
-            // Lock acquired, write timestamp inside lock-dir/timestamp
-            let timestamp = SystemTime::now()
-                .duration_since(UNIX_EPOCH)
-                .unwrap()
-                .as_secs();
-            let write_ts_cmd = format!("echo {} > {}/timestamp", timestamp, LOCK_FOLDER);
-            ssh_command(&config.ssh_host, config.ssh_port, &write_ts_cmd)?;
+    /// Single attempt - stale-check, create and stamp the lock in one remote command.
+    /// A connection dropping after `mkdir` succeeded leaves a lock owned by `owner` - so a retry with the same owner
+    /// recognises it as its own instead of reporting it as held. A lock missing its timestamp goes stale by its mtime.
+    fn try_acquire(
+        ssh_host: &'c str,
+        ssh_port: u32,
+        remote_timeouts: RemoteTimeouts,
+        owner: &str,
+    ) -> Result<Self, String> {
+        let now = get_unix_timestamp_secs();
+        // `find -mmin` instead of `stat` - whose flags differ between GNU, BSD and busybox.
+        let stale_mins = STALE_TIMEOUT_SECS.div_ceil(60);
+        let acquire_cmd = format!(
+            "if [ -d {LOCK_FOLDER} ]; then \
+                [ \"$(cat {LOCK_FOLDER}/owner 2>/dev/null)\" = '{owner}' ] && exit 0; \
+                ts=$(cat {LOCK_FOLDER}/timestamp 2>/dev/null); \
+                if [ -n \"$ts\" ]; then [ $((ts + {STALE_TIMEOUT_SECS})) -lt {now} ] && rm -rf {LOCK_FOLDER}; \
+                elif [ -n \"$(find {LOCK_FOLDER} -maxdepth 0 -mmin +{stale_mins} 2>/dev/null)\" ]; then rm -rf {LOCK_FOLDER}; fi; \
+            fi; \
+            mkdir {LOCK_FOLDER} 2>/dev/null || exit 1; \
+            echo '{owner}' > {LOCK_FOLDER}/owner && echo {now} > {LOCK_FOLDER}/timestamp"
+        );
+        let res = ssh_command(ssh_host, ssh_port, &remote_timeouts, &acquire_cmd)?;
 
+        let acquired = match res.code.code() {
+            Some(0) => true,
+            Some(1) => false,
+            _ => {
+                return Err(format!(
+                    "Error ocurred acquiring remote lock - Exit Code:{}\n{}",
+                    res.code_display(),
+                    res.output_lossy()
+                ));
+            }
+        };
+        if acquired {
             println!("Remote Lock acquired: {}", LOCK_FOLDER);
-            Ok(Self {
-                config,
-                acquired: true,
-            })
-        } else {
-            // Could not acquire lock
-            Ok(Self {
-                config,
-                acquired: false,
-            })
         }
+        Ok(Self {
+            ssh_host,
+            ssh_port,
+            remote_timeouts,
+            owner: owner.to_string(),
+            acquired: acquired,
+        })
     }
+}
 
-    /// Returns whether we successfully acquired the lock
-    fn is_acquired(&self) -> bool {
-        self.acquired
-    }
+/// Unique per acquire call - hostname, pid and time, restricted to characters safe inside single quotes.
+fn new_owner_token() -> String {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos();
+    let hostname: String = gethostname::gethostname()
+        .to_string_lossy()
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric() || *c == '-' || *c == '.')
+        .collect();
+    format!("{hostname}-{}-{nanos}", std::process::id())
 }
 
 // Ensure lock cleanup when RemoteLock is dropped
 impl<'c> Drop for SshRemoteLock<'c> {
     fn drop(&mut self) {
         if self.acquired {
-            let rmdir_cmd = format!("rm {}/timestamp && rmdir {}", LOCK_FOLDER, LOCK_FOLDER);
-            match ssh_command(&self.config.ssh_host, self.config.ssh_port, &rmdir_cmd) {
+            // Only remove the lock if it is still ours - it may have gone stale and been taken over.
+            let rmdir_cmd = format!(
+                "[ \"$(cat {LOCK_FOLDER}/owner 2>/dev/null)\" = '{}' ] && rm -rf {LOCK_FOLDER}",
+                self.owner
+            );
+            match ssh_command(
+                self.ssh_host,
+                self.ssh_port,
+                &self.remote_timeouts,
+                &rmdir_cmd,
+            ) {
                 Ok(status) if status.code.success() => {
                     println!("Remote Lock released: {}", LOCK_FOLDER);
                 }
                 Ok(status) => {
-                    eprintln!("Failed to remove lock, exit code: {:?}", status.code.code());
+                    eprintln!(
+                        "Failed to remove lock - no longer held by this client, exit code: {:?}",
+                        status.code.code()
+                    );
                 }
                 Err(e) => {
                     eprintln!("Error releasing lock: {}", e);
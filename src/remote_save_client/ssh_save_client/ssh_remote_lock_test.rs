@@ -1,6 +1,8 @@
 use crate::utils::get_unix_timestamp_secs;
 
 use super::*;
+use crate::config::{ConflictStrategy, RemoteStorageMode, RemoteTimeouts, TransferCompression};
+use crate::hashing::HashAlgorithm;
 use globset::GlobSet;
 use serial_test::serial;
 use std::path::Path;
@@ -17,7 +19,17 @@ fn get_test_config() -> RuntimeSyncConfig {
         remote_sync_key: "test-key".to_string(),
         remote_sync_root: "/tmp/test-save".to_string(),
         local_save_folder: Path::new("").to_path_buf(),
+        save_folder_variant: String::from("saveFolderPath"),
         ignore_globset: GlobSet::empty(),
+        conflict_strategy: ConflictStrategy::Manual,
+        hash_algorithm: HashAlgorithm::Blake3,
+        transfer_compression: TransferCompression::None,
+        remote_storage: RemoteStorageMode::Tree,
+        encryption_key: None,
+        remote_timeouts: RemoteTimeouts::default(),
+        process_names: vec![],
+        rehash: false,
+        allow_unencrypted_pull: false,
         local_head_folder: Path::new("/tmp").to_path_buf(),
     };
 }
@@ -48,6 +60,7 @@ fn test_stale_lock() {
     let _ = ssh_command(
         TEST_SSH_HOST,
         TEST_SSH_PORT,
+        &RemoteTimeouts::default(),
         &format!(
             "mkdir -p {} && echo {} > {}/timestamp",
             LOCK_FOLDER, old_ts, LOCK_FOLDER
@@ -62,3 +75,57 @@ fn test_stale_lock() {
         "Lock was unable to be acquired after stale cleanup"
     );
 }
+
+#[test]
+#[serial]
+fn test_retried_acquire_recognises_own_lock() {
+    // Simulates a connection dropping after the first attempt created the lock.
+    let first = SshRemoteLock::try_acquire(
+        TEST_SSH_HOST,
+        TEST_SSH_PORT,
+        RemoteTimeouts::default(),
+        "test-owner",
+    )
+    .expect("Failed to acquire lock");
+    assert!(first.is_acquired());
+
+    let other = SshRemoteLock::acquire_on(TEST_SSH_HOST, TEST_SSH_PORT, RemoteTimeouts::default())
+        .expect("Failed to attempt lock");
+    assert!(!other.is_acquired(), "Lock of another owner was taken");
+
+    let retried = SshRemoteLock::try_acquire(
+        TEST_SSH_HOST,
+        TEST_SSH_PORT,
+        RemoteTimeouts::default(),
+        "test-owner",
+    )
+    .expect("Failed to retry lock");
+    assert!(
+        retried.is_acquired(),
+        "Retry did not recognise its own lock"
+    );
+    // Both represent the same remote lock - release it once.
+    std::mem::forget(first);
+}
+
+#[test]
+#[serial]
+fn test_lock_without_timestamp_goes_stale() {
+    // A lock whose creator died before stamping it - aged through its mtime.
+    let _ = ssh_command(
+        TEST_SSH_HOST,
+        TEST_SSH_PORT,
+        &RemoteTimeouts::default(),
+        &format!(
+            "rm -rf {LOCK_FOLDER} && mkdir {LOCK_FOLDER} && touch -d @{} {LOCK_FOLDER}",
+            get_unix_timestamp_secs() - (STALE_TIMEOUT_SECS + 60)
+        ),
+    );
+
+    let cfg = get_test_config();
+    let lock = SshRemoteLock::acquire(&cfg).expect("Failed to acquire lock after stale cleanup");
+    assert!(
+        lock.is_acquired(),
+        "Lock without timestamp never went stale"
+    );
+}
@@ -1,26 +1,34 @@
-use crate::common::Revision;
+use crate::cancellation::CancellationToken;
+use crate::common::{Revision, deserialize_revision_log};
 use crate::config::RuntimeSyncConfig;
 use crate::config::config_commons::{
-    REMOTE_HEAD_FOLDER_NAME, REMOTE_SAVES_FOLDER_NAME, REMOTE_SNAPSHOT_FOLDER_NAME,
+    REMOTE_ARCHIVE_FILE_NAME, REMOTE_ARCHIVE_MANIFEST_FILE_NAME, REMOTE_HEAD_FOLDER_NAME,
+    REMOTE_SAVES_FOLDER_NAME, REMOTE_SNAPSHOT_FOLDER_NAME,
 };
+use crate::config::{RemoteStorageMode, TransferCompression};
+use crate::encryption::{decrypt_remote_manifest, encrypt_remote_manifest};
+use crate::manifest::Manifest;
+use crate::progress::TransferProgress;
 use crate::remote_save_client::RemoteSaveClient;
+use crate::remote_save_client::offline::retry_with_backoff;
 use crate::remote_save_client::remote_lock::RemoteLock;
 use crate::remote_save_client::ssh_save_client::ssh_remote_lock::SshRemoteLock;
 use crate::remote_save_client::ssh_save_client::ssh_utils::{
-    scp_from_remote, scp_to_remote, ssh_command,
+    ssh_command, ssh_command_with_stdin, ssh_command_with_stdin_writer,
+    ssh_command_with_stdout_reader, ssh_long_running_command,
 };
-use crate::tree_utils::UploadTempFolder;
+use crate::sync_history::{SyncEvent, deserialize_sync_history};
+use crate::tree_utils::{
+    copy_folder_into, create_staging_temp_folder, unpack_tar_stream, write_tar_stream,
+};
+use std::path::Path;
 
 pub struct SshSaveClient<'c> {
     config: &'c RuntimeSyncConfig,
 }
 
-impl<'c> RemoteSaveClient<'c> for SshSaveClient<'c> {
-    fn init(config: &'c RuntimeSyncConfig) -> SshSaveClient<'c> {
-        return SshSaveClient { config: config };
-    }
-
-    fn get_remote_head(&self) -> Result<Option<Revision>, String> {
+impl<'c> SshSaveClient<'c> {
+    fn read_remote_head_str(&self) -> Result<Option<String>, String> {
         let exists_command = format!(
             "cd {dir} 2>/dev/null || exit 100; \
         [ -r {REMOTE_HEAD_FOLDER_NAME}/{key}.HEAD ] && cat {REMOTE_HEAD_FOLDER_NAME}/{key}.HEAD && exit 0; \
@@ -30,13 +38,20 @@ impl<'c> RemoteSaveClient<'c> for SshSaveClient<'c> {
             key = self.config.remote_sync_key
         );
 
-        let res = ssh_command(&self.config.ssh_host, self.config.ssh_port, &exists_command)?;
+        // Read-only - safe to retry while the remote is unreachable.
+        let res = retry_with_backoff(self.config.remote_timeouts.retries, || {
+            ssh_command(
+                &self.config.ssh_host,
+                self.config.ssh_port,
+                &self.config.remote_timeouts,
+                &exists_command,
+            )
+        })?;
         return match res.code.code() {
             Some(0) => {
                 let filestr = String::from_utf8(res.stdout)
                     .map_err(|e| format!("Unable to read file HEAD {}", e))?;
-                let rev = Revision::deserialize(filestr.trim())?;
-                return Ok(Some(rev));
+                Ok(Some(filestr.trim().to_string()))
             }
             Some(1) => Err(String::from("Remote HEAD file is not readable")),
             Some(2) => Ok(None),
@@ -48,7 +63,200 @@ impl<'c> RemoteSaveClient<'c> for SshSaveClient<'c> {
         };
     }
 
+    /// Best-effort removal of a partial upload - the current saves are left untouched.
+    fn discard_incoming(&self, incoming: &str) {
+        let discard_cmd = format!(
+            "cd {base} 2>/dev/null || exit 100; rm -rf {incoming}",
+            base = &self.config.remote_sync_root,
+        );
+        match ssh_command(
+            &self.config.ssh_host,
+            self.config.ssh_port,
+            &self.config.remote_timeouts,
+            &discard_cmd,
+        ) {
+            Ok(res) if res.code.success() => println!("Discarded partial upload {incoming}"),
+            Ok(res) => eprintln!(
+                "Warning: Unable to discard partial upload {incoming} - Exit Code:{}",
+                res.code_display()
+            ),
+            Err(e) => eprintln!("Warning: Unable to discard partial upload {incoming}\n{e}"),
+        }
+    }
+
+    fn write_remote_head(&self, head: &Revision) -> Result<(), String> {
+        let updatehead_cmd = ssh_command_with_stdin(
+            &self.config.ssh_host,
+            self.config.ssh_port,
+            &self.config.remote_timeouts,
+            &format!(
+                "cat > {base}/{REMOTE_HEAD_FOLDER_NAME}/{key}.HEAD",
+                base = &self.config.remote_sync_root,
+                key = &self.config.remote_sync_key
+            ),
+            format!("{}\n", head.serialize()).as_bytes(),
+        )?;
+
+        if !updatehead_cmd.code.success() {
+            return Err(format!(
+                "Error updating remote HEAD - Exit Code:{}\n{}",
+                updatehead_cmd.code_display(),
+                updatehead_cmd.output_lossy()
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+impl<'c> RemoteSaveClient<'c> for SshSaveClient<'c> {
+    fn init(config: &'c RuntimeSyncConfig) -> SshSaveClient<'c> {
+        return SshSaveClient { config: config };
+    }
+
+    fn get_remote_head(&self) -> Result<Option<Revision>, String> {
+        return match self.read_remote_head_str()? {
+            Some(headstr) => Ok(Some(Revision::deserialize(&headstr)?)),
+            None => Ok(None),
+        };
+    }
+
+    fn migrate_remote_head(&self) -> Result<bool, String> {
+        let Some(headstr) = self.read_remote_head_str()? else {
+            return Ok(false);
+        };
+        if !Revision::is_legacy_format(&headstr) {
+            return Ok(false);
+        }
+
+        let head = Revision::deserialize(&headstr)?;
+        self.write_remote_head(&head)?;
+        Ok(true)
+    }
+
+    fn get_remote_history(&self) -> Result<Vec<Revision>, String> {
+        let read_command = format!(
+            "cd {dir} 2>/dev/null || exit 100; \
+        [ -r {REMOTE_HEAD_FOLDER_NAME}/{key}.LOG ] && cat {REMOTE_HEAD_FOLDER_NAME}/{key}.LOG && exit 0; \
+        [ -e {REMOTE_HEAD_FOLDER_NAME}/{key}.LOG ] && exit 1; \
+        exit 2",
+            dir = self.config.remote_sync_root,
+            key = self.config.remote_sync_key
+        );
+
+        let res = ssh_command(
+            &self.config.ssh_host,
+            self.config.ssh_port,
+            &self.config.remote_timeouts,
+            &read_command,
+        )?;
+        return match res.code.code() {
+            Some(0) => {
+                let filestr = String::from_utf8(res.stdout)
+                    .map_err(|e| format!("Unable to read file LOG {}", e))?;
+                deserialize_revision_log(&filestr)
+            }
+            Some(1) => Err(String::from("Remote LOG file is not readable")),
+            Some(2) => Ok(vec![]),
+            Some(_) | None => Err(format!(
+                "Error ocurred during reading SSH remote LOG - Exit Code:{}\n{}",
+                res.code_display(),
+                res.output_lossy()
+            )),
+        };
+    }
+
+    fn get_remote_sync_history(&self) -> Result<Vec<SyncEvent>, String> {
+        let read_command = format!(
+            "cd {dir} 2>/dev/null || exit 100; \
+        [ -r {REMOTE_HEAD_FOLDER_NAME}/{key}.HISTORY ] && cat {REMOTE_HEAD_FOLDER_NAME}/{key}.HISTORY && exit 0; \
+        [ -e {REMOTE_HEAD_FOLDER_NAME}/{key}.HISTORY ] && exit 1; \
+        exit 2",
+            dir = self.config.remote_sync_root,
+            key = self.config.remote_sync_key
+        );
+
+        let res = ssh_command(
+            &self.config.ssh_host,
+            self.config.ssh_port,
+            &self.config.remote_timeouts,
+            &read_command,
+        )?;
+        return match res.code.code() {
+            Some(0) => {
+                let filestr = String::from_utf8(res.stdout)
+                    .map_err(|e| format!("Unable to read file HISTORY {}", e))?;
+                deserialize_sync_history(&filestr)
+            }
+            Some(1) => Err(String::from("Remote HISTORY file is not readable")),
+            Some(2) => Ok(vec![]),
+            Some(_) | None => Err(format!(
+                "Error ocurred during reading SSH remote HISTORY - Exit Code:{}\n{}",
+                res.code_display(),
+                res.output_lossy()
+            )),
+        };
+    }
+
+    fn append_remote_sync_history(&self, event: &SyncEvent) -> Result<(), String> {
+        let res = ssh_command_with_stdin(
+            &self.config.ssh_host,
+            self.config.ssh_port,
+            &self.config.remote_timeouts,
+            &format!(
+                "mkdir -p {base}/{REMOTE_HEAD_FOLDER_NAME} && cat >> {base}/{REMOTE_HEAD_FOLDER_NAME}/{key}.HISTORY",
+                base = &self.config.remote_sync_root,
+                key = &self.config.remote_sync_key
+            ),
+            format!("{}\n", event.serialize()?).as_bytes(),
+        )?;
+
+        if !res.code.success() {
+            return Err(format!(
+                "Error appending to remote HISTORY - Exit Code:{}\n{}",
+                res.code_display(),
+                res.output_lossy()
+            ));
+        }
+
+        Ok(())
+    }
+
+    fn get_remote_manifest(&self) -> Result<Option<Manifest>, String> {
+        let read_command = format!(
+            "cd {dir} 2>/dev/null || exit 100; \
+        [ -r {REMOTE_HEAD_FOLDER_NAME}/{key}.MANIFEST ] && cat {REMOTE_HEAD_FOLDER_NAME}/{key}.MANIFEST && exit 0; \
+        [ -e {REMOTE_HEAD_FOLDER_NAME}/{key}.MANIFEST ] && exit 1; \
+        exit 2",
+            dir = self.config.remote_sync_root,
+            key = self.config.remote_sync_key
+        );
+
+        let res = ssh_command(
+            &self.config.ssh_host,
+            self.config.ssh_port,
+            &self.config.remote_timeouts,
+            &read_command,
+        )?;
+        return match res.code.code() {
+            Some(0) => Ok(Some(decrypt_remote_manifest(
+                self.config.encryption_key.as_ref(),
+                self.config.allow_unencrypted_pull,
+                &self.config.remote_sync_key,
+                res.stdout,
+            )?)),
+            Some(1) => Err(String::from("Remote MANIFEST file is not readable")),
+            Some(2) => Ok(None),
+            Some(_) | None => Err(format!(
+                "Error ocurred during reading SSH remote MANIFEST - Exit Code:{}\n{}",
+                res.code_display(),
+                res.output_lossy()
+            )),
+        };
+    }
+
     fn acquire_lock<'l>(&'l self) -> Result<impl RemoteLock<'l>, String> {
+        // Retries while unreachable on its own - recognising a lock it created before the connection dropped.
         return SshRemoteLock::acquire(self.config);
     }
 
@@ -63,7 +271,15 @@ impl<'c> RemoteSaveClient<'c> for SshSaveClient<'c> {
             key = self.config.remote_sync_key
         );
 
-        let res = ssh_command(&self.config.ssh_host, self.config.ssh_port, &exists_command)?;
+        // No deadline - a large first backup takes long. Only connection failures are retried - backing up again just dedupes.
+        let res = retry_with_backoff(self.config.remote_timeouts.retries, || {
+            ssh_long_running_command(
+                &self.config.ssh_host,
+                self.config.ssh_port,
+                &self.config.remote_timeouts,
+                &exists_command,
+            )
+        })?;
 
         return match res.code.code() {
             Some(0) => Ok(()),
@@ -78,85 +294,221 @@ impl<'c> RemoteSaveClient<'c> for SshSaveClient<'c> {
         };
     }
 
-    fn push(&self, src_path: &UploadTempFolder, new_head: &Revision) -> Result<(), String> {
-        let rmrf_cmd = ssh_command(
+    fn push(
+        &self,
+        src_root: &Path,
+        new_head: &Revision,
+        manifest: &Manifest,
+        progress: &TransferProgress,
+        cancel: &CancellationToken,
+    ) -> Result<(), String> {
+        // Receive into a staging folder first - the current saves are only replaced once the stream fully arrived.
+        let incoming = format!(
+            "{REMOTE_SAVES_FOLDER_NAME}/{}.incoming",
+            self.config.remote_sync_key
+        );
+        let receive_cmd = match (self.config.remote_storage, self.config.transfer_compression) {
+            (RemoteStorageMode::Archive, _) => {
+                format!("cat > {incoming}/{REMOTE_ARCHIVE_FILE_NAME}")
+            }
+            (RemoteStorageMode::Tree, TransferCompression::None) => {
+                format!("tar -xf - -C {incoming}")
+            }
+            (RemoteStorageMode::Tree, TransferCompression::Zstd) => format!(
+                "{{ command -v zstd >/dev/null || exit 104; }}; zstd -dc | tar -xf - -C {incoming}"
+            ),
+        };
+        let compress = self.config.remote_storage == RemoteStorageMode::Archive
+            || self.config.transfer_compression == TransferCompression::Zstd;
+        let remote_manifest = encrypt_remote_manifest(
+            self.config.encryption_key.as_ref(),
+            &self.config.remote_sync_key,
+            manifest,
+        )?;
+
+        let upload_res = ssh_command_with_stdin_writer(
             &self.config.ssh_host,
             self.config.ssh_port,
+            &self.config.remote_timeouts,
             &format!(
-                "mkdir -p {base}/{REMOTE_SAVES_FOLDER_NAME} && rm -rf {base}/{REMOTE_SAVES_FOLDER_NAME}/{key}",
+                "cd {base} 2>/dev/null || exit 100; \
+                mkdir -p {REMOTE_SAVES_FOLDER_NAME} || exit 101; \
+                rm -rf {incoming} && mkdir {incoming} || exit 102; \
+                {receive_cmd} || exit 103",
                 base = &self.config.remote_sync_root,
-                key = &self.config.remote_sync_key
             ),
-        )?;
+            cancel,
+            |stdin| {
+                write_tar_stream(
+                    stdin,
+                    src_root,
+                    manifest,
+                    compress,
+                    self.config.encryption_key.as_ref(),
+                    progress,
+                )
+            },
+        )
+        .and_then(|upload_cmd| match upload_cmd.code.code() {
+            Some(0) => Ok(()),
+            Some(104) => Err(String::from(
+                "zstd is not installed on the remote - required for transferCompression zstd",
+            )),
+            _ => Err(format!(
+                "Error ocurred during upload stream - Exit Code:{}\n{}",
+                upload_cmd.code_display(),
+                upload_cmd.output_lossy()
+            )),
+        })
+        // Last chance to cancel - past this point the remote is updated and must be completed.
+        .and_then(|_| cancel.check());
+
+        if let Err(e) = upload_res {
+            self.discard_incoming(&incoming);
+            return Err(e);
+        }
+        progress.finish();
+
+        let swap_cmd = format!(
+            "cd {base} 2>/dev/null || exit 100; \
+            rm -rf {REMOTE_SAVES_FOLDER_NAME}/{key} && mv {incoming} {REMOTE_SAVES_FOLDER_NAME}/{key}",
+            base = &self.config.remote_sync_root,
+            key = &self.config.remote_sync_key
+        );
+        let swap_res = match self.config.remote_storage {
+            RemoteStorageMode::Tree => ssh_command(
+                &self.config.ssh_host,
+                self.config.ssh_port,
+                &self.config.remote_timeouts,
+                &swap_cmd,
+            )?,
+            // Archive keeps its manifest alongside - so the folder is self-describing.
+            RemoteStorageMode::Archive => ssh_command_with_stdin(
+                &self.config.ssh_host,
+                self.config.ssh_port,
+                &self.config.remote_timeouts,
+                &format!(
+                    "cat > {base}/{incoming}/{REMOTE_ARCHIVE_MANIFEST_FILE_NAME} || exit 105; {swap_cmd}",
+                    base = &self.config.remote_sync_root,
+                ),
+                &remote_manifest,
+            )?,
+        };
 
-        if !rmrf_cmd.code.success() {
+        if !swap_res.code.success() {
             return Err(format!(
-                "Error ocurred during pre-SCP cleanup - Exit Code:{}\n{}",
-                rmrf_cmd.code_display(),
-                rmrf_cmd.output_lossy()
+                "Error replacing remote saves with uploaded ones - Exit Code:{}\n{}",
+                swap_res.code_display(),
+                swap_res.output_lossy()
             ));
         }
 
-        let scp_result = scp_to_remote(
+        // NOTE: Readers must verify the manifest tree hash against the HEAD - a failure below leaves it stale.
+        let updatemanifest_cmd = ssh_command_with_stdin(
             &self.config.ssh_host,
             self.config.ssh_port,
-            &src_path.path,
+            &self.config.remote_timeouts,
             &format!(
-                "{base}/{REMOTE_SAVES_FOLDER_NAME}/{key}",
+                "cat > {base}/{REMOTE_HEAD_FOLDER_NAME}/{key}.MANIFEST",
                 base = &self.config.remote_sync_root,
                 key = &self.config.remote_sync_key
             ),
+            &remote_manifest,
         )?;
 
-        if !scp_result.code.success() {
+        if !updatemanifest_cmd.code.success() {
             return Err(format!(
-                "Error ocurred during SCP - Exit Code:{}\n{}",
-                scp_result.code_display(),
-                scp_result.output_lossy()
+                "Error updating remote MANIFEST - Exit Code:{}\n{}",
+                updatemanifest_cmd.code_display(),
+                updatemanifest_cmd.output_lossy()
             ));
         }
 
-        let updatehead_cmd = ssh_command(
+        self.write_remote_head(new_head)?;
+
+        let appendlog_cmd = ssh_command_with_stdin(
             &self.config.ssh_host,
             self.config.ssh_port,
+            &self.config.remote_timeouts,
             &format!(
-                "echo \"{headstr}\" > {base}/{REMOTE_HEAD_FOLDER_NAME}/{key}.HEAD",
-                headstr = new_head.serialize(),
+                "cat >> {base}/{REMOTE_HEAD_FOLDER_NAME}/{key}.LOG",
                 base = &self.config.remote_sync_root,
                 key = &self.config.remote_sync_key
             ),
+            format!("{}\n", new_head.serialize_log_entry()).as_bytes(),
         )?;
 
-        if !updatehead_cmd.code.success() {
+        if !appendlog_cmd.code.success() {
             return Err(format!(
-                "Error updating remote HEAD - Exit Code:{}\n{}",
-                updatehead_cmd.code_display(),
-                updatehead_cmd.output_lossy()
+                "Error appending to remote LOG - Exit Code:{}\n{}",
+                appendlog_cmd.code_display(),
+                appendlog_cmd.output_lossy()
             ));
         }
 
         Ok(())
     }
 
-    fn pull(&self) -> Result<(), String> {
-        let scp_result = scp_from_remote(
+    fn pull(&self, progress: &TransferProgress, cancel: &CancellationToken) -> Result<(), String> {
+        // Download fully before touching the local saves - an interrupted pull must not leave a mix of both.
+        let staging = create_staging_temp_folder()?;
+        self.pull_into(&staging.path, progress, cancel)?;
+        copy_folder_into(&staging.path, &self.config.local_save_folder)
+    }
+
+    fn pull_into(
+        &self,
+        dst: &Path,
+        progress: &TransferProgress,
+        cancel: &CancellationToken,
+    ) -> Result<(), String> {
+        // Archives are sent as stored. Trees are packed on the fly - the receiving side detects compression.
+        let pack_tree_cmd = match self.config.transfer_compression {
+            TransferCompression::None => String::from("tar -cf - ."),
+            TransferCompression::Zstd => {
+                String::from("{ command -v zstd >/dev/null || exit 104; }; tar -cf - . | zstd -c")
+            }
+        };
+
+        let download_cmd = ssh_command_with_stdout_reader(
             &self.config.ssh_host,
             self.config.ssh_port,
+            &self.config.remote_timeouts,
             &format!(
-                "{base}/{REMOTE_SAVES_FOLDER_NAME}/{key}/.", // use this syntax to ensure full copy
+                "cd {base}/{REMOTE_SAVES_FOLDER_NAME}/{key} 2>/dev/null || exit 100; \
+                if [ -f {REMOTE_ARCHIVE_FILE_NAME} ]; then cat {REMOTE_ARCHIVE_FILE_NAME}; else {pack_tree_cmd}; fi",
                 base = &self.config.remote_sync_root,
                 key = &self.config.remote_sync_key
             ),
-            &self.config.local_save_folder,
+            cancel,
+            |stdout| {
+                unpack_tar_stream(
+                    stdout,
+                    dst,
+                    self.config.encryption_key.as_ref(),
+                    self.config.allow_unencrypted_pull,
+                    progress,
+                )
+            },
         )?;
 
-        if !scp_result.code.success() {
-            return Err(format!(
-                "Error ocurred during SCP - Exit Code:{}\n{}",
-                scp_result.code_display(),
-                scp_result.output_lossy()
-            ));
+        if !download_cmd.code.success() {
+            return Err(match download_cmd.code.code() {
+                Some(100) => format!(
+                    "No remote saves found for key {}",
+                    self.config.remote_sync_key
+                ),
+                Some(104) => String::from(
+                    "zstd is not installed on the remote - required for transferCompression zstd",
+                ),
+                _ => format!(
+                    "Error ocurred during download stream - Exit Code:{}\n{}",
+                    download_cmd.code_display(),
+                    download_cmd.output_lossy()
+                ),
+            });
         }
+        progress.finish();
 
         Ok(())
     }
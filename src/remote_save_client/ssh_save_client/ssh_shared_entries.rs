@@ -0,0 +1,79 @@
+use crate::config::ValidatedSyncOptions;
+use crate::config::config_commons::{REMOTE_HEAD_FOLDER_NAME, REMOTE_SHARED_ENTRIES_FILE_NAME};
+use crate::remote_save_client::RemoteLock;
+use crate::remote_save_client::retry_with_backoff;
+use crate::remote_save_client::ssh_save_client::ssh_remote_lock::SshRemoteLock;
+use crate::remote_save_client::ssh_save_client::ssh_utils::{ssh_command, ssh_command_with_stdin};
+
+/// Reads the sync entries shared by every device of the remote. None if none were pushed yet.
+pub fn read_remote_shared_entries(
+    options: &ValidatedSyncOptions,
+) -> Result<Option<String>, String> {
+    let read_command = format!(
+        "cd {dir} 2>/dev/null || exit 100; \
+        [ -r {REMOTE_HEAD_FOLDER_NAME}/{REMOTE_SHARED_ENTRIES_FILE_NAME} ] && cat {REMOTE_HEAD_FOLDER_NAME}/{REMOTE_SHARED_ENTRIES_FILE_NAME} && exit 0; \
+        [ -e {REMOTE_HEAD_FOLDER_NAME}/{REMOTE_SHARED_ENTRIES_FILE_NAME} ] && exit 1; \
+        exit 2",
+        dir = options.remote_sync_root,
+    );
+
+    let res = retry_with_backoff(options.remote_timeouts.retries, || {
+        ssh_command(
+            &options.ssh_host,
+            options.ssh_port,
+            &options.remote_timeouts,
+            &read_command,
+        )
+    })?;
+    return match res.code.code() {
+        Some(0) => {
+            Ok(Some(String::from_utf8(res.stdout).map_err(|e| {
+                format!("Unable to read remote shared entries {}", e)
+            })?))
+        }
+        Some(1) => Err(String::from("Remote shared entries file is not readable")),
+        Some(2) | Some(100) => Ok(None),
+        Some(_) | None => Err(format!(
+            "Error ocurred reading remote shared entries - Exit Code:{}\n{}",
+            res.code_display(),
+            res.output_lossy()
+        )),
+    };
+}
+
+/// Replaces the remote shared entries - written to a temporary file first so readers never see a partial file.
+/// Caller must be holding the remote lock.
+pub fn write_remote_shared_entries(
+    options: &ValidatedSyncOptions,
+    content: &str,
+) -> Result<(), String> {
+    let write_command = format!(
+        "mkdir -p {dir}/{REMOTE_HEAD_FOLDER_NAME} || exit 100; \
+        cat > {dir}/{REMOTE_HEAD_FOLDER_NAME}/{REMOTE_SHARED_ENTRIES_FILE_NAME}.tmp && \
+        mv {dir}/{REMOTE_HEAD_FOLDER_NAME}/{REMOTE_SHARED_ENTRIES_FILE_NAME}.tmp {dir}/{REMOTE_HEAD_FOLDER_NAME}/{REMOTE_SHARED_ENTRIES_FILE_NAME}",
+        dir = options.remote_sync_root,
+    );
+
+    let res = ssh_command_with_stdin(
+        &options.ssh_host,
+        options.ssh_port,
+        &options.remote_timeouts,
+        &write_command,
+        content.as_bytes(),
+    )?;
+    if !res.code.success() {
+        return Err(format!(
+            "Error ocurred writing remote shared entries - Exit Code:{}\n{}",
+            res.code_display(),
+            res.output_lossy()
+        ));
+    }
+    Ok(())
+}
+
+/// Acquires the same remote lock used while syncing saves - with the same retries.
+pub fn acquire_options_lock<'o>(
+    options: &'o ValidatedSyncOptions,
+) -> Result<impl RemoteLock<'o>, String> {
+    SshRemoteLock::acquire_on(&options.ssh_host, options.ssh_port, options.remote_timeouts)
+}
@@ -1,8 +1,36 @@
 use std::{
-    path::{MAIN_SEPARATOR_STR, Path},
-    process::{Command, ExitStatus},
+    io::{self, Read, Write},
+    process::{Child, ChildStdin, ChildStdout, Command, ExitStatus, Output, Stdio},
+    sync::{
+        Mutex,
+        atomic::{AtomicBool, Ordering},
+    },
+    thread,
+    time::{Duration, Instant},
 };
 
+use crate::cancellation::CancellationToken;
+use crate::config::RemoteTimeouts;
+use crate::remote_save_client::OFFLINE_ERROR_PREFIX;
+
+/// How often streaming commands check for cancellation (and whether the child exited).
+const CANCEL_POLL_INTERVAL: Duration = Duration::from_millis(50);
+/// Keepalives missed before SSH drops a stalled connection.
+const SERVER_ALIVE_COUNT_MAX: u64 = 3;
+/// SSH errors (exit code 255) meaning the host could not be reached - as opposed to e.g. auth failures.
+const UNREACHABLE_SSH_ERRORS: &[&str] = &[
+    "Could not resolve hostname",
+    "Connection timed out",
+    "Operation timed out",
+    "Connection refused",
+    "No route to host",
+    "Network is unreachable",
+    "Connection reset",
+    "Connection closed",
+    "Broken pipe",
+    "Timeout, server",
+];
+
 /// Result of an SSH command
 #[derive(Debug)]
 pub struct SshOutput {
@@ -43,10 +71,19 @@ impl SshOutput {
     }
 }
 
-/// Runs a command over SSH and returns both the ExitStatus and stdout
-pub fn ssh_command(host: &str, port: u32, cmd: &str) -> Result<SshOutput, String> {
+fn build_ssh_command(host: &str, port: u32, timeouts: &RemoteTimeouts, cmd: &str) -> Command {
     let mut command = Command::new("ssh");
     command.args(["-p", &port.to_string()]).arg(host);
+    // Keepalives drop stalled connections after roughly the operation timeout - also covers long streamed transfers.
+    let server_alive_interval = (timeouts.operation.as_secs() / SERVER_ALIVE_COUNT_MAX).max(1);
+    command.args([
+        "-o",
+        &format!("ConnectTimeout={}", timeouts.connect.as_secs().max(1)),
+        "-o",
+        &format!("ServerAliveInterval={server_alive_interval}"),
+        "-o",
+        &format!("ServerAliveCountMax={SERVER_ALIVE_COUNT_MAX}"),
+    ]);
 
     #[cfg(feature = "insecure-ssh")]
     {
@@ -59,10 +96,21 @@ pub fn ssh_command(host: &str, port: u32, cmd: &str) -> Result<SshOutput, String
     }
 
     command.arg(cmd);
+    command
+}
 
-    let output = command.output().map_err(|e| e.to_string())?;
+fn to_ssh_output(output: Output) -> Result<SshOutput, String> {
     if output.status.code() == Some(255) {
         let error = String::from_utf8(output.stderr).unwrap_or_default();
+        if UNREACHABLE_SSH_ERRORS
+            .iter()
+            .any(|pattern| error.contains(pattern))
+        {
+            return Err(format!(
+                "{OFFLINE_ERROR_PREFIX} - SSH Connection Error:\n{}",
+                error
+            ));
+        }
         return Err(format!("SSH Connection Error:\n{}", error));
     }
 
@@ -73,68 +121,295 @@ pub fn ssh_command(host: &str, port: u32, cmd: &str) -> Result<SshOutput, String
     })
 }
 
-/// Internal helper to run `scp` with the given arguments.
-fn run_scp(args: &[&str]) -> Result<SshOutput, String> {
-    println!("Executing: scp {}", args.join(" "));
-    let mut command = Command::new("scp");
+/// Runs a command over SSH and returns both the ExitStatus and stdout.
+/// Killed once it exceeds the operation timeout - see `wait_with_output_cancellable`.
+pub fn ssh_command(
+    host: &str,
+    port: u32,
+    timeouts: &RemoteTimeouts,
+    cmd: &str,
+) -> Result<SshOutput, String> {
+    run_ssh_command(host, port, timeouts, cmd, Some(timeouts.operation))
+}
 
-    #[cfg(feature = "insecure-ssh")]
-    {
-        command.args([
-            "-o",
-            "StrictHostKeyChecking=no",
-            "-o",
-            "UserKnownHostsFile=/dev/null",
-        ]);
+/// Like `ssh_command` - but without a deadline, for remote work that may legitimately take long (e.g. restic backups).
+/// A stalled connection is still dropped by the SSH keepalives.
+pub fn ssh_long_running_command(
+    host: &str,
+    port: u32,
+    timeouts: &RemoteTimeouts,
+    cmd: &str,
+) -> Result<SshOutput, String> {
+    run_ssh_command(host, port, timeouts, cmd, None)
+}
+
+fn run_ssh_command(
+    host: &str,
+    port: u32,
+    timeouts: &RemoteTimeouts,
+    cmd: &str,
+    max_duration: Option<Duration>,
+) -> Result<SshOutput, String> {
+    let child = build_ssh_command(host, port, timeouts, cmd)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| e.to_string())?;
+
+    let deadline = max_duration.map(|max_duration| Instant::now() + max_duration);
+    let (output, _) =
+        wait_with_output_cancellable(child, &CancellationToken::new(), deadline, || ())?;
+    to_ssh_output(output)
+}
+
+/// Runs a command over SSH feeding the given bytes as its stdin. Meant for small payloads (HEAD, manifests).
+/// Killed once it exceeds the operation timeout - see `wait_with_output_cancellable`.
+pub fn ssh_command_with_stdin(
+    host: &str,
+    port: u32,
+    timeouts: &RemoteTimeouts,
+    cmd: &str,
+    stdin: &[u8],
+) -> Result<SshOutput, String> {
+    let mut child = build_ssh_command(host, port, timeouts, cmd)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| e.to_string())?;
+
+    let child_stdin = child.stdin.take();
+    let deadline = Some(Instant::now() + timeouts.operation);
+    let (output, write_res) =
+        wait_with_output_cancellable(child, &CancellationToken::new(), deadline, || {
+            match child_stdin {
+                Some(mut child_stdin) => child_stdin.write_all(stdin),
+                None => Ok(()),
+            } // stdin dropped here - so remote sees EOF
+        })?;
+
+    let output = to_ssh_output(output)?;
+    if let Err(e) = write_res {
+        return Err(format!(
+            "Unable to write to SSH stdin\n{e}\n{}",
+            output.output_lossy()
+        ));
     }
 
-    command.args(args);
+    Ok(output)
+}
 
-    let output = command.output().map_err(|e| e.to_string())?;
-    if output.status.code() == Some(255) {
-        let error = String::from_utf8(output.stderr).unwrap_or_default();
-        return Err(format!("SSH Connection Error:\n{}", error));
+fn read_pipe(pipe: Option<impl Read>) -> Vec<u8> {
+    let mut buf = vec![];
+    if let Some(mut pipe) = pipe {
+        let _ = pipe.read_to_end(&mut buf);
     }
+    buf
+}
 
-    Ok(SshOutput {
-        code: output.status,
-        stdout: output.stdout,
-        stderr: output.stderr,
-    })
+/// Waits for the child to exit while `io` streams to/from it on the current thread.
+/// The child is killed as soon as `cancel` triggers or `deadline` passes - which also unblocks a stalled `io`.
+/// A deadline kill is NOT an offline error - the remote was reachable and may still be running the command, so it must not be retried.
+fn wait_with_output_cancellable<T>(
+    mut child: Child,
+    cancel: &CancellationToken,
+    deadline: Option<Instant>,
+    io: impl FnOnce() -> T,
+) -> Result<(Output, T), String> {
+    let stdout_pipe = child.stdout.take();
+    let stderr_pipe = child.stderr.take();
+    let child = Mutex::new(child);
+    let exited = AtomicBool::new(false);
+    let timed_out = AtomicBool::new(false);
+    let started_at = Instant::now();
+
+    let (status, io_res, stdout, stderr) = thread::scope(|scope| {
+        let stdout_reader = scope.spawn(move || read_pipe(stdout_pipe));
+        let stderr_reader = scope.spawn(move || read_pipe(stderr_pipe));
+        scope.spawn(|| {
+            while !exited.load(Ordering::SeqCst) {
+                if cancel.is_cancelled() {
+                    let _ = child.lock().unwrap().kill();
+                    return;
+                }
+                if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+                    timed_out.store(true, Ordering::SeqCst);
+                    let _ = child.lock().unwrap().kill();
+                    return;
+                }
+                thread::sleep(CANCEL_POLL_INTERVAL);
+            }
+        });
+
+        let io_res = io();
+        let status = loop {
+            match child.lock().unwrap().try_wait() {
+                Ok(Some(status)) => break Ok(status),
+                Ok(None) => {}
+                Err(e) => break Err(e.to_string()),
+            }
+            thread::sleep(CANCEL_POLL_INTERVAL);
+        };
+        // Always stop the watcher - even on error - otherwise the scope never joins.
+        exited.store(true, Ordering::SeqCst);
+
+        (
+            status,
+            io_res,
+            stdout_reader.join().unwrap_or_default(),
+            stderr_reader.join().unwrap_or_default(),
+        )
+    });
+
+    cancel.check()?;
+    if timed_out.load(Ordering::SeqCst) {
+        return Err(format!(
+            "Remote command timed out after {}s - it may still be running on the remote",
+            started_at.elapsed().as_secs()
+        ));
+    }
+    let output = Output {
+        status: status?,
+        stdout,
+        stderr,
+    };
+    Ok((output, io_res))
 }
 
-/// Copy from local -> remote via scp
-pub fn scp_to_remote(
-    ssh_host: &str,
-    scp_port: u32,
-    src_folder: &Path,
-    dst_folder: &str,
-) -> Result<SshOutput, String> {
-    let scp_source = src_folder
-        .to_str()
-        .ok_or_else(|| String::from("Invalid source folder for scp"))?;
+/// Runs a command over SSH streaming whatever `write_stdin` writes as its stdin (e.g. a tar stream).
+/// Killed if `cancel` triggers - returning `CANCELLED_ERROR`.
+pub fn ssh_command_with_stdin_writer<F>(
+    host: &str,
+    port: u32,
+    timeouts: &RemoteTimeouts,
+    cmd: &str,
+    cancel: &CancellationToken,
+    write_stdin: F,
+) -> Result<SshOutput, String>
+where
+    F: FnOnce(&mut ChildStdin) -> io::Result<()>,
+{
+    cancel.check()?;
+    let mut child = build_ssh_command(host, port, timeouts, cmd)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| e.to_string())?;
 
-    let scp_target = format!("{}:{}", ssh_host, dst_folder);
+    let child_stdin = child.stdin.take();
+    let (output, write_res) =
+        wait_with_output_cancellable(child, cancel, None, || match child_stdin {
+            Some(mut child_stdin) => write_stdin(&mut child_stdin),
+            None => Ok(()),
+        })?; // stdin dropped once written - so remote sees EOF
 
-    let args = ["-P", &scp_port.to_string(), "-r", scp_source, &scp_target];
-    run_scp(&args)
+    // Always collect the output - if the remote died mid-stream its error is more useful than the broken pipe.
+    let output = to_ssh_output(output)?;
+    if let Err(e) = write_res {
+        return Err(format!(
+            "Unable to write to SSH stdin\n{e}\n{}",
+            output.output_lossy()
+        ));
+    }
+
+    Ok(output)
 }
 
-/// Copy from remote -> local via scp
-pub fn scp_from_remote(
-    ssh_host: &str,
-    scp_port: u32,
-    src_folder: &str,
-    dst_folder: &Path,
-) -> Result<SshOutput, String> {
-    let scp_source = format!("{}:{}", ssh_host, src_folder);
+/// Runs a command over SSH handing its stdout to `read_stdout` as it streams (e.g. a tar stream).
+/// The returned output holds no stdout - whatever `read_stdout` did not consume is discarded.
+/// Killed if `cancel` triggers - returning `CANCELLED_ERROR`.
+pub fn ssh_command_with_stdout_reader<F>(
+    host: &str,
+    port: u32,
+    timeouts: &RemoteTimeouts,
+    cmd: &str,
+    cancel: &CancellationToken,
+    read_stdout: F,
+) -> Result<SshOutput, String>
+where
+    F: FnOnce(&mut ChildStdout) -> io::Result<()>,
+{
+    cancel.check()?;
+    let mut child = build_ssh_command(host, port, timeouts, cmd)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| e.to_string())?;
 
-    let scp_target = dst_folder
-        .to_str()
-        .ok_or_else(|| String::from("Invalid destination folder for scp"))?;
-    // Add separator + dot to ensure folder contents are copied
-    let scp_target = format!("{}{}.", scp_target, MAIN_SEPARATOR_STR);
+    let child_stdout = child.stdout.take();
+    let (output, read_res) =
+        wait_with_output_cancellable(child, cancel, None, || match child_stdout {
+            Some(mut child_stdout) => {
+                let read_res = read_stdout(&mut child_stdout);
+                // Drain whatever wasn't consumed - the remote would block on a full pipe otherwise.
+                let _ = io::copy(&mut child_stdout, &mut io::sink());
+                read_res
+            }
+            None => Ok(()),
+        })?;
 
-    let args = ["-P", &scp_port.to_string(), "-r", &scp_source, &scp_target];
-    run_scp(&args)
+    // Remote failures (e.g. missing folder) explain a failed read better - so check the exit code first.
+    let output = to_ssh_output(output)?;
+    if !output.code.success() {
+        return Ok(output);
+    }
+    if let Err(e) = read_res {
+        return Err(format!(
+            "Unable to read from SSH stdout\n{e}\n{}",
+            output.output_lossy()
+        ));
+    }
+
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serial_test::serial;
+    use std::time::Instant;
+
+    // Serialized with other tests spawning children - `run_and_wait_for_process_tree` reaps any child.
+    #[test]
+    #[serial(child_processes)]
+    fn cancel_kills_running_child() {
+        let child = Command::new("sleep")
+            .arg("10")
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .unwrap();
+        let cancel = CancellationToken::new();
+        let started_at = Instant::now();
+
+        let res = wait_with_output_cancellable(child, &cancel, None, || cancel.cancel());
+
+        assert_eq!(res.unwrap_err(), crate::cancellation::CANCELLED_ERROR);
+        assert!(started_at.elapsed() < Duration::from_secs(5));
+        // Token can be re-armed for a retry.
+        cancel.reset();
+        assert!(cancel.check().is_ok());
+    }
+
+    #[test]
+    #[serial(child_processes)]
+    fn deadline_kills_child_without_offline_error() {
+        let child = Command::new("sleep")
+            .arg("10")
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .unwrap();
+        let deadline = Some(Instant::now() + Duration::from_millis(200));
+
+        let res = wait_with_output_cancellable(child, &CancellationToken::new(), deadline, || ());
+
+        let err = res.unwrap_err();
+        assert!(err.contains("timed out"), "{err}");
+        // Retrying would run the command a second time next to the one possibly still running remotely.
+        assert!(!crate::remote_save_client::is_offline_error(&err), "{err}");
+    }
 }
@@ -0,0 +1,233 @@
+use crate::config::CURRENT_OS_NAME;
+use crate::config::config_commons::expand_config_placeholders;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Save locations of known games - YAML keyed by title, in the style of the Ludusavi manifest:
+///
+/// ```yaml
+/// Hollow Knight:
+///   steam:
+///     id: 367520
+///   files:
+///     "{{HOME}}/.config/unity3d/Team Cherry/Hollow Knight":
+///       when:
+///         - os: linux
+///     "{{APPDATA}}/../LocalLow/Team Cherry/Hollow Knight":
+///       tags: [save]
+///       when:
+///         - os: windows
+/// ```
+///
+/// Paths use the config placeholders. Entries tagged without `save` (e.g. only `config`) are ignored.
+pub type SaveDatabase = BTreeMap<String, SaveDatabaseGame>;
+
+#[derive(Deserialize, Debug, Clone, Default)]
+pub struct SaveDatabaseGame {
+    pub steam: Option<SteamInfo>,
+    #[serde(default)]
+    pub files: BTreeMap<String, Option<FileRule>>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct SteamInfo {
+    pub id: u64,
+}
+
+#[derive(Deserialize, Debug, Clone, Default)]
+pub struct FileRule {
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub when: Vec<FileCondition>,
+}
+
+#[derive(Deserialize, Debug, Clone, Default)]
+pub struct FileCondition {
+    pub os: Option<String>,
+}
+
+pub const SAVE_TAG: &str = "save";
+
+/// OS name of a database condition as used in the config - Ludusavi calls macOS `mac`.
+fn config_os_name(database_os: &str) -> &str {
+    match database_os {
+        "mac" => "macos",
+        os => os,
+    }
+}
+
+impl FileRule {
+    fn is_save(&self) -> bool {
+        self.tags.is_empty() || self.tags.iter().any(|tag| tag == SAVE_TAG)
+    }
+
+    fn applies_to_os(&self, os: &str) -> bool {
+        let os_conditions: Vec<&str> = self
+            .when
+            .iter()
+            .filter_map(|condition| condition.os.as_deref())
+            .map(config_os_name)
+            .collect();
+        os_conditions.is_empty() || os_conditions.contains(&os)
+    }
+}
+
+impl SaveDatabaseGame {
+    /// Save paths (as written in the database) applying to the given OS - named like `CURRENT_OS_NAME`. Glob paths are not supported and skipped.
+    pub fn save_paths_for_os(&self, os: &str) -> Vec<&str> {
+        self.files
+            .iter()
+            .filter(|(path, _)| !path.contains('*'))
+            .filter(|(_, rule)| match rule {
+                Some(rule) => rule.is_save() && rule.applies_to_os(os),
+                None => true,
+            })
+            .map(|(path, _)| path.as_str())
+            .collect()
+    }
+}
+
+pub fn read_save_database(database_path: &Path) -> Result<SaveDatabase, String> {
+    if !database_path.exists() {
+        return Err(format!(
+            "Save location database {} does not exist",
+            database_path.display()
+        ));
+    }
+    let content = fs::read_to_string(database_path)
+        .map_err(|e| format!("Unable to read save location database\n{e}"))?;
+    serde_yaml::from_str(&content)
+        .map_err(|e| format!("Unable to parse save location database\n{e}"))
+}
+
+/// A database path that exists on this machine.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResolvedSaveLocation {
+    /// Path to write into the config - with placeholders where possible.
+    pub config_path: String,
+    pub resolved_path: PathBuf,
+}
+
+/// Resolves the game's native paths with the config placeholders - keeping only existing folders.
+/// Paths with placeholders that have no value on this machine are skipped.
+pub fn resolve_native_save_locations(
+    game: &SaveDatabaseGame,
+    variables: &BTreeMap<String, String>,
+) -> Vec<ResolvedSaveLocation> {
+    game.save_paths_for_os(CURRENT_OS_NAME)
+        .into_iter()
+        .filter_map(|path| {
+            let resolved_path = expand_config_placeholders(path, variables).ok()?;
+            Some(ResolvedSaveLocation {
+                config_path: path.to_string(),
+                resolved_path: PathBuf::from(resolved_path),
+            })
+        })
+        .filter(|location| location.resolved_path.is_dir())
+        .collect()
+}
+
+/// Resolves the game's Windows paths inside its Proton prefix user folder - keeping only existing folders.
+/// Config paths use the `{{PROTON_*:<appid>}}` placeholders - so the entry also works on Windows.
+pub fn resolve_proton_save_locations(
+    game: &SaveDatabaseGame,
+    app_id: &str,
+    user_folder: &Path,
+) -> Vec<ResolvedSaveLocation> {
+    // Database placeholder -> folder below the prefix user folder + its Proton placeholder.
+    let windows_folders = [
+        ("{{APPDATA}}", "AppData/Roaming", "PROTON_APPDATA"),
+        ("{{LOCALAPPDATA}}", "AppData/Local", "PROTON_LOCALAPPDATA"),
+        ("{{HOME}}/Documents", "Documents", "PROTON_DOCUMENTS"),
+    ];
+
+    let mut locations = vec![];
+    for path in game.save_paths_for_os("windows") {
+        let Some((placeholder, subfolder, proton_placeholder)) = windows_folders
+            .iter()
+            .find(|(placeholder, _, _)| path.starts_with(placeholder))
+        else {
+            continue;
+        };
+        let rest = path[placeholder.len()..]
+            .trim_start_matches(['/', '\\'])
+            .replace('\\', "/");
+
+        let resolved_path = user_folder.join(subfolder).join(&rest);
+        if resolved_path.is_dir() {
+            locations.push(ResolvedSaveLocation {
+                config_path: format!("{{{{{proton_placeholder}:{app_id}}}}}/{rest}"),
+                resolved_path: resolved_path,
+            });
+        }
+    }
+    locations
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DATABASE: &str = r#"
+Test Game:
+  steam:
+    id: 1234
+  files:
+    "{{APPDATA}}/TestStudio/Saves":
+      tags: [save]
+      when:
+        - os: windows
+    "{{LOCALAPPDATA}}/TestStudio/Config":
+      tags: [config]
+    "{{HOME}}/Library/TestStudio":
+      when:
+        - os: mac
+    "{{HOME}}/*.sav":
+    "/definitely/missing/folder":
+"#;
+
+    #[test]
+    fn parses_and_filters_save_paths() {
+        let database: SaveDatabase = serde_yaml::from_str(DATABASE).unwrap();
+        let game = &database["Test Game"];
+
+        assert_eq!(game.steam.as_ref().map(|steam| steam.id), Some(1234));
+        assert_eq!(
+            game.save_paths_for_os("windows"),
+            vec!["/definitely/missing/folder", "{{APPDATA}}/TestStudio/Saves"]
+        );
+        assert_eq!(
+            game.save_paths_for_os("linux"),
+            vec!["/definitely/missing/folder"]
+        );
+        assert_eq!(
+            game.save_paths_for_os("macos"),
+            vec!["/definitely/missing/folder", "{{HOME}}/Library/TestStudio"]
+        );
+        assert!(resolve_native_save_locations(game, &BTreeMap::new()).is_empty());
+    }
+
+    #[test]
+    fn resolves_windows_paths_inside_proton_prefix() {
+        let database: SaveDatabase = serde_yaml::from_str(DATABASE).unwrap();
+        let user_folder = std::env::temp_dir().join(format!(
+            "local_cloud_game_sync_save_db_test_{}",
+            std::process::id()
+        ));
+        fs::create_dir_all(user_folder.join("AppData/Roaming/TestStudio/Saves")).unwrap();
+
+        let locations = resolve_proton_save_locations(&database["Test Game"], "1234", &user_folder);
+        fs::remove_dir_all(&user_folder).unwrap();
+
+        assert_eq!(
+            locations,
+            vec![ResolvedSaveLocation {
+                config_path: String::from("{{PROTON_APPDATA:1234}}/TestStudio/Saves"),
+                resolved_path: user_folder.join("AppData/Roaming/TestStudio/Saves"),
+            }]
+        );
+    }
+}
@@ -0,0 +1,204 @@
+use crate::steam::vdf::{VdfValue, parse_vdf};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Home folder of the Windows user inside a Proton prefix - Documents, AppData etc. live below.
+pub const PROTON_PREFIX_USER_FOLDER: &str = "drive_c/users/steamuser";
+
+/// An installed Steam app - read from its `appmanifest_<appid>.acf`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SteamApp {
+    pub app_id: String,
+    pub name: String,
+    /// Library root (the folder containing `steamapps`) the app is installed in.
+    pub library: PathBuf,
+    pub install_dir: PathBuf,
+    /// `steamapps/compatdata/<appid>` - only set if the app has a Proton prefix.
+    pub compat_data: Option<PathBuf>,
+}
+
+impl SteamApp {
+    /// Proton prefix - the Wine C: drive lives in `<prefix>/drive_c`.
+    pub fn proton_prefix(&self) -> Option<PathBuf> {
+        self.compat_data.as_ref().map(|path| path.join("pfx"))
+    }
+
+    pub fn proton_user_folder(&self) -> Option<PathBuf> {
+        self.proton_prefix()
+            .map(|prefix| prefix.join(PROTON_PREFIX_USER_FOLDER))
+    }
+
+    /// Proton user folder as written in a config - through `{{PROTON_PREFIX:<appid>}}` so any library works.
+    pub fn proton_user_folder_config_path(&self) -> Option<String> {
+        self.compat_data.as_ref().map(|_| {
+            format!(
+                "{{{{PROTON_PREFIX:{}}}}}/{PROTON_PREFIX_USER_FOLDER}",
+                self.app_id
+            )
+        })
+    }
+}
+
+pub fn is_same_path(a: &Path, b: &Path) -> bool {
+    fs::canonicalize(a).unwrap_or(a.to_path_buf()) == fs::canonicalize(b).unwrap_or(b.to_path_buf())
+}
+
+/// `steamapps/compatdata/<appid>` of the app - searching every library, the Steam root first.
+pub fn find_app_compat_data(steam_path: &Path, app_id: &str) -> Option<PathBuf> {
+    read_library_folders(steam_path)
+        .unwrap_or_default()
+        .into_iter()
+        .map(|library| library.join("steamapps").join("compatdata").join(app_id))
+        .find(|compat_data| compat_data.is_dir())
+}
+
+fn read_vdf_file(path: &Path) -> Result<VdfValue, String> {
+    let content =
+        fs::read_to_string(path).map_err(|e| format!("Unable to read {}\n{e}", path.display()))?;
+    parse_vdf(&content).map_err(|e| format!("Unable to parse {}\n{e}", path.display()))
+}
+
+/// Every library root registered in `libraryfolders.vdf` - the Steam root itself first.
+/// Libraries on unmounted drives are skipped.
+pub fn read_library_folders(steam_path: &Path) -> Result<Vec<PathBuf>, String> {
+    let mut libraries = vec![steam_path.to_path_buf()];
+
+    let vdf_path = steam_path.join("steamapps").join("libraryfolders.vdf");
+    if vdf_path.exists() {
+        let vdf = read_vdf_file(&vdf_path)?;
+        let folders = vdf
+            .get("libraryfolders")
+            .ok_or(format!("No libraryfolders in {}", vdf_path.display()))?;
+        for (_, folder) in folders.children() {
+            // Older format: "1" "/path" - newer: "1" { "path" "/path" ... }
+            let path = match folder {
+                VdfValue::Str(path) => Some(path.as_str()),
+                VdfValue::Obj(_) => folder.get_str("path"),
+            };
+            if let Some(path) = path {
+                libraries.push(PathBuf::from(path));
+            }
+        }
+    }
+
+    // The Steam root is usually listed as well - possibly through a symlink.
+    let mut unique: Vec<PathBuf> = vec![];
+    for library in libraries {
+        if !library.join("steamapps").is_dir() {
+            continue;
+        }
+        let canonical = fs::canonicalize(&library).unwrap_or(library.clone());
+        if !unique
+            .iter()
+            .any(|seen| fs::canonicalize(seen).unwrap_or(seen.clone()) == canonical)
+        {
+            unique.push(library);
+        }
+    }
+    Ok(unique)
+}
+
+fn read_app_manifest(library: &Path, manifest_path: &Path) -> Result<SteamApp, String> {
+    let vdf = read_vdf_file(manifest_path)?;
+    let app_state = vdf
+        .get("AppState")
+        .ok_or(format!("No AppState in {}", manifest_path.display()))?;
+    let field = |key: &str| {
+        app_state
+            .get_str(key)
+            .map(str::to_string)
+            .ok_or(format!("No {key} in {}", manifest_path.display()))
+    };
+
+    let app_id = field("appid")?;
+    let steamapps = library.join("steamapps");
+    let compat_data = steamapps.join("compatdata").join(&app_id);
+    Ok(SteamApp {
+        name: field("name")?,
+        library: library.to_path_buf(),
+        install_dir: steamapps.join("common").join(field("installdir")?),
+        compat_data: compat_data.is_dir().then_some(compat_data),
+        app_id: app_id,
+    })
+}
+
+/// Installed apps across every library - sorted by name. Unreadable manifests are reported and skipped.
+pub fn scan_installed_apps(steam_path: &Path) -> Result<Vec<SteamApp>, String> {
+    let mut apps = vec![];
+    for library in read_library_folders(steam_path)? {
+        let steamapps = library.join("steamapps");
+        let entries = fs::read_dir(&steamapps)
+            .map_err(|e| format!("Unable to read {}\n{e}", steamapps.display()))?;
+        for entry in entries.flatten() {
+            let file_name = entry.file_name().to_string_lossy().to_string();
+            if !(file_name.starts_with("appmanifest_") && file_name.ends_with(".acf")) {
+                continue;
+            }
+            match read_app_manifest(&library, &entry.path()) {
+                Ok(app) => apps.push(app),
+                Err(e) => eprintln!("Skipping app manifest\n{e}"),
+            }
+        }
+    }
+    apps.sort_by(|a, b| a.name.to_lowercase().cmp(&b.name.to_lowercase()));
+    Ok(apps)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scans_apps_across_libraries() {
+        let root = std::env::temp_dir().join(format!(
+            "local_cloud_game_sync_steam_test_{}",
+            std::process::id()
+        ));
+        let steam = root.join("Steam");
+        let second = root.join("SecondLibrary");
+        fs::create_dir_all(steam.join("steamapps")).unwrap();
+        fs::create_dir_all(second.join("steamapps/compatdata/620")).unwrap();
+        fs::write(
+            steam.join("steamapps/libraryfolders.vdf"),
+            format!(
+                "\"libraryfolders\" {{ \"0\" {{ \"path\" \"{}\" }} \"1\" {{ \"path\" \"{}\" }} \"2\" {{ \"path\" \"/missing/drive\" }} }}",
+                steam.display(),
+                second.display()
+            ),
+        )
+        .unwrap();
+        fs::write(
+            steam.join("steamapps/appmanifest_400.acf"),
+            "\"AppState\" { \"appid\" \"400\" \"name\" \"Portal\" \"installdir\" \"Portal\" }",
+        )
+        .unwrap();
+        fs::write(
+            second.join("steamapps/appmanifest_620.acf"),
+            "\"AppState\" { \"appid\" \"620\" \"name\" \"Portal 2\" \"installdir\" \"Portal 2\" }",
+        )
+        .unwrap();
+
+        assert_eq!(
+            read_library_folders(&steam).unwrap(),
+            vec![steam.clone(), second.clone()]
+        );
+        let apps = scan_installed_apps(&steam).unwrap();
+        assert_eq!(apps.len(), 2);
+        assert_eq!(apps[0].name, "Portal");
+        assert_eq!(apps[0].compat_data, None);
+        assert_eq!(
+            apps[1].install_dir,
+            second.join("steamapps/common/Portal 2")
+        );
+        assert_eq!(
+            apps[1].proton_prefix(),
+            Some(second.join("steamapps/compatdata/620/pfx"))
+        );
+        assert_eq!(
+            find_app_compat_data(&steam, "620"),
+            Some(second.join("steamapps/compatdata/620"))
+        );
+        assert_eq!(find_app_compat_data(&steam, "400"), None);
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
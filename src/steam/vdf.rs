@@ -0,0 +1,148 @@
+/// Minimal parser for Valve's KeyValues text format (`libraryfolders.vdf`, `appmanifest_*.acf`).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum VdfValue {
+    Str(String),
+    Obj(Vec<(String, VdfValue)>),
+}
+
+impl VdfValue {
+    /// First child with the given key - keys are case-insensitive like in Steam.
+    pub fn get(&self, key: &str) -> Option<&VdfValue> {
+        match self {
+            VdfValue::Obj(children) => children
+                .iter()
+                .find(|(child_key, _)| child_key.eq_ignore_ascii_case(key))
+                .map(|(_, value)| value),
+            VdfValue::Str(_) => None,
+        }
+    }
+
+    pub fn get_str(&self, key: &str) -> Option<&str> {
+        match self.get(key) {
+            Some(VdfValue::Str(value)) => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn children(&self) -> &[(String, VdfValue)] {
+        match self {
+            VdfValue::Obj(children) => children,
+            VdfValue::Str(_) => &[],
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum Token {
+    Str(String),
+    Open,
+    Close,
+}
+
+fn tokenize(input: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = vec![];
+    let mut chars = input.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '{' => tokens.push(Token::Open),
+            '}' => tokens.push(Token::Close),
+            '"' => {
+                let mut value = String::new();
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => match chars.next() {
+                            Some('n') => value.push('\n'),
+                            Some('t') => value.push('\t'),
+                            Some(escaped) => value.push(escaped),
+                            None => return Err(String::from("Unterminated escape in VDF")),
+                        },
+                        Some(other) => value.push(other),
+                        None => return Err(String::from("Unterminated string in VDF")),
+                    }
+                }
+                tokens.push(Token::Str(value));
+            }
+            '/' if chars.peek() == Some(&'/') => {
+                // Comment until end of line
+                while chars.next_if(|next| *next != '\n').is_some() {}
+            }
+            c if c.is_whitespace() => {}
+            _ => {
+                // Unquoted token
+                let mut value = String::from(c);
+                while let Some(next) =
+                    chars.next_if(|next| !next.is_whitespace() && !matches!(next, '{' | '}' | '"'))
+                {
+                    value.push(next);
+                }
+                tokens.push(Token::Str(value));
+            }
+        }
+    }
+    Ok(tokens)
+}
+
+fn parse_children(
+    tokens: &mut std::iter::Peekable<std::vec::IntoIter<Token>>,
+    nested: bool,
+) -> Result<Vec<(String, VdfValue)>, String> {
+    let mut children = vec![];
+    loop {
+        let key = match tokens.next() {
+            Some(Token::Str(key)) => key,
+            Some(Token::Close) if nested => return Ok(children),
+            None if !nested => return Ok(children),
+            Some(token) => return Err(format!("Unexpected {token:?} in VDF - expected a key")),
+            None => return Err(String::from("Unexpected end of VDF - missing }")),
+        };
+        let value = match tokens.next() {
+            Some(Token::Str(value)) => VdfValue::Str(value),
+            Some(Token::Open) => VdfValue::Obj(parse_children(tokens, true)?),
+            _ => return Err(format!("Missing value for key \"{key}\" in VDF")),
+        };
+        children.push((key, value));
+    }
+}
+
+/// Parses a whole VDF document - the result is an object holding the top-level keys.
+pub fn parse_vdf(input: &str) -> Result<VdfValue, String> {
+    let mut tokens = tokenize(input)?.into_iter().peekable();
+    Ok(VdfValue::Obj(parse_children(&mut tokens, false)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_nested_objects_escapes_and_comments() {
+        let doc = parse_vdf(
+            r#"
+            // comment
+            "libraryfolders"
+            {
+                "0"
+                {
+                    "path"		"C:\\Program Files (x86)\\Steam"
+                    "apps" { "228980" "123" }
+                }
+                "1" { "path" "/mnt/games/SteamLibrary" }
+            }
+            "#,
+        )
+        .unwrap();
+
+        let folders = doc.get("LibraryFolders").unwrap();
+        assert_eq!(folders.children().len(), 2);
+        assert_eq!(
+            folders.get("0").unwrap().get_str("path"),
+            Some("C:\\Program Files (x86)\\Steam")
+        );
+        assert_eq!(
+            folders.get("1").unwrap().get_str("path"),
+            Some("/mnt/games/SteamLibrary")
+        );
+        assert!(parse_vdf("\"a\" { \"b\" \"c\"").is_err());
+    }
+}
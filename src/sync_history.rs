@@ -0,0 +1,157 @@
+use crate::common::{Revision, format_unix_timestamp};
+use crate::config::RuntimeSyncConfig;
+use crate::local_head::append_local_journal;
+use crate::remote_save_client::RemoteSaveClient;
+use crate::tree_utils::{collect_matching_files, files_total_size};
+use crate::utils::{format_bytes, get_unix_timestamp_secs};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::time::Instant;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum SyncEventKind {
+    Push,
+    Pull,
+    Merge,
+}
+
+impl fmt::Display for SyncEventKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SyncEventKind::Push => write!(f, "push"),
+            SyncEventKind::Pull => write!(f, "pull"),
+            SyncEventKind::Merge => write!(f, "merge"),
+        }
+    }
+}
+
+/// A single completed sync operation - recorded both on the remote history and the local journal.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SyncEvent {
+    pub client_name: String,
+    pub kind: SyncEventKind,
+    /// Whether the sync overwrote changes from the other side (i.e. resolved a conflict).
+    pub resolved_conflict: bool,
+    pub revision: Revision,
+    /// When the sync finished (unix secs).
+    pub timestamp: u64,
+    pub file_count: u64,
+    pub bytes: u64,
+    pub duration_ms: u64,
+}
+
+impl SyncEvent {
+    pub fn serialize(&self) -> Result<String, String> {
+        serde_json::to_string(self).map_err(|e| format!("Unable to serialize sync event\n{e}"))
+    }
+}
+
+impl fmt::Display for SyncEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let conflict_display = match (self.resolved_conflict, self.kind) {
+            (false, _) => "",
+            (true, SyncEventKind::Push) => " (conflict resolved - kept local)",
+            (true, SyncEventKind::Pull) => " (conflict resolved - kept remote)",
+            (true, SyncEventKind::Merge) => " (conflict resolved - merged)",
+        };
+
+        write!(
+            f,
+            "{} {} by {}{} - {} file(s), {} in {:.1}s\n\trevision: {}",
+            format_unix_timestamp(self.timestamp, "%H:%M:%S %a %e %b %Y"),
+            self.kind,
+            self.client_name,
+            conflict_display,
+            self.file_count,
+            format_bytes(self.bytes),
+            self.duration_ms as f64 / 1000.0,
+            self.revision
+        )
+    }
+}
+
+/// Parses a sync history - one JSON event per line, oldest first.
+pub fn deserialize_sync_history(s: &str) -> Result<Vec<SyncEvent>, String> {
+    s.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(|line| {
+            serde_json::from_str(line).map_err(|e| format!("Invalid sync history entry\n{e}"))
+        })
+        .collect()
+}
+
+/// Records a finished sync on both the remote history and the local journal.
+/// Best-effort - the sync itself already succeeded, so failures are only reported.
+pub fn record_sync_event<'c>(
+    client: &impl RemoteSaveClient<'c>,
+    sync_config: &RuntimeSyncConfig,
+    kind: SyncEventKind,
+    resolved_conflict: bool,
+    revision: &Revision,
+    started_at: Instant,
+) {
+    let tracked_files =
+        collect_matching_files(&sync_config.local_save_folder, &sync_config.ignore_globset)
+            .map(|(tracked, _)| tracked)
+            .unwrap_or_default();
+
+    let event = SyncEvent {
+        client_name: sync_config.client_name.clone(),
+        kind,
+        resolved_conflict,
+        revision: revision.clone(),
+        timestamp: get_unix_timestamp_secs(),
+        file_count: tracked_files.len() as u64,
+        bytes: files_total_size(&sync_config.local_save_folder, tracked_files.iter()),
+        duration_ms: started_at.elapsed().as_millis() as u64,
+    };
+
+    if let Err(e) = client.append_remote_sync_history(&event) {
+        eprintln!("Warning: Unable to record sync in remote history.\n{e}");
+    }
+    if let Err(e) = append_local_journal(sync_config, &event) {
+        eprintln!("Warning: Unable to record sync in local journal.\n{e}");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hashing::HashAlgorithm;
+
+    #[test]
+    fn history_roundtrip() {
+        let event = SyncEvent {
+            client_name: "Steam Deck".to_string(),
+            kind: SyncEventKind::Pull,
+            resolved_conflict: true,
+            revision: Revision {
+                hash: "abcdef".to_string(),
+                timestamp: 12000,
+                author: "Desktop".to_string(),
+                parent: Some("012345".to_string()),
+                hash_algorithm: HashAlgorithm::Blake3,
+            },
+            timestamp: 13000,
+            file_count: 3,
+            bytes: 2048,
+            duration_ms: 1500,
+        };
+
+        let history = format!(
+            "{}\n{}\n",
+            event.serialize().unwrap(),
+            event.serialize().unwrap()
+        );
+        let parsed = deserialize_sync_history(&history).unwrap();
+
+        assert_eq!(parsed.len(), 2);
+        assert_eq!(parsed[0].client_name, "Steam Deck");
+        assert_eq!(parsed[0].kind, SyncEventKind::Pull);
+        assert_eq!(parsed[0].revision.hash, "abcdef");
+        assert_eq!(parsed[0].revision.parent.as_deref(), Some("012345"));
+        assert_eq!(parsed[0].bytes, 2048);
+    }
+}
@@ -1,34 +1,17 @@
+use crate::encryption::{EncryptionKey, decrypt_pulled_file};
+use crate::hash_cache::{FileStamp, HashCache};
+use crate::hashing::{HashAlgorithm, StreamDigest};
+use crate::manifest::{Manifest, MergePlan};
+use crate::progress::{ProgressReader, TransferProgress};
 use globset::GlobSet;
-use md5;
+use rayon::prelude::*;
 use std::env;
-use std::fs::{self, File};
-use std::io::{self, BufReader, Read};
-use std::path::{Path, PathBuf};
+use std::fs;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::path::{Component, Path, PathBuf};
+use std::process;
 use std::time::{SystemTime, UNIX_EPOCH};
 
-const CHECKSUM_BUFFER_MB: usize = 5;
-
-/// Computes a deterministic hash by calculating the md5 of each 5mb chunks of file
-fn digest_file(path: &Path) -> io::Result<String> {
-    let file = File::open(path)?;
-    let mut reader = BufReader::new(file);
-    let mut buffer = vec![0u8; CHECKSUM_BUFFER_MB * 1024 * 1024];
-    let mut chunk_digests: Vec<u8> = Vec::new();
-
-    loop {
-        let n = reader.read(&mut buffer)?;
-        if n == 0 {
-            break;
-        }
-        let digest = md5::compute(&buffer[..n]);
-        chunk_digests.extend_from_slice(&digest.0); // append raw 16 bytes
-    }
-
-    // Hash the concatenated digests to get a final checksum
-    let final_digest = md5::compute(&chunk_digests);
-    Ok(format!("{:x}", final_digest))
-}
-
 /// Recursively walk a folder, calling `callback` for each file (not for dirs).
 fn walk_folder_rec<F, G>(
     root: &Path,
@@ -87,42 +70,76 @@ where
     return walk_folder_rec(path, path, ignore_globset, callback, &mut |_, _| Ok(()));
 }
 
-// TODO: Make multi-threaded for faster checksumming - usually fine for save folders
-/// Recursively compute the MD5 checksum of a folder
+/// Recursively compute the per-file checksums of a folder - files are hashed in parallel.
 /// Also returns the last modification timestamp of all files (max modified time).
-pub fn tree_folder_hash(path: &Path, ignore_globset: &GlobSet) -> Result<(String, u64), String> {
-    let mut entries: Vec<(String, String)> = Vec::new();
-    let mut latest_mod_time: SystemTime = UNIX_EPOCH;
+pub fn tree_folder_manifest(
+    path: &Path,
+    ignore_globset: &GlobSet,
+    algorithm: HashAlgorithm,
+) -> Result<(Manifest, u64), String> {
+    let (manifest, latest_mod_unix, _cache) =
+        tree_folder_manifest_cached(path, ignore_globset, algorithm, &HashCache::default())?;
+    Ok((manifest, latest_mod_unix))
+}
 
+/// Same as `tree_folder_manifest` - but reuses digests from the cache for files that are unchanged since.
+/// Also returns the updated cache - holding only the files currently present.
+pub fn tree_folder_manifest_cached(
+    path: &Path,
+    ignore_globset: &GlobSet,
+    algorithm: HashAlgorithm,
+    cache: &HashCache,
+) -> Result<(Manifest, u64, HashCache), String> {
+    let mut files: Vec<(PathBuf, String)> = Vec::new();
     walk_folder(path, ignore_globset, &mut |filepath, rel_path| {
-        // Compute hash
-        let file_md5 = digest_file(filepath)
-            .map_err(|e| format!("Error checksumming file {}\n{}", filepath.display(), e))?;
-        entries.push((rel_path.to_string_lossy().to_string(), file_md5));
-
-        // Update last modified timestamp
-        let metadata = filepath
-            .metadata()
-            .map_err(|e| format!("Unable to read metadata for {}\n{}", filepath.display(), e))?;
-        if let Ok(modified) = metadata.modified() {
-            if modified > latest_mod_time {
-                latest_mod_time = modified;
-            }
-        }
-
+        files.push((
+            filepath.to_path_buf(),
+            rel_path.to_string_lossy().to_string(),
+        ));
         Ok(())
     })?;
 
-    // Sort entries by name for deterministic hash
-    entries.sort_by(|a, b| a.0.cmp(&b.0));
-
-    let combined = entries
-        .iter()
-        .map(|(name, hash)| format!("{}:{}", name, hash))
-        .collect::<Vec<_>>()
-        .join("\n");
-
-    let folder_hash = format!("{:x}", md5::compute(combined.as_bytes()));
+    let digests = files
+        .par_iter()
+        .map(|(filepath, rel_path)| {
+            let metadata = filepath.metadata().map_err(|e| {
+                format!("Unable to read metadata for {}\n{}", filepath.display(), e)
+            })?;
+            let stamp = FileStamp::from_metadata(&metadata);
+            let cached = stamp
+                .as_ref()
+                .and_then(|stamp| cache.get(rel_path, stamp, algorithm));
+
+            let digest = match cached {
+                Some(digest) => digest.to_string(),
+                None => algorithm.digest_file(filepath).map_err(|e| {
+                    format!("Error checksumming file {}\n{}", filepath.display(), e)
+                })?,
+            };
+            Ok((
+                rel_path.clone(),
+                digest,
+                metadata.len(),
+                metadata.modified().ok(),
+                stamp,
+            ))
+        })
+        .collect::<Result<Vec<_>, String>>()?;
+
+    let mut manifest = Manifest::new(algorithm);
+    let mut updated_cache = HashCache::default();
+    let mut latest_mod_time: SystemTime = UNIX_EPOCH;
+    for (rel_path, digest, size, modified, stamp) in digests {
+        if let Some(stamp) = stamp {
+            updated_cache.insert(rel_path.clone(), stamp, algorithm, digest.clone());
+        }
+        manifest.sizes.insert(rel_path.clone(), size);
+        manifest.files.insert(rel_path, digest);
+        // Update last modified timestamp
+        if let Some(modified) = modified.filter(|modified| *modified > latest_mod_time) {
+            latest_mod_time = modified;
+        }
+    }
 
     // Convert latest_mod_time to UNIX timestamp (seconds)
     let latest_mod_unix = latest_mod_time
@@ -130,62 +147,282 @@ pub fn tree_folder_hash(path: &Path, ignore_globset: &GlobSet) -> Result<(String
         .map_err(|e| format!("SystemTime before UNIX_EPOCH: {}", e))?
         .as_secs();
 
-    Ok((folder_hash, latest_mod_unix))
+    Ok((manifest, latest_mod_unix, updated_cache))
+}
+
+/// Recursively compute the checksum of a folder
+/// Also returns the last modification timestamp of all files (max modified time).
+pub fn tree_folder_hash(
+    path: &Path,
+    ignore_globset: &GlobSet,
+    algorithm: HashAlgorithm,
+) -> Result<(String, u64), String> {
+    let (manifest, latest_mod_unix) = tree_folder_manifest(path, ignore_globset, algorithm)?;
+    Ok((manifest.tree_hash(), latest_mod_unix))
+}
+
+const ZSTD_COMPRESSION_LEVEL: i32 = 3;
+const ZSTD_MAGIC: [u8; 4] = [0x28, 0xB5, 0x2F, 0xFD];
+
+/// Reader adapter feeding everything read through it into a digest.
+struct DigestReader<R> {
+    inner: R,
+    digest: StreamDigest,
+}
+
+impl<R: Read> Read for DigestReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read = self.inner.read(buf)?;
+        self.digest.update(&buf[..read]);
+        Ok(read)
+    }
+}
+
+/// Error for a file no longer matching the manifest it was hashed into - e.g. the game saved meanwhile.
+fn changed_since_hashing_error(relpath: &str) -> io::Error {
+    io::Error::other(format!(
+        "{relpath} changed since it was hashed - push again once the game stopped writing its saves"
+    ))
+}
+
+/// Writes the manifest's files (relative to root) as a tar stream - used to upload without a temporary copy.
+/// Optionally zstd-compressed. With a key every file is encrypted before it enters the stream.
+/// Fails if a file no longer matches its manifest digest - the stream must not disagree with the pushed HEAD.
+pub fn write_tar_stream<W: Write>(
+    writer: W,
+    root: &Path,
+    manifest: &Manifest,
+    compress: bool,
+    key: Option<&EncryptionKey>,
+    progress: &TransferProgress,
+) -> io::Result<()> {
+    if compress {
+        let mut encoder = zstd::Encoder::new(writer, ZSTD_COMPRESSION_LEVEL)?;
+        append_tar_files(&mut encoder, root, manifest, key, progress)?;
+        encoder.finish()?;
+        return Ok(());
+    }
+
+    append_tar_files(writer, root, manifest, key, progress)
+}
+
+fn append_tar_files<W: Write>(
+    writer: W,
+    root: &Path,
+    manifest: &Manifest,
+    key: Option<&EncryptionKey>,
+    progress: &TransferProgress,
+) -> io::Result<()> {
+    let mut builder = tar::Builder::new(writer);
+    for (relpath, expected_digest) in manifest.files.iter() {
+        progress.start_file(relpath);
+        let file = fs::File::open(root.join(relpath))?;
+        let metadata = file.metadata()?;
+        if manifest
+            .sizes
+            .get(relpath)
+            .is_some_and(|size| *size != metadata.len())
+        {
+            return Err(changed_since_hashing_error(relpath));
+        }
+        let mut header = tar::Header::new_gnu();
+        header.set_metadata(&metadata);
+
+        // Capped to the declared size - a file growing meanwhile must not corrupt the stream.
+        let mut reader = DigestReader {
+            inner: file.take(header.size()?),
+            digest: manifest.algorithm.stream_digest(),
+        };
+        match key {
+            None => {
+                builder.append_data(
+                    &mut header,
+                    relpath,
+                    ProgressReader::new(&mut reader, progress),
+                )?;
+            }
+            Some(key) => {
+                let mut data = vec![];
+                reader.read_to_end(&mut data)?;
+                let encrypted = key.encrypt(relpath, &data).map_err(io::Error::other)?;
+                header.set_size(encrypted.len() as u64);
+                builder.append_data(&mut header, relpath, encrypted.as_slice())?;
+                progress.add_bytes(metadata.len());
+            }
+        }
+        // Also catches a file shrinking meanwhile - its entry came out short.
+        if reader.digest.finalize() != *expected_digest {
+            return Err(changed_since_hashing_error(relpath));
+        }
+        progress.finish_file();
+    }
+    builder.finish()
+}
+
+/// Extracts a tar stream into dst - zstd-compressed streams are detected automatically.
+/// Encrypted files are decrypted with the key - see `decrypt_pulled_file` for `allow_plaintext`.
+/// Existing files not present in the stream are kept.
+pub fn unpack_tar_stream<R: Read>(
+    reader: R,
+    dst: &Path,
+    key: Option<&EncryptionKey>,
+    allow_plaintext: bool,
+    progress: &TransferProgress,
+) -> io::Result<()> {
+    let mut reader = BufReader::new(reader);
+    let is_zstd = reader.fill_buf()?.starts_with(&ZSTD_MAGIC);
+
+    if is_zstd {
+        return unpack_tar(
+            zstd::Decoder::with_buffer(reader)?,
+            dst,
+            key,
+            allow_plaintext,
+            progress,
+        );
+    }
+    unpack_tar(reader, dst, key, allow_plaintext, progress)
+}
+
+fn unpack_tar<R: Read>(
+    reader: R,
+    dst: &Path,
+    key: Option<&EncryptionKey>,
+    allow_plaintext: bool,
+    progress: &TransferProgress,
+) -> io::Result<()> {
+    let mut archive = tar::Archive::new(reader);
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        // Only plain relative paths - never write outside of dst.
+        let relpath: PathBuf = entry
+            .path()?
+            .components()
+            .filter(|c| !matches!(c, Component::CurDir))
+            .map(|c| match c {
+                Component::Normal(part) => Ok(part),
+                _ => Err(io::Error::other(
+                    "Refusing to unpack path outside of target",
+                )),
+            })
+            .collect::<io::Result<_>>()?;
+        if relpath.as_os_str().is_empty() {
+            continue;
+        }
+        let target = dst.join(&relpath);
+
+        match entry.header().entry_type() {
+            tar::EntryType::Directory => fs::create_dir_all(&target)?,
+            tar::EntryType::Regular => {
+                let relpath_str = relpath.to_string_lossy().replace('\\', "/");
+                progress.start_file(&relpath_str);
+                let mut data = Vec::with_capacity(entry.size() as usize);
+                ProgressReader::new(&mut entry, progress).read_to_end(&mut data)?;
+                let data = decrypt_pulled_file(key, allow_plaintext, &relpath_str, data)
+                    .map_err(io::Error::other)?;
+
+                if let Some(parent) = target.parent() {
+                    fs::create_dir_all(parent)?;
+                }
+                // Pulled files are new local modifications - same as a plain copy.
+                fs::write(&target, data)?;
+                progress.finish_file();
+            }
+            // Saves are plain files - links and special files are never pushed.
+            _ => {}
+        }
+    }
+    Ok(())
 }
 
-fn get_tmp_sync_directory() -> PathBuf {
-    return env::temp_dir().join("local_cloud_game_sync_tmp");
+/// Unique per process - concurrent lcgsync processes must not delete each other's folder.
+fn get_tmp_staging_directory() -> PathBuf {
+    return env::temp_dir().join(format!(
+        "local_cloud_game_sync_staging_tmp_{}",
+        process::id()
+    ));
 }
 
-pub struct UploadTempFolder {
+/// Temporary folder holding a full remote copy while merging or pulling - deleted on drop.
+pub struct StagingTempFolder {
     pub path: PathBuf,
 }
 
-impl Drop for UploadTempFolder {
+impl Drop for StagingTempFolder {
     fn drop(&mut self) {
-        let _ = delete_tmp_sync_directory();
+        let _ = fs::remove_dir_all(&self.path);
     }
 }
 
-// TODO: Make multi-threaded for faster checksumming - usually fine for save folders
-pub fn tree_folder_temp_copy(
-    path: &Path,
-    ignore_globset: &GlobSet,
-) -> Result<UploadTempFolder, String> {
-    let target = get_tmp_sync_directory();
-    delete_tmp_sync_directory()?;
+pub fn create_staging_temp_folder() -> Result<StagingTempFolder, String> {
+    let target = get_tmp_staging_directory();
+    if target.exists() {
+        fs::remove_dir_all(&target).map_err(|e| format!("Unable to delete directory\n{}", e))?;
+    }
+    fs::create_dir_all(&target)
+        .map_err(|e| format!("Error creating staging dir {}: {}", target.display(), e))?;
+
+    Ok(StagingTempFolder { path: target })
+}
 
-    walk_folder(path, ignore_globset, &mut |filepath, relpath| {
-        let target = target.join(relpath);
+/// Copies every file of `src` into `dst` - overwriting existing files but keeping files only present in `dst`.
+pub fn copy_folder_into(src: &Path, dst: &Path) -> Result<(), String> {
+    walk_folder(src, &GlobSet::empty(), &mut |source, relpath| {
+        let target = dst.join(relpath);
         if let Some(parent) = target.parent() {
             fs::create_dir_all(parent)
                 .map_err(|e| format!("Error creating parent dir {}: {}", parent.display(), e))?;
         }
-        fs::copy(filepath, &target).map_err(|e| {
+        fs::copy(source, &target).map_err(|e| {
             format!(
                 "Error copying {} -> {}\n{}",
-                filepath.display(),
+                source.display(),
                 target.display(),
                 e
             )
         })?;
-
         Ok(())
-    })?;
-
-    Ok(UploadTempFolder { path: target })
+    })
 }
 
-pub fn delete_tmp_sync_directory() -> Result<(), String> {
-    let tmpdir = get_tmp_sync_directory();
-    if !tmpdir.exists() {
-        return Ok(());
+/// Applies the remote side of a merge into `dst` - copying changed files from `remote_copy` and deleting removed ones.
+pub fn apply_merge_plan(remote_copy: &Path, dst: &Path, plan: &MergePlan) -> Result<(), String> {
+    for relpath in &plan.pull_files {
+        let source = remote_copy.join(relpath);
+        let target = dst.join(relpath);
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|e| format!("Error creating parent dir {}: {}", parent.display(), e))?;
+        }
+        fs::copy(&source, &target).map_err(|e| {
+            format!(
+                "Error copying {} -> {}\n{}",
+                source.display(),
+                target.display(),
+                e
+            )
+        })?;
+    }
+
+    for relpath in &plan.delete_files {
+        let target = dst.join(relpath);
+        if target.exists() {
+            fs::remove_file(&target)
+                .map_err(|e| format!("Error deleting {}\n{}", target.display(), e))?;
+        }
     }
 
-    fs::remove_dir_all(tmpdir).map_err(|e| format!("Unable to delete directory\n{}", e))?;
     Ok(())
 }
 
+/// Sums the size of the given files relative to root. Unreadable files count as 0.
+pub fn files_total_size<'a>(root: &Path, files: impl Iterator<Item = &'a String>) -> u64 {
+    files
+        .filter_map(|relpath| root.join(relpath).metadata().ok())
+        .map(|metadata| metadata.len())
+        .sum()
+}
+
 pub fn collect_matching_files(
     path: &Path,
     ignore_globset: &GlobSet,
@@ -216,3 +453,117 @@ pub fn collect_matching_files(
 
     Ok((tracked_files, ignored_entries))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::progress::SyncPhase;
+
+    fn test_folder(name: &str) -> PathBuf {
+        let folder = env::temp_dir().join(format!(
+            "local_cloud_game_sync_tree_utils_test_{name}_{}",
+            process::id()
+        ));
+        let _ = fs::remove_dir_all(&folder);
+        fs::create_dir_all(folder.join("slots")).unwrap();
+        fs::write(folder.join("slots/slot1.sav"), b"HERO LEVEL 42 GOLD 9001").unwrap();
+        fs::write(folder.join("settings.ini"), b"volume=11;music=7").unwrap();
+        folder
+    }
+
+    fn write_push_stream(
+        root: &Path,
+        manifest: &Manifest,
+        compress: bool,
+        key: Option<&EncryptionKey>,
+    ) -> io::Result<Vec<u8>> {
+        let callback = |_| {};
+        let progress = TransferProgress::new(&callback, SyncPhase::Uploading, "", None, None);
+        let mut stream = vec![];
+        write_tar_stream(&mut stream, root, manifest, compress, key, &progress)?;
+        Ok(stream)
+    }
+
+    fn unpack_pull_stream(
+        stream: &[u8],
+        key: Option<&EncryptionKey>,
+        name: &str,
+    ) -> io::Result<Manifest> {
+        let dst = env::temp_dir().join(format!(
+            "local_cloud_game_sync_tree_utils_test_{name}_dst_{}",
+            process::id()
+        ));
+        let callback = |_| {};
+        let progress = TransferProgress::new(&callback, SyncPhase::Downloading, "", None, None);
+        let res = unpack_tar_stream(stream, &dst, key, false, &progress);
+        let unpacked = tree_folder_manifest(&dst, &GlobSet::empty(), HashAlgorithm::Blake3);
+        let _ = fs::remove_dir_all(&dst);
+        res?;
+        Ok(unpacked.map_err(io::Error::other)?.0)
+    }
+
+    #[test]
+    fn push_stream_roundtrips() {
+        let src = test_folder("roundtrip");
+        let (manifest, _) =
+            tree_folder_manifest(&src, &GlobSet::empty(), HashAlgorithm::Blake3).unwrap();
+
+        for compress in [false, true] {
+            let stream = write_push_stream(&src, &manifest, compress, None).unwrap();
+            let unpacked = unpack_pull_stream(&stream, None, "roundtrip").unwrap();
+            assert_eq!(unpacked.files, manifest.files);
+        }
+        fs::remove_dir_all(&src).unwrap();
+    }
+
+    #[test]
+    fn encrypted_push_stream_holds_no_plaintext() {
+        let src = test_folder("encrypted");
+        let key =
+            EncryptionKey::derive("correct horse battery staple", b"0123456789abcdef").unwrap();
+        let (manifest, _) =
+            tree_folder_manifest(&src, &GlobSet::empty(), HashAlgorithm::Blake3).unwrap();
+
+        let stream = write_push_stream(&src, &manifest, false, Some(&key)).unwrap();
+        for plaintext in [&b"HERO LEVEL 42 GOLD 9001"[..], b"volume=11;music=7"] {
+            // Any 8 byte window of the contents would reveal them.
+            assert!(
+                !plaintext
+                    .windows(8)
+                    .any(|window| stream.windows(8).any(|chunk| chunk == window)),
+                "Push stream contains plaintext of {}",
+                String::from_utf8_lossy(plaintext)
+            );
+        }
+        for compress in [false, true] {
+            let stream = write_push_stream(&src, &manifest, compress, Some(&key)).unwrap();
+            let unpacked = unpack_pull_stream(&stream, Some(&key), "encrypted").unwrap();
+            assert_eq!(unpacked.files, manifest.files);
+            // Without the key the ciphertext must never end up as a save.
+            assert!(unpack_pull_stream(&stream, None, "encrypted").is_err());
+        }
+        fs::remove_dir_all(&src).unwrap();
+    }
+
+    #[test]
+    fn push_stream_fails_for_files_changed_since_hashing() {
+        let src = test_folder("changed");
+        let (manifest, _) =
+            tree_folder_manifest(&src, &GlobSet::empty(), HashAlgorithm::Md5).unwrap();
+
+        // Same size - only the digest tells.
+        fs::write(src.join("settings.ini"), b"volume=12;music=7").unwrap();
+        let same_size_error = write_push_stream(&src, &manifest, false, None)
+            .err()
+            .unwrap();
+        // Grown - caught before streaming.
+        fs::write(src.join("settings.ini"), b"volume=11;music=7 and more").unwrap();
+        let grown_error = write_push_stream(&src, &manifest, false, None)
+            .err()
+            .unwrap();
+        fs::remove_dir_all(&src).unwrap();
+
+        assert!(same_size_error.to_string().contains("settings.ini changed"));
+        assert!(grown_error.to_string().contains("settings.ini changed"));
+    }
+}
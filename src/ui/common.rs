@@ -2,6 +2,8 @@ pub const UI_TITLE_NAME: &str = "Local Cloud Game Sync";
 pub const UI_INITIAL_SIZE_WIDTH_PX: f64 = 1000.0;
 pub const UI_INITIAL_SIZE_HEIGHT_PX: f64 = 720.0;
 pub const VITE_DEV_LOCALHOST_URL: &str = "http://localhost:5173";
+use crate::cancellation::CancellationToken;
+use crate::progress::ProgressEvent;
 use serde::Serialize;
 use std::{path::PathBuf, sync::mpsc::Receiver};
 use tao::event_loop::EventLoopProxy;
@@ -14,6 +16,7 @@ pub enum UserChoice {
     Retry,
     Close,
     ContinueOffline,
+    Cancel, // Handled by the main loop - the sync thread is busy running the operation.
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -48,6 +51,8 @@ pub struct ConflictDisplayInfo {
     pub remote_uploaded_time: String,
     pub local_author: String,
     pub remote_author: String,
+    // Set if this device has progress that never reached the remote - keeping remote discards it.
+    pub local_unpushed_since: Option<String>,
 }
 
 // Commands generated from rust code to be processed to the webview.
@@ -58,12 +63,19 @@ pub enum WebViewCommand {
         sub_text: String,
         conflict_info: Option<ConflictDisplayInfo>,
         is_after_game: bool,
+        // Error was caused by the remote being unreachable - Continue Offline is offered without confirmation.
+        is_offline: bool,
     },
 
     WebViewStateChange {
         state: WebViewState,
     },
 
+    // Progress of the running transfer - drives the loading progress bar.
+    WebViewProgress {
+        progress: ProgressEvent,
+    },
+
     // Notifies the webview when the user is attempting to close the window.
     WebViewNotifyClose {
         empty_payload: u32, // needs something else parsing logic breaks
@@ -91,6 +103,8 @@ pub struct SyncThreadContext {
     pub sync_rx: Receiver<SyncThreadCommand>,
     pub after_game: bool,
     pub config_file_override: Option<PathBuf>,
+    // Shared with the main loop - triggered by the Cancel button while loading.
+    pub cancel: CancellationToken,
     // TODO: This shouldn't be here but I don't wanna refactor further. This is just to display game name best-effort on error.
     // Game name cannot be displayed if the error itself was loading the config.
     pub game_display_name: Option<String>,
@@ -1,6 +1,7 @@
+use crate::cancellation::CancellationToken;
 use crate::ui::{
     common::{
-        SyncThreadCommand, UIEvent, WebViewCommand, WebViewEvent, WebViewState,
+        SyncThreadCommand, UIEvent, UserChoice, WebViewCommand, WebViewEvent, WebViewState,
         send_event_to_webview,
     },
     handle_window_event::handle_window_event,
@@ -16,6 +17,7 @@ pub fn handle_main_loop_event(
     webview: &Rc<RefCell<WebView>>,
     window: &Window,
     sync_tx: &Sender<SyncThreadCommand>,
+    cancel: &CancellationToken,
     sync_thread_handle: &RefCell<Option<JoinHandle<()>>>,
     current_state: &RefCell<WebViewState>,
     is_after_game: bool,
@@ -38,6 +40,12 @@ pub fn handle_main_loop_event(
                         // notify sync thread so it can start working.
                         let _ = sync_tx.send(SyncThreadCommand::UIReady);
                     }
+                    WebViewEvent::UserChoice {
+                        choice: UserChoice::Cancel,
+                    } => {
+                        // Sync thread is blocked on the running operation - so cancel it directly.
+                        cancel.cancel();
+                    }
                     WebViewEvent::UserChoice { choice } => {
                         let _ = sync_tx.send(SyncThreadCommand::UserChoice { choice });
                     }
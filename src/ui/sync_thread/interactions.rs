@@ -1,6 +1,8 @@
 use crate::{
     common::Revision,
     config::RuntimeSyncConfig,
+    pending_sync::read_pending_sync,
+    remote_save_client::is_offline_error,
     ui::common::{SyncThreadContext, UserChoice, WebViewState},
 };
 use std::sync::mpsc::Receiver;
@@ -78,11 +80,13 @@ pub(super) fn handle_conflict(
     local: &Revision,
     remote: &Revision,
 ) -> Result<SyncOutcome, String> {
+    let pending = read_pending_sync(&sync_config.local_head_folder, &sync_config.remote_sync_key)?;
     context.send_ui_change_state(WebViewState::Conflict);
     context.send_ui_display_update_conflict(
         &format!("{} Conflict Found", sync_config.game_display_name),
         local,
         remote,
+        pending.as_ref(),
     );
 
     let choice = wait_for_user_choice(&context.sync_rx, |choice| match choice {
@@ -108,14 +112,14 @@ pub(super) fn handle_sync_error(
     context: &SyncThreadContext,
     error_message: &str,
 ) -> ErrorResolution {
+    let is_offline = is_offline_error(error_message);
+    let game_display_name = context.game_display_name.clone().unwrap_or_default();
+    let title = match is_offline {
+        true => format!("{game_display_name} Remote Unreachable"),
+        false => format!("{game_display_name} Sync Error"),
+    };
     context.send_ui_change_state(WebViewState::Error);
-    context.send_ui_display_update(
-        format!(
-            "{} Sync Error",
-            &context.game_display_name.clone().unwrap_or_default()
-        ),
-        error_message,
-    );
+    context.send_ui_display_update_error(title, error_message, is_offline);
 
     let choice = wait_for_user_choice(&context.sync_rx, |choice| match choice {
         UserChoice::Close | UserChoice::ContinueOffline | UserChoice::Retry => Some(choice),
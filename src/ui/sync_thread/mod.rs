@@ -1,6 +1,8 @@
 use crate::{
     commands::{CheckSyncResult, check_sync_command},
-    config::config_commons::load_config,
+    config::config_commons::{load_config, load_global_options},
+    game_process::{format_running_processes, wait_for_game_to_close},
+    pending_sync::{PendingReason, clear_pending_sync, mark_pending_sync},
     ui::common::{SyncThreadCommand, SyncThreadContext, UIEvent, WebViewState},
 };
 use std::sync::mpsc::Receiver;
@@ -12,7 +14,7 @@ mod ui_messages;
 use interactions::{
     ErrorResolution, SyncOutcome, handle_conflict, handle_remote_empty, handle_sync_error,
 };
-use operations::{pull_from_remote, push_to_remote};
+use operations::{merge_with_remote, pull_from_remote, push_to_remote};
 
 fn block_until<T, F>(receiver: &Receiver<T>, predicate: F) -> T
 where
@@ -33,17 +35,31 @@ where
 fn do_sync(sync_key: &str, context: &mut SyncThreadContext) -> Result<SyncOutcome, String> {
     // Load config
     context.show_loading_step("Syncing", "Checking remote...");
-    let sync_config = load_config(sync_key, context.config_file_override.as_deref())?;
+    let mut sync_config = load_config(sync_key, context.config_file_override.as_deref())?;
+    // No retries while the user waits - the first offline error offers Continue Offline, Retry is up to them.
+    sync_config.remote_timeouts.retries = 0;
     context.game_display_name = Some(sync_config.game_display_name.clone());
     let context: &SyncThreadContext = context; // drop mutability
 
     let main_sync_title = format!("Syncing {}", sync_config.game_display_name);
+
+    // Never sync saves the game may still be writing - e.g. launchers outliving the wrapped command.
+    wait_for_game_to_close(&sync_config, &context.cancel, |running| {
+        context.show_loading_step(
+            &main_sync_title,
+            &format!(
+                "Waiting for game to close... ({})",
+                format_running_processes(running)
+            ),
+        );
+    })?;
     context.show_loading_step(&main_sync_title, "Checking remote...");
 
     let (check_sync_result, remote_head) = check_sync_command(&sync_config)?;
 
     match check_sync_result {
         CheckSyncResult::UpToDate => {
+            clear_pending_sync(&sync_config.local_head_folder, &sync_config.remote_sync_key)?;
             context.show_success_message(&sync_config.game_display_name, "Local is up to date!");
             Ok(SyncOutcome::Completed)
         }
@@ -55,6 +71,10 @@ fn do_sync(sync_key: &str, context: &mut SyncThreadContext) -> Result<SyncOutcom
             push_to_remote(&sync_config, &context, &remote_head, &main_sync_title)?;
             Ok(SyncOutcome::Completed)
         }
+        CheckSyncResult::AutoMerge { .. } => {
+            merge_with_remote(&sync_config, &context, &remote_head, &main_sync_title)?;
+            Ok(SyncOutcome::Completed)
+        }
         CheckSyncResult::RemoteEmpty => {
             handle_remote_empty(&sync_config, &context, &remote_head, &main_sync_title)
         }
@@ -69,12 +89,28 @@ fn do_sync(sync_key: &str, context: &mut SyncThreadContext) -> Result<SyncOutcom
     }
 }
 
+/// Records that local progress was not pushed. Best-effort - must not keep the user from closing.
+fn record_pending_sync(
+    sync_key: &str,
+    context: &SyncThreadContext,
+    reason: PendingReason,
+    error: Option<&str>,
+) {
+    let res = load_global_options(context.config_file_override.as_deref())
+        .and_then(|options| mark_pending_sync(&options.local_head_folder, sync_key, reason, error));
+    if let Err(e) = res {
+        eprintln!("Unable to record pending sync for {sync_key}\n{e}");
+    }
+}
+
 pub fn sync_thread_main(sync_key: &str, mut context: SyncThreadContext) {
     // Await for UI.
     block_until(&context.sync_rx, |cmd| {
         matches!(cmd, SyncThreadCommand::UIReady)
     });
     loop {
+        // Re-arm - a previous attempt may have been cancelled.
+        context.cancel.reset();
         match do_sync(&sync_key, &mut context) {
             Ok(SyncOutcome::Completed) => {
                 // We're done - let UI thread exit with success
@@ -86,22 +122,39 @@ pub fn sync_thread_main(sync_key: &str, mut context: SyncThreadContext) {
                 break;
             }
             Ok(SyncOutcome::Cancelled) => {
+                if context.after_game {
+                    record_pending_sync(sync_key, &context, PendingReason::PushFailed, None);
+                }
                 let _ = context.ui_proxy.send_event(UIEvent::SyncFailedEvent);
                 break;
             }
-            Err(e) => match handle_sync_error(&context, &e) {
-                ErrorResolution::Retry => continue,
-                ErrorResolution::Close => {
-                    let _ = context.ui_proxy.send_event(UIEvent::SyncFailedEvent);
-                    break;
+            Err(e) => {
+                // Recorded right away - the user may close the window instead of choosing.
+                if context.after_game {
+                    record_pending_sync(sync_key, &context, PendingReason::PushFailed, Some(&e));
                 }
-                ErrorResolution::ContinueOffline => {
-                    let _ = context
-                        .ui_proxy
-                        .send_event(UIEvent::SyncSuccessCompletedEvent);
-                    break;
+                match handle_sync_error(&context, &e) {
+                    ErrorResolution::Retry => continue,
+                    ErrorResolution::Close => {
+                        let _ = context.ui_proxy.send_event(UIEvent::SyncFailedEvent);
+                        break;
+                    }
+                    ErrorResolution::ContinueOffline => {
+                        if !context.after_game {
+                            record_pending_sync(
+                                sync_key,
+                                &context,
+                                PendingReason::ContinuedOffline,
+                                Some(&e),
+                            );
+                        }
+                        let _ = context
+                            .ui_proxy
+                            .send_event(UIEvent::SyncSuccessCompletedEvent);
+                        break;
+                    }
                 }
-            },
+            }
         }
     }
 }
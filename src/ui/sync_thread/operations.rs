@@ -1,5 +1,8 @@
 use crate::{
-    commands::{pull_command_with_update_callback, push_command_with_update_callback},
+    commands::{
+        merge_command_with_update_callback, pull_command_with_update_callback,
+        push_command_with_update_callback,
+    },
     common::Revision,
     config::RuntimeSyncConfig,
     ui::common::SyncThreadContext,
@@ -17,9 +20,10 @@ pub(super) fn push_to_remote(
     push_command_with_update_callback(
         sync_config,
         remote_head.as_ref().map(|head| head.hash.as_str()),
-        |txt| {
-            context.send_ui_display_update(&push_title, txt);
+        |progress| {
+            context.send_ui_progress(&push_title, progress);
         },
+        &context.cancel,
     )?;
 
     context.show_success_message(&sync_config.game_display_name, "Uploaded to remote!");
@@ -42,12 +46,39 @@ pub(super) fn pull_from_remote(
     pull_command_with_update_callback(
         sync_config,
         remote_head.as_ref().map(|head| head.hash.as_str()),
-        |txt| {
-            context.send_ui_display_update(&pull_title, txt);
+        |progress| {
+            context.send_ui_progress(&pull_title, progress);
         },
+        &context.cancel,
     )?;
 
     context.show_success_message(&sync_config.game_display_name, "Downloaded from remote!");
 
     Ok(())
 }
+
+pub(super) fn merge_with_remote(
+    sync_config: &RuntimeSyncConfig,
+    context: &SyncThreadContext,
+    remote_head: &Option<Revision>,
+    main_sync_title: &str,
+) -> Result<(), String> {
+    context.show_loading_step(
+        main_sync_title,
+        "Local and remote changed different files - merging both...",
+    );
+
+    let merge_title = format!("Merging {} save files", sync_config.game_display_name);
+    merge_command_with_update_callback(
+        sync_config,
+        remote_head.as_ref().map(|head| head.hash.as_str()),
+        |progress| {
+            context.send_ui_progress(&merge_title, progress);
+        },
+        &context.cancel,
+    )?;
+
+    context.show_success_message(&sync_config.game_display_name, "Merged with remote!");
+
+    Ok(())
+}
@@ -1,5 +1,7 @@
 use crate::{
     common::Revision,
+    pending_sync::PendingSync,
+    progress::ProgressEvent,
     ui::common::{ConflictDisplayInfo, SyncThreadContext, UIEvent, WebViewCommand, WebViewState},
 };
 
@@ -15,6 +17,25 @@ impl SyncThreadContext {
                 sub_text: subtext.into(),
                 conflict_info: None,
                 is_after_game: self.after_game,
+                is_offline: false,
+            },
+        };
+        let _ = self.ui_proxy.send_event(cmd);
+    }
+
+    pub(super) fn send_ui_display_update_error(
+        &self,
+        title: String,
+        error: &str,
+        is_offline: bool,
+    ) {
+        let cmd = UIEvent::WebViewCommand {
+            command: WebViewCommand::WebViewUpdate {
+                title_text: title,
+                sub_text: error.to_string(),
+                conflict_info: None,
+                is_after_game: self.after_game,
+                is_offline: is_offline,
             },
         };
         let _ = self.ui_proxy.send_event(cmd);
@@ -25,12 +46,14 @@ impl SyncThreadContext {
         title: &str,
         local: &Revision,
         remote: &Revision,
+        pending: Option<&PendingSync>,
     ) {
         let conflict_info = ConflictDisplayInfo {
             local_modified_time: local.time_display_str(),
             remote_uploaded_time: remote.time_display_str(),
             local_author: local.author.clone(),
             remote_author: remote.author.clone(),
+            local_unpushed_since: pending.map(|pending| pending.since_display_str()),
         };
 
         let cmd = UIEvent::WebViewCommand {
@@ -39,11 +62,19 @@ impl SyncThreadContext {
                 sub_text: "".to_string(),
                 conflict_info: Some(conflict_info),
                 is_after_game: self.after_game,
+                is_offline: false,
             },
         };
         let _ = self.ui_proxy.send_event(cmd);
     }
 
+    pub(super) fn send_ui_progress(&self, title: &str, progress: ProgressEvent) {
+        self.send_ui_display_update(title, progress.message.clone());
+        let _ = self.ui_proxy.send_event(UIEvent::WebViewCommand {
+            command: WebViewCommand::WebViewProgress { progress },
+        });
+    }
+
     pub(super) fn send_ui_change_state(&self, state: WebViewState) {
         let _ = self.ui_proxy.send_event(UIEvent::WebViewCommand {
             command: WebViewCommand::WebViewStateChange { state },
@@ -7,6 +7,7 @@ use std::{
 use tao::event_loop::EventLoopBuilder;
 use wry::http::Request;
 
+use crate::cancellation::CancellationToken;
 use crate::ui::{
     common::{SyncThreadCommand, SyncThreadContext, UIEvent, WebViewState},
     handle_main_loop_event::handle_main_loop_event,
@@ -32,6 +33,9 @@ pub fn ui_loop_main(
     let (sync_tx, sync_rx): (Sender<SyncThreadCommand>, Receiver<SyncThreadCommand>) =
         mpsc::channel();
 
+    let cancel = CancellationToken::new();
+    let sync_thread_cancel = cancel.clone();
+
     // Spawn actual sync thread
     let sync_thread_handle = std::thread::spawn(move || {
         let context = SyncThreadContext {
@@ -40,6 +44,7 @@ pub fn ui_loop_main(
             after_game,
             config_file_override,
             game_display_name: None,
+            cancel: sync_thread_cancel,
         };
         sync_thread_main(&sync_key, context);
     });
@@ -53,6 +58,7 @@ pub fn ui_loop_main(
             &webview,
             &window,
             &sync_tx,
+            &cancel,
             &sync_thread_handle,
             &webview_state,
             after_game,
@@ -32,6 +32,7 @@ impl FromStr for UserChoice {
             "retry" => Ok(UserChoice::Retry),
             "close" => Ok(UserChoice::Close),
             "continue-offline" => Ok(UserChoice::ContinueOffline),
+            "cancel" => Ok(UserChoice::Cancel),
             _ => Err(()),
         }
     }
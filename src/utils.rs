@@ -61,6 +61,22 @@ pub fn get_unix_timestamp_secs() -> u64 {
         .as_secs();
 }
 
+/// Human readable size - e.g. `1.5 MB`.
+pub fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KB", "MB", "GB", "TB"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+
+    if unit == 0 {
+        return format!("{} {}", bytes, UNITS[0]);
+    }
+    format!("{:.1} {}", value, UNITS[unit])
+}
+
 pub fn generate_display_name_from_key(save_key: &str) -> String {
     // Replace underscores and hyphens with spaces, then capitalize each word
     let mut display_name = save_key
@@ -84,10 +100,28 @@ pub fn generate_display_name_from_key(save_key: &str) -> String {
     display_name
 }
 
+/// Sync key for a game title - e.g. `Hollow Knight: Silksong` -> `hollow-knight-silksong`.
+pub fn generate_key_from_name(name: &str) -> String {
+    return name
+        .to_lowercase()
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .collect::<Vec<_>>()
+        .join("-");
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn format_bytes_test() {
+        assert_eq!(format_bytes(0), "0 B");
+        assert_eq!(format_bytes(1023), "1023 B");
+        assert_eq!(format_bytes(1536), "1.5 KB");
+        assert_eq!(format_bytes(5 * 1024 * 1024), "5.0 MB");
+    }
+
     #[test]
     fn display_name_fallback_test() {
         assert_eq!(generate_display_name_from_key("wargroove"), "Wargroove");
@@ -100,4 +134,13 @@ mod tests {
             "Hollow Knight Silksong"
         );
     }
+
+    #[test]
+    fn key_from_name_test() {
+        assert_eq!(
+            generate_key_from_name("Hollow Knight: Silksong"),
+            "hollow-knight-silksong"
+        );
+        assert_eq!(generate_key_from_name("  DOOM (2016) "), "doom-2016");
+    }
 }
@@ -0,0 +1,17 @@
+// Own test binary - becoming child subreaper and reaping every child would interfere with other tests.
+#![cfg(target_os = "linux")]
+
+use local_cloud_game_sync::game_process::run_and_wait_for_process_tree;
+use std::process::Command;
+use std::time::{Duration, Instant};
+
+#[test]
+pub fn waits_for_orphaned_descendants() {
+    let started_at = Instant::now();
+    // The shell exits right away while its backgrounded child keeps running.
+    let exit_code =
+        run_and_wait_for_process_tree(Command::new("sh").args(["-c", "sleep 2 & exit 3"])).unwrap();
+
+    assert_eq!(exit_code, 3);
+    assert!(started_at.elapsed() >= Duration::from_secs(2));
+}
@@ -3,7 +3,18 @@ mod tests_common;
 use crate::tests_common::common::TEST_SSH_HOST;
 use crate::tests_common::test_sync_client::AssertableCheckSyncResult;
 use crate::tests_common::{test_remote::TestRemote, test_sync_client::TestSyncClient};
+use local_cloud_game_sync::commands::check_sync_command;
+use local_cloud_game_sync::common::Revision;
+use local_cloud_game_sync::config::{ConflictStrategy, RemoteStorageMode, TransferCompression};
+use local_cloud_game_sync::encryption::is_encrypted;
+use local_cloud_game_sync::hashing::HashAlgorithm;
+use local_cloud_game_sync::pending_sync::PendingReason;
+use local_cloud_game_sync::progress::{ProgressEvent, SyncPhase};
+use local_cloud_game_sync::remote_save_client::is_offline_error;
+use local_cloud_game_sync::sync_history::SyncEventKind;
 use serial_test::serial;
+use std::thread;
+use std::time::{Duration, Instant};
 
 #[serial]
 #[test]
@@ -243,3 +254,698 @@ pub fn play_offline_but_accidentally_played_on_other_device_hence_conflict() {
     client1.check_sync().assert_conflict(); // can fast forward
     client2.assert_client_is_remote_author(&remote); // client 2 is the one who managed to push
 }
+
+#[serial]
+#[test]
+pub fn file_merge_different_files_changed() {
+    // Setup
+    let remote = TestRemote::builder().with_empty_remote().build();
+    let client1 = TestSyncClient::builder()
+        .with_client_name("client1")
+        .with_sync_key("testKey")
+        .with_local_test_folder1()
+        .with_conflict_strategy(ConflictStrategy::FileMerge)
+        .build();
+    client1.push().expect("Failed setup push");
+
+    let client2 = TestSyncClient::builder()
+        .with_client_name("client2")
+        .with_sync_key("testKey")
+        .with_empty_test_folder()
+        .with_conflict_strategy(ConflictStrategy::FileMerge)
+        .build();
+    client2.pull().expect("Failed setup pull");
+
+    // Act
+    // client 2 plays one save slot - pushes it
+    client2.modify_stored_file("nested/include.cfg");
+    client2.push().expect("Unable to push to remote");
+
+    // client 1 plays another save slot offline
+    client1.modify_stored_file("save_state.json");
+
+    // Different files - should be merged instead of conflicting
+    client1.check_sync().assert_auto_merge();
+    client1.merge().expect("Unable to merge");
+
+    // Assert
+    client1.assert_client_is_remote_author(&remote);
+    client1.assert_local_data_matches_remote_data(&remote);
+    client1.assert_local_head_and_remote_head_matches_local_data(&remote);
+    client1.assert_is_last_snapshot_restorable_and_matches_local_data(&remote);
+
+    // client 2 just needs to fast-forward to get both changes
+    client2.check_sync().assert_fast_forward_local();
+    client2.pull().expect("Unable to pull merged");
+    client2.assert_local_data_matches_remote_data(&remote);
+}
+
+#[serial]
+#[test]
+pub fn file_merge_same_file_changed_is_conflict() {
+    // Setup
+    let _remote = TestRemote::builder().with_empty_remote().build();
+    let client1 = TestSyncClient::builder()
+        .with_client_name("client1")
+        .with_sync_key("testKey")
+        .with_local_test_folder1()
+        .with_conflict_strategy(ConflictStrategy::FileMerge)
+        .build();
+    client1.push().expect("Failed setup push");
+
+    let client2 = TestSyncClient::builder()
+        .with_client_name("client2")
+        .with_sync_key("testKey")
+        .with_empty_test_folder()
+        .with_conflict_strategy(ConflictStrategy::FileMerge)
+        .build();
+    client2.pull().expect("Failed setup pull");
+
+    // Act
+    client2.modify_stored_save();
+    client2.push().expect("Unable to push to remote");
+    client1.modify_stored_save();
+    client1.modify_stored_save();
+
+    // Same file changed - needs manual resolution
+    client1.check_sync().assert_conflict();
+    client1.merge().expect_err("Expected merge to be refused");
+}
+
+#[serial]
+#[test]
+pub fn revision_log_tracks_ancestry() {
+    // Setup
+    let remote = TestRemote::builder().with_empty_remote().build();
+    let client1 = TestSyncClient::builder()
+        .with_client_name("client1")
+        .with_sync_key("testKey")
+        .with_local_test_folder1()
+        .build();
+    client1.push().expect("Failed setup push");
+    let first_hash = client1.get_local_hash();
+
+    let client2 = TestSyncClient::builder()
+        .with_client_name("client2")
+        .with_sync_key("testKey")
+        .with_empty_test_folder()
+        .build();
+    client2.pull().expect("Failed setup pull");
+
+    // Act - client 1 pushes twice, client 2 stays behind without playing.
+    client1.modify_stored_save();
+    client1.push().expect("Unable to push");
+    let second_hash = client1.get_local_hash();
+    client1.modify_stored_save();
+    client1.push().expect("Unable to push");
+
+    // Assert
+    let history = remote
+        .read_remote_history("testKey")
+        .expect("Unable to read remote history");
+    assert_eq!(history.len(), 3);
+    assert_eq!(history[0].parent, None);
+    assert_eq!(history[1].parent.as_deref(), Some(first_hash.as_str()));
+    assert_eq!(history[2].parent.as_deref(), Some(second_hash.as_str()));
+    assert_eq!(history[2].author, "client1");
+
+    // Several revisions behind but unmodified - can fast forward.
+    client2.check_sync().assert_fast_forward_local();
+    client2.pull().expect("Unable to pull");
+    client2.assert_local_head_and_remote_head_matches_local_data(&remote);
+}
+
+#[serial]
+#[test]
+pub fn sync_history_records_every_device() {
+    // Setup
+    let remote = TestRemote::builder().with_empty_remote().build();
+    let client1 = TestSyncClient::builder()
+        .with_client_name("client1")
+        .with_sync_key("testKey")
+        .with_local_test_folder1()
+        .build();
+    let client2 = TestSyncClient::builder()
+        .with_client_name("client2")
+        .with_sync_key("testKey")
+        .with_empty_test_folder()
+        .build();
+
+    // Act - client 1 overwrites client 2 changes it never pulled.
+    client1.push().expect("Unable to push");
+    client2.pull().expect("Unable to pull");
+    client2.modify_stored_save();
+    client2.push().expect("Unable to push");
+    client1.modify_stored_save();
+    client1.push().expect("Unable to push");
+
+    // Assert
+    let history = remote
+        .read_remote_sync_history("testKey")
+        .expect("Unable to read remote sync history");
+    let summary: Vec<(&str, SyncEventKind, bool)> = history
+        .iter()
+        .map(|e| (e.client_name.as_str(), e.kind, e.resolved_conflict))
+        .collect();
+    assert_eq!(
+        summary,
+        vec![
+            ("client1", SyncEventKind::Push, false),
+            ("client2", SyncEventKind::Pull, false),
+            ("client2", SyncEventKind::Push, false),
+            ("client1", SyncEventKind::Push, true),
+        ]
+    );
+    assert!(history.iter().all(|e| e.file_count > 0 && e.bytes > 0));
+    assert_eq!(history[3].revision.hash, client1.get_local_hash());
+}
+
+#[serial]
+#[test]
+pub fn migrate_legacy_heads() {
+    // Setup - HEADs written by an older client version.
+    let remote = TestRemote::builder().with_empty_remote().build();
+    let client = TestSyncClient::builder()
+        .with_client_name("client1")
+        .with_sync_key("testKey")
+        .with_local_test_folder1()
+        .build();
+    client.push().expect("Failed setup push");
+    remote.rewrite_remote_head_as_legacy("testKey");
+    client.rewrite_local_head_as_legacy();
+
+    // Legacy HEADs are still readable.
+    client.check_sync().assert_up_to_date();
+
+    // Act
+    client.migrate().expect("Unable to migrate");
+
+    // Assert
+    assert!(!Revision::is_legacy_format(
+        &remote.read_remote_head_str("testKey")
+    ));
+    assert!(!Revision::is_legacy_format(&client.read_local_head_str()));
+    client.check_sync().assert_up_to_date();
+    client.assert_local_head_and_remote_head_matches_local_data(&remote);
+}
+
+#[serial]
+#[test]
+pub fn mixed_hash_algorithms_sync() {
+    // Setup - client 1 still hashes with md5, client 2 uses the default.
+    let remote = TestRemote::builder().with_empty_remote().build();
+    let client1 = TestSyncClient::builder()
+        .with_client_name("client1")
+        .with_sync_key("testKey")
+        .with_hash_algorithm(HashAlgorithm::Md5)
+        .with_local_test_folder1()
+        .build();
+    let client2 = TestSyncClient::builder()
+        .with_client_name("client2")
+        .with_sync_key("testKey")
+        .with_empty_test_folder()
+        .build();
+
+    client1.push().expect("Unable to push");
+    let remote_head = remote.read_remote_head("testKey").unwrap().unwrap();
+    assert_eq!(remote_head.hash_algorithm, HashAlgorithm::Md5);
+
+    // Act & Assert - md5 HEAD is understood by client 2.
+    client2.check_sync().assert_fast_forward_local();
+    client2.pull().expect("Unable to pull");
+    client2.check_sync().assert_up_to_date();
+
+    // client 2 pushes with its own algorithm - client 1 can still fast-forward without a conflict.
+    client2.modify_stored_save();
+    client2.check_sync().assert_fast_forward_remote();
+    client2.push().expect("Unable to push");
+    let remote_head = remote.read_remote_head("testKey").unwrap().unwrap();
+    assert_eq!(remote_head.hash_algorithm, HashAlgorithm::Blake3);
+
+    client1.check_sync().assert_fast_forward_local();
+    client1.pull().expect("Unable to pull");
+    client1.check_sync().assert_up_to_date();
+    client2.assert_local_data_matches_remote_data(&remote);
+}
+
+#[serial]
+#[test]
+pub fn hash_cache_detects_modifications() {
+    // Setup
+    let _remote = TestRemote::builder().with_empty_remote().build();
+    let mut client = TestSyncClient::builder()
+        .with_client_name("client1")
+        .with_sync_key("testKey")
+        .with_local_test_folder1()
+        .build();
+    client.push().expect("Unable to push");
+    assert!(client.has_hash_cache());
+
+    // Act & Assert - cached digests must not hide new changes.
+    client.check_sync().assert_up_to_date();
+    client.modify_stored_save();
+    client.check_sync().assert_fast_forward_remote();
+
+    client.config.rehash = true;
+    client.check_sync().assert_fast_forward_remote();
+}
+
+#[serial]
+#[test]
+pub fn compressed_transfer_roundtrip() {
+    // Setup
+    let remote = TestRemote::builder().with_empty_remote().build();
+    let client1 = TestSyncClient::builder()
+        .with_client_name("client1")
+        .with_sync_key("testKey")
+        .with_transfer_compression(TransferCompression::Zstd)
+        .with_local_test_folder1()
+        .build();
+    let client2 = TestSyncClient::builder()
+        .with_client_name("client2")
+        .with_sync_key("testKey")
+        .with_transfer_compression(TransferCompression::Zstd)
+        .with_empty_test_folder()
+        .build();
+
+    // Act
+    client1.push().expect("Unable to push");
+    client2.pull().expect("Unable to pull");
+
+    // Assert - remote still stores a loose tree, hashes are unaffected by compression.
+    client1.assert_local_data_matches_remote_data(&remote);
+    client2.assert_local_head_and_remote_head_matches_local_data(&remote);
+    assert_eq!(client1.get_local_hash(), client2.get_local_hash());
+}
+
+#[serial]
+#[test]
+pub fn archive_storage_roundtrip() {
+    // Setup
+    let remote = TestRemote::builder().with_empty_remote().build();
+    let client1 = TestSyncClient::builder()
+        .with_client_name("client1")
+        .with_sync_key("testKey")
+        .with_remote_storage(RemoteStorageMode::Archive)
+        .with_local_test_folder1()
+        .build();
+    // Pulling side does not need to be configured for archives - it is detected.
+    let client2 = TestSyncClient::builder()
+        .with_client_name("client2")
+        .with_sync_key("testKey")
+        .with_empty_test_folder()
+        .build();
+
+    // Act
+    client1.push().expect("Unable to push");
+    client2.pull().expect("Unable to pull");
+
+    // Assert
+    assert_eq!(
+        remote.list_remote_saves_folder("testKey"),
+        vec!["manifest.json".to_string(), "saves.tar.zst".to_string()]
+    );
+    client2.check_sync().assert_up_to_date();
+    assert_eq!(client1.get_local_hash(), client2.get_local_hash());
+    let remote_head = remote.read_remote_head("testKey").unwrap().unwrap();
+    assert_eq!(remote_head.hash, client2.get_local_hash());
+}
+
+#[serial]
+#[test]
+pub fn encrypted_saves_never_reach_remote_as_plaintext() {
+    // Setup
+    let remote = TestRemote::builder().with_empty_remote().build();
+    let client1 = TestSyncClient::builder()
+        .with_client_name("client1")
+        .with_sync_key("testKey")
+        .with_encryption_passphrase("correct horse battery staple")
+        .with_local_test_folder1()
+        .build();
+    let client2 = TestSyncClient::builder()
+        .with_client_name("client2")
+        .with_sync_key("testKey")
+        .with_encryption_passphrase("correct horse battery staple")
+        .with_empty_test_folder()
+        .build();
+    let mut client3 = TestSyncClient::builder()
+        .with_client_name("client3")
+        .with_sync_key("testKey")
+        .with_empty_test_folder()
+        .build();
+
+    // Act
+    client1.push().expect("Unable to push");
+
+    // Assert - no remote file contains any local file's contents.
+    let remote_files = remote.read_remote_save_files("testKey");
+    let local_files = client1.read_local_save_files();
+    assert_eq!(remote_files.len(), local_files.len());
+    for plaintext in local_files.iter().filter(|f| !f.is_empty()) {
+        assert!(remote_files.iter().all(|remote_file| {
+            !remote_file
+                .windows(plaintext.len())
+                .any(|window| window == plaintext.as_slice())
+        }));
+    }
+    // Neither do the per-file digests of the MANIFEST.
+    let remote_manifest = remote.read_remote_manifest_bytes("testKey");
+    assert!(is_encrypted(&remote_manifest));
+
+    // HEAD is computed on plaintext - so devices compare as usual.
+    let remote_head = remote.read_remote_head("testKey").unwrap().unwrap();
+    assert_eq!(remote_head.hash, client1.get_local_hash());
+    client2.check_sync().assert_fast_forward_local();
+    client2.pull().expect("Unable to pull");
+    client2.check_sync().assert_up_to_date();
+    assert_eq!(client1.get_local_hash(), client2.get_local_hash());
+
+    // Devices without the key can neither pull ciphertext nor join with a wrong passphrase.
+    let pull_err = client3.pull().unwrap_err();
+    assert!(pull_err.contains("is encrypted"), "{pull_err}");
+    assert!(client3.init_encryption_key("wrong passphrase").is_err());
+}
+
+#[serial]
+#[test]
+pub fn daemon_pushes_changes_after_quiet_period() {
+    // Setup
+    let remote = TestRemote::builder().with_empty_remote().build();
+    let client = TestSyncClient::builder()
+        .with_client_name("client1")
+        .with_sync_key("testKey")
+        .with_local_test_folder1()
+        .build();
+    client.push().expect("Unable to push");
+    let quiet_period = Duration::from_secs(2);
+
+    // Act
+    client.run_daemon(quiet_period, || {
+        client.modify_stored_save();
+        let changed_at = Instant::now();
+        let changed_hash = client.get_local_hash();
+        let mut pushed_after = None;
+        while changed_at.elapsed() < Duration::from_secs(60) {
+            let remote_head = remote.read_remote_head("testKey").unwrap();
+            if remote_head.is_some_and(|head| head.hash == changed_hash) {
+                pushed_after = Some(changed_at.elapsed());
+                break;
+            }
+            thread::sleep(Duration::from_millis(200));
+        }
+
+        // Assert
+        let pushed_after = pushed_after.expect("Daemon did not push the change");
+        assert!(
+            pushed_after >= quiet_period,
+            "Pushed after {pushed_after:?} - before the quiet period"
+        );
+    });
+
+    // Assert
+    client.assert_local_head_and_remote_head_matches_local_data(&remote);
+    client.assert_client_is_remote_author(&remote);
+}
+
+#[serial]
+#[test]
+pub fn encrypted_clients_refuse_plaintext_remote_files_unless_migrating() {
+    // Setup
+    let _remote = TestRemote::builder().with_empty_remote().build();
+    let client1 = TestSyncClient::builder()
+        .with_client_name("client1")
+        .with_sync_key("testKey")
+        .with_local_test_folder1()
+        .build();
+    let mut client2 = TestSyncClient::builder()
+        .with_client_name("client2")
+        .with_sync_key("testKey")
+        .with_encryption_passphrase("correct horse battery staple")
+        .with_empty_test_folder()
+        .build();
+
+    // Act - saves pushed before encryption was enabled.
+    client1.push().expect("Unable to push");
+
+    // Assert
+    let pull_err = client2.pull().unwrap_err();
+    assert!(pull_err.contains("is not encrypted"), "{pull_err}");
+    client2.allow_unencrypted_pull();
+    client2.pull().expect("Unable to pull while migrating");
+    assert_eq!(client1.get_local_hash(), client2.get_local_hash());
+}
+
+#[serial]
+#[test]
+pub fn transfers_report_file_and_byte_progress() {
+    // Setup
+    let _remote = TestRemote::builder().with_empty_remote().build();
+    let client1 = TestSyncClient::builder()
+        .with_client_name("client1")
+        .with_sync_key("testKey")
+        .with_local_test_folder1()
+        .build();
+    let client2 = TestSyncClient::builder()
+        .with_client_name("client2")
+        .with_sync_key("testKey")
+        .with_empty_test_folder()
+        .build();
+
+    // Act
+    let push_events = client1.push_collecting_progress().expect("Unable to push");
+    let pull_events = client2.pull_collecting_progress().expect("Unable to pull");
+
+    // Assert - the last transfer event has every file and byte accounted for.
+    let last_of = |events: &[ProgressEvent], phase: SyncPhase| {
+        events
+            .iter()
+            .filter(|event| event.phase == phase)
+            .last()
+            .cloned()
+            .expect("No transfer progress reported")
+    };
+    for transfer in [
+        last_of(&push_events, SyncPhase::Uploading),
+        last_of(&pull_events, SyncPhase::Downloading),
+    ] {
+        assert_eq!(transfer.files_total, Some(4));
+        assert_eq!(transfer.files_done, 4);
+        assert_eq!(transfer.bytes_total, Some(transfer.bytes_done));
+        assert_eq!(transfer.current_file, None);
+    }
+    assert_eq!(push_events.last().unwrap().phase, SyncPhase::Done);
+    assert_eq!(client1.get_local_hash(), client2.get_local_hash());
+}
+
+#[test]
+#[serial]
+pub fn cancelled_transfers_leave_remote_and_local_untouched() {
+    // Setup
+    let remote = TestRemote::builder().with_empty_remote().build();
+    let client1 = TestSyncClient::builder()
+        .with_client_name("client1")
+        .with_sync_key("testKey")
+        .with_local_test_folder1()
+        .build();
+    let client2 = TestSyncClient::builder()
+        .with_client_name("client2")
+        .with_sync_key("testKey")
+        .with_empty_test_folder()
+        .build();
+
+    // Act & Assert - cancelled push leaves no trace on the remote
+    let err = client1
+        .push_cancelled_during_upload()
+        .expect_err("Expected cancelled push to fail");
+    assert!(err.contains("cancelled"), "Unexpected error: {err}");
+    assert!(remote.read_remote_head("testKey").unwrap().is_none());
+    assert!(!remote.has_remote_incoming_folder("testKey"));
+    client1.check_sync().assert_remote_empty();
+
+    // Lock was released - so pushing again works.
+    client1.push().expect("Unable to push after cancel");
+    client1.assert_local_head_and_remote_head_matches_local_data(&remote);
+
+    // Cancelled pull leaves local files untouched
+    let err = client2
+        .pull_cancelled_during_download()
+        .expect_err("Expected cancelled pull to fail");
+    assert!(err.contains("cancelled"), "Unexpected error: {err}");
+    assert!(client2.read_local_save_files().is_empty());
+    client2.pull().expect("Unable to pull after cancel");
+    assert_eq!(client1.get_local_hash(), client2.get_local_hash());
+}
+
+#[test]
+#[serial]
+pub fn unreachable_remote_fails_fast_as_offline() {
+    // Setup - nothing listens on port 1, so the connection is refused.
+    let mut client = TestSyncClient::builder()
+        .with_client_name("client1")
+        .with_sync_key("testKey")
+        .with_local_test_folder1()
+        .build();
+    client.config.ssh_port = 1;
+    client.config.remote_timeouts.retries = 1;
+
+    // Act
+    let started_at = std::time::Instant::now();
+    let check_err = check_sync_command(&client.config).expect_err("Expected check to fail");
+    let push_err = client.push().expect_err("Expected push to fail");
+
+    // Assert - reported as offline after a single retry, instead of hanging.
+    assert!(
+        is_offline_error(&check_err),
+        "Unexpected error: {check_err}"
+    );
+    assert!(is_offline_error(&push_err), "Unexpected error: {push_err}");
+    assert!(started_at.elapsed() < std::time::Duration::from_secs(30));
+}
+
+#[test]
+#[serial]
+pub fn pending_syncs_are_drained_once_remote_is_reachable() {
+    // Setup
+    let remote = TestRemote::builder().with_empty_remote().build();
+    let client1 = TestSyncClient::builder()
+        .with_client_name("client1")
+        .with_sync_key("testKey")
+        .with_local_test_folder1()
+        .build();
+    let client2 = TestSyncClient::builder()
+        .with_client_name("client2")
+        .with_sync_key("testKey")
+        .with_empty_test_folder()
+        .build();
+    client1.push().expect("Failed setup push");
+    client2.pull().expect("Failed setup pull");
+
+    // Act - client1 played offline, then sync --pending once online
+    client1.modify_stored_save();
+    client1.mark_pending(PendingReason::ContinuedOffline);
+    client1.sync_pending().expect("Unable to sync pending");
+
+    // Assert
+    assert_eq!(client1.read_pending(), None);
+    client1.assert_local_head_and_remote_head_matches_local_data(&remote);
+
+    // Act - client2 also played offline - conflicts are left for the UI.
+    client2.modify_stored_save();
+    client2.mark_pending(PendingReason::PushFailed);
+    client2
+        .sync_pending()
+        .expect_err("Expected conflict to fail sync --pending");
+
+    // Assert - marker kept until resolved
+    assert_eq!(
+        client2.read_pending().map(|pending| pending.reason),
+        Some(PendingReason::PushFailed)
+    );
+    client2.push().expect("Unable to resolve by pushing");
+    assert_eq!(client2.read_pending(), None);
+}
+
+#[test]
+#[serial]
+pub fn running_game_blocks_pulls_and_pushes_and_defers_sync_pushes() {
+    // Setup - `sleep` stands in for the game process.
+    let remote = TestRemote::builder().with_empty_remote().build();
+    let client1 = TestSyncClient::builder()
+        .with_client_name("client1")
+        .with_sync_key("testKey")
+        .with_local_test_folder1()
+        .build();
+    let client2 = TestSyncClient::builder()
+        .with_client_name("client2")
+        .with_sync_key("testKey")
+        .with_empty_test_folder()
+        .with_process_names(&["sleep"])
+        .build();
+    client1.push().expect("Failed setup push");
+    let mut game = std::process::Command::new("sleep")
+        .arg("30")
+        .spawn()
+        .expect("Unable to spawn game stand-in");
+
+    // Act & Assert - pulling is refused while the game runs
+    let pull_err = client2.pull().expect_err("Expected pull to be refused");
+    assert!(
+        pull_err.contains("still running"),
+        "Unexpected error: {pull_err}"
+    );
+    game.kill().expect("Unable to stop game stand-in");
+    game.wait().expect("Unable to reap game stand-in");
+    client2.pull().expect("Unable to pull once the game closed");
+
+    // Act - sync waits for the game to close before pushing
+    let mut game = std::process::Command::new("sleep")
+        .arg("30")
+        .spawn()
+        .expect("Unable to spawn game stand-in");
+    client2.modify_stored_save();
+    // A plain push is refused - it would upload saves the game may be halfway through writing.
+    let push_err = client2.push().expect_err("Expected push to be refused");
+    assert!(
+        push_err.contains("still running"),
+        "Unexpected error: {push_err}"
+    );
+    let closer = std::thread::spawn(move || {
+        std::thread::sleep(std::time::Duration::from_secs(3));
+        game.kill().expect("Unable to stop game stand-in");
+        game.wait().expect("Unable to reap game stand-in");
+    });
+    let started_at = std::time::Instant::now();
+    client2
+        .sync()
+        .expect("Unable to sync after the game closed");
+    closer.join().unwrap();
+
+    // Assert
+    assert!(started_at.elapsed() >= std::time::Duration::from_secs(3));
+    client2.assert_local_head_and_remote_head_matches_local_data(&remote);
+}
+
+#[test]
+#[serial]
+pub fn shared_entries_are_pushed_and_pulled_between_devices() {
+    // Setup
+    let _remote = TestRemote::builder().with_empty_remote().build();
+    let client1 = TestSyncClient::builder()
+        .with_client_name("client1")
+        .with_sync_key("testKey")
+        .with_local_test_folder1()
+        .build();
+    let client2 = TestSyncClient::builder()
+        .with_client_name("client2")
+        .with_sync_key("testKey")
+        .with_empty_test_folder()
+        .build();
+    let one_entry =
+        r#"[{"remoteSyncKey": "game-a", "saveFolderPath": "/saves/a", "saveIgnoreGlob": []}]"#;
+    let two_entries = r#"[
+        {"remoteSyncKey": "game-a", "saveFolderPath": "/saves/a", "saveIgnoreGlob": []},
+        {"remoteSyncKey": "game-b", "saveFolderPath": {"linux": "/saves/b"}, "saveIgnoreGlob": []}
+    ]"#;
+
+    // Act - client1 shares an entry, client2 pulls it and adds another
+    client2
+        .config_pull(false)
+        .expect_err("Expected pull from an empty remote to fail");
+    client1.write_shared_entries(one_entry);
+    client1.config_push(false).expect("Unable to push entries");
+    client2.config_pull(false).expect("Unable to pull entries");
+    assert_eq!(client2.read_shared_entries(), one_entry);
+    client2.write_shared_entries(two_entries);
+    client2
+        .config_push(false)
+        .expect("Unable to push new entry");
+
+    // Assert - client1 can't overwrite or discard changes it hasn't seen
+    client1.write_shared_entries("[]");
+    let push_err = client1.config_push(false).unwrap_err();
+    assert!(push_err.contains("changed since"), "{push_err}");
+    let pull_err = client1.config_pull(false).unwrap_err();
+    assert!(pull_err.contains("never pushed"), "{pull_err}");
+    client1.config_pull(true).expect("Unable to force pull");
+    assert_eq!(client1.read_shared_entries(), two_entries);
+}
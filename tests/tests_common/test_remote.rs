@@ -5,10 +5,12 @@ use std::{
 
 use globset::GlobSet;
 use local_cloud_game_sync::{
-    common::Revision,
+    common::{Revision, deserialize_revision_log},
     config::config_commons::{
         REMOTE_HEAD_FOLDER_NAME, REMOTE_SAVES_FOLDER_NAME, REMOTE_SNAPSHOT_FOLDER_NAME,
     },
+    hashing::HashAlgorithm,
+    sync_history::{SyncEvent, deserialize_sync_history},
     tree_utils::tree_folder_hash,
 };
 
@@ -49,6 +51,88 @@ impl TestRemote {
         Ok(Some(rev))
     }
 
+    pub fn read_remote_head_str(&self, sync_key: &str) -> String {
+        fs::read_to_string(
+            self.remote_heads_folder_path
+                .join(format!("{}.HEAD", sync_key)),
+        )
+        .expect("Unable to read remote HEAD")
+    }
+
+    /// Raw remote MANIFEST - encrypted when pushed with a key.
+    pub fn read_remote_manifest_bytes(&self, sync_key: &str) -> Vec<u8> {
+        fs::read(
+            self.remote_heads_folder_path
+                .join(format!("{}.MANIFEST", sync_key)),
+        )
+        .expect("Unable to read remote MANIFEST")
+    }
+
+    // Helper for simulating a remote HEAD written by an older client version
+    pub fn rewrite_remote_head_as_legacy(&self, sync_key: &str) -> () {
+        let head = self
+            .read_remote_head(sync_key)
+            .expect("Unable to read remote HEAD")
+            .expect("No remote HEAD to rewrite");
+        fs::write(
+            self.remote_heads_folder_path
+                .join(format!("{}.HEAD", sync_key)),
+            format!("{}\n", head.serialize_legacy()),
+        )
+        .expect("Unable to rewrite remote HEAD");
+    }
+
+    /// Lists the entries directly inside `GameSaves/<key>` on the remote.
+    pub fn list_remote_saves_folder(&self, sync_key: &str) -> Vec<String> {
+        let mut entries: Vec<String> = fs::read_dir(self.remote_saves_folder_path.join(sync_key))
+            .expect("Unable to list remote saves folder")
+            .map(|entry| {
+                entry
+                    .expect("Unable to read remote saves folder entry")
+                    .file_name()
+                    .to_string_lossy()
+                    .to_string()
+            })
+            .collect();
+        entries.sort();
+        entries
+    }
+
+    /// Whether a staged upload (`GameSaves/<key>.incoming`) was left behind on the remote.
+    pub fn has_remote_incoming_folder(&self, sync_key: &str) -> bool {
+        self.remote_saves_folder_path
+            .join(format!("{sync_key}.incoming"))
+            .exists()
+    }
+
+    pub fn read_remote_history(&self, sync_key: &str) -> Result<Vec<Revision>, String> {
+        let remote_log_path = self
+            .remote_heads_folder_path
+            .join(format!("{}.LOG", sync_key));
+
+        if !remote_log_path.exists() {
+            return Ok(vec![]);
+        }
+
+        let logstr = fs::read_to_string(remote_log_path)
+            .map_err(|e| format!("Unable to read remote revision log\n{e}"))?;
+        deserialize_revision_log(&logstr)
+    }
+
+    pub fn read_remote_sync_history(&self, sync_key: &str) -> Result<Vec<SyncEvent>, String> {
+        let remote_history_path = self
+            .remote_heads_folder_path
+            .join(format!("{}.HISTORY", sync_key));
+
+        if !remote_history_path.exists() {
+            return Ok(vec![]);
+        }
+
+        let historystr = fs::read_to_string(remote_history_path)
+            .map_err(|e| format!("Unable to read remote sync history\n{e}"))?;
+        deserialize_sync_history(&historystr)
+    }
+
     pub fn get_snapshots(&self, sync_key: &str) -> io::Result<Vec<ResticSnapshotManifest>> {
         let repo_location = self.remote_snapshots_folder_path.join(sync_key);
 
@@ -79,10 +163,27 @@ impl TestRemote {
         Ok(TestTempFolder::from_path(restored_path))
     }
 
-    pub fn get_remote_hash(&self, sync_key: &str) -> String {
+    /// Raw contents of every file stored for the key - exactly what the remote sees.
+    pub fn read_remote_save_files(&self, sync_key: &str) -> Vec<Vec<u8>> {
+        let mut contents = vec![];
+        let mut pending = vec![self.remote_saves_folder_path.join(sync_key)];
+        while let Some(dir) = pending.pop() {
+            for entry in fs::read_dir(&dir).expect("Unable to read remote saves folder") {
+                let path = entry.expect("Unable to read remote saves entry").path();
+                match path.is_dir() {
+                    true => pending.push(path),
+                    false => contents.push(fs::read(&path).expect("Unable to read remote save")),
+                }
+            }
+        }
+        return contents;
+    }
+
+    pub fn get_remote_hash(&self, sync_key: &str, algorithm: HashAlgorithm) -> String {
         let (hash, _ts) = tree_folder_hash(
             &self.remote_saves_folder_path.join(sync_key),
             &GlobSet::empty(),
+            algorithm,
         )
         .unwrap();
 
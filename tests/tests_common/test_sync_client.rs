@@ -1,9 +1,31 @@
-use std::{fs::OpenOptions, io::Write, path::Path};
+use std::{
+    cell::RefCell,
+    collections::BTreeMap,
+    fs::OpenOptions,
+    io::Write,
+    path::{Path, PathBuf},
+    thread,
+    time::{Duration, Instant},
+};
 
 use globset::GlobSet;
 use local_cloud_game_sync::{
-    commands::{CheckSyncResult, check_sync_command, pull_command, push_command},
-    config::RuntimeSyncConfig,
+    cancellation::CancellationToken,
+    commands::{
+        CheckSyncResult, check_sync_command, config_pull_command, config_push_command,
+        keys_init_command, merge_command, migrate_command, pull_command,
+        pull_command_with_update_callback, push_command, push_command_with_update_callback,
+        sync_command, sync_pending_command,
+    },
+    config::{
+        DaemonOptions, RuntimeSyncConfig, SyncEntry, ValidatedSyncOptions,
+        shared_entries::get_shared_entries_path,
+    },
+    daemon::{daemon_main::daemon_main, daemon_state::read_daemon_state},
+    encryption::read_local_key,
+    local_head::{get_local_hash_cache_filepath, get_local_head_filepath, read_local_head},
+    pending_sync::{PendingReason, PendingSync, mark_pending_sync, read_pending_sync},
+    progress::{ProgressEvent, SyncPhase},
     tree_utils::tree_folder_hash,
 };
 
@@ -44,9 +66,276 @@ impl TestSyncClient {
         Ok(res)
     }
 
+    /// Pushes and returns every progress event reported on the way.
+    pub fn push_collecting_progress(&self) -> Result<Vec<ProgressEvent>, String> {
+        let events = RefCell::new(vec![]);
+        push_command_with_update_callback(
+            &self.config,
+            None,
+            |event| events.borrow_mut().push(event),
+            &CancellationToken::new(),
+        )?;
+        Ok(events.into_inner())
+    }
+
+    /// Pulls and returns every progress event reported on the way.
+    pub fn pull_collecting_progress(&self) -> Result<Vec<ProgressEvent>, String> {
+        let events = RefCell::new(vec![]);
+        pull_command_with_update_callback(
+            &self.config,
+            None,
+            |event| events.borrow_mut().push(event),
+            &CancellationToken::new(),
+        )?;
+        Ok(events.into_inner())
+    }
+
+    /// Pushes - cancelling as soon as the upload starts.
+    pub fn push_cancelled_during_upload(&self) -> Result<(), String> {
+        let cancel = CancellationToken::new();
+        push_command_with_update_callback(
+            &self.config,
+            None,
+            |event| {
+                if event.phase == SyncPhase::Uploading {
+                    cancel.cancel();
+                }
+            },
+            &cancel,
+        )
+    }
+
+    /// Pulls - cancelling as soon as the download starts.
+    pub fn pull_cancelled_during_download(&self) -> Result<(), String> {
+        let cancel = CancellationToken::new();
+        pull_command_with_update_callback(
+            &self.config,
+            None,
+            |event| {
+                if event.phase == SyncPhase::Downloading {
+                    cancel.cancel();
+                }
+            },
+            &cancel,
+        )
+    }
+
+    pub fn mark_pending(&self, reason: PendingReason) -> () {
+        mark_pending_sync(
+            &self.config.local_head_folder,
+            &self.config.remote_sync_key,
+            reason,
+            None,
+        )
+        .expect("Unable to mark pending sync");
+    }
+
+    pub fn read_pending(&self) -> Option<PendingSync> {
+        read_pending_sync(&self.config.local_head_folder, &self.config.remote_sync_key)
+            .expect("Unable to read pending sync")
+    }
+
+    pub fn sync(&self) -> Result<CheckSyncResult, String> {
+        println!("--- [{}] Starting sync ---- ", self._client_name);
+        let res = sync_command(&self.config, |_| {}, &CancellationToken::new())?;
+        println!("--- [{}] Finished sync ---- ", self._client_name);
+        Ok(res)
+    }
+
+    /// Runs `sync --pending` for this client's head folder - only its own key can be loaded.
+    pub fn sync_pending(&self) -> Result<(), String> {
+        println!("--- [{}] Starting sync --pending ---- ", self._client_name);
+        let res = sync_pending_command(
+            &self.config.local_head_folder,
+            |sync_key| match sync_key == self.config.remote_sync_key {
+                true => Ok(self.config.clone()),
+                false => Err(format!("Unknown sync key {sync_key}")),
+            },
+            |_| {},
+            &CancellationToken::new(),
+        )?;
+        println!("--- [{}] Finished sync --pending ---- ", self._client_name);
+        Ok(res)
+    }
+
+    pub fn merge(&self) -> Result<(), String> {
+        println!("--- [{}] Starting merge ---- ", self._client_name);
+        let res = merge_command(&self.config, None)?;
+        println!("--- [{}] Finished merge ---- ", self._client_name);
+        Ok(res)
+    }
+
+    pub fn migrate(&self) -> Result<(), String> {
+        println!("--- [{}] Starting migrate ---- ", self._client_name);
+        let res = migrate_command(&self.config)?;
+        println!("--- [{}] Finished migrate ---- ", self._client_name);
+        Ok(res)
+    }
+
+    fn global_options(&self, encrypt: bool) -> ValidatedSyncOptions {
+        ValidatedSyncOptions {
+            client_name: self.config.client_name.clone(),
+            ssh_host: self.config.ssh_host.clone(),
+            ssh_port: self.config.ssh_port,
+            remote_sync_root: self.config.remote_sync_root.clone(),
+            local_head_folder: self.config.local_head_folder.clone(),
+            hash_algorithm: self.config.hash_algorithm,
+            transfer_compression: self.config.transfer_compression,
+            encrypt: encrypt,
+            remote_timeouts: self.config.remote_timeouts,
+            daemon: DaemonOptions::default(),
+            variables: BTreeMap::new(),
+            sync_entries: vec![],
+        }
+    }
+
+    /// Runs the daemon for this client's entry while `during` runs - once its first poll finished.
+    /// Remote polls after that are disabled - only local changes sync.
+    pub fn run_daemon<F>(&self, quiet_period: Duration, during: F) -> ()
+    where
+        F: FnOnce(),
+    {
+        let mut options = self.global_options(self.config.encryption_key.is_some());
+        options.daemon = DaemonOptions {
+            quiet_period: quiet_period,
+            poll_interval: Duration::from_secs(3600),
+        };
+        options.sync_entries = vec![SyncEntry {
+            remote_sync_key: self.config.remote_sync_key.clone(),
+            save_folder_path: self.config.local_save_folder.display().to_string().into(),
+            save_ignore_glob: vec![],
+            display_name: None,
+            conflict_strategy: None,
+            remote_storage: None,
+            process_names: None,
+        }];
+        let cancel = CancellationToken::new();
+
+        println!("--- [{}] Starting daemon ---- ", self._client_name);
+        thread::scope(|scope| {
+            let daemon =
+                scope.spawn(|| daemon_main(&options, |_| Ok(self.config.clone()), &cancel));
+
+            let started_at = Instant::now();
+            while !read_daemon_state(&self.config.local_head_folder)
+                .expect("Unable to read daemon state")
+                .is_some_and(|state| state.last_poll_at.is_some())
+            {
+                assert!(
+                    started_at.elapsed() < Duration::from_secs(60),
+                    "Daemon did not finish its first poll"
+                );
+                thread::sleep(Duration::from_millis(100));
+            }
+            during();
+
+            cancel.cancel();
+            daemon
+                .join()
+                .expect("Daemon panicked")
+                .expect("Daemon failed");
+        });
+        println!("--- [{}] Stopped daemon ---- ", self._client_name);
+    }
+
+    /// Runs `keys init` against the test remote and enables encryption with the resulting key.
+    /// Same as the `--allow-unencrypted` CLI flag.
+    pub fn allow_unencrypted_pull(&mut self) -> () {
+        self.config.allow_unencrypted_pull = true;
+    }
+
+    pub fn init_encryption_key(&mut self, passphrase: &str) -> Result<(), String> {
+        let options = self.global_options(true);
+        keys_init_command(&options, passphrase)?;
+        self.config.encryption_key = read_local_key(&self.config.local_head_folder)?;
+        Ok(())
+    }
+
+    /// Config file of the client - only its folder matters for the shared entries commands.
+    fn config_file(&self) -> PathBuf {
+        let config_file = self.config.local_head_folder.join("sync_config.json");
+        if !config_file.exists() {
+            std::fs::write(&config_file, "{}").expect("Unable to write test config file");
+        }
+        return config_file;
+    }
+
+    pub fn write_shared_entries(&self, content: &str) {
+        std::fs::write(get_shared_entries_path(&self.config_file()), content)
+            .expect("Unable to write shared entries");
+    }
+
+    pub fn read_shared_entries(&self) -> String {
+        std::fs::read_to_string(get_shared_entries_path(&self.config_file()))
+            .expect("Unable to read shared entries")
+    }
+
+    pub fn config_pull(&self, force: bool) -> Result<(), String> {
+        println!("--- [{}] Starting config pull ---- ", self._client_name);
+        let res = config_pull_command(
+            &self.global_options(false),
+            Some(&self.config_file()),
+            force,
+        );
+        println!("--- [{}] Finished config pull ---- ", self._client_name);
+        res
+    }
+
+    pub fn config_push(&self, force: bool) -> Result<(), String> {
+        println!("--- [{}] Starting config push ---- ", self._client_name);
+        let res = config_push_command(
+            &self.global_options(false),
+            Some(&self.config_file()),
+            force,
+        );
+        println!("--- [{}] Finished config push ---- ", self._client_name);
+        res
+    }
+
+    /// Raw contents of every local save file.
+    pub fn read_local_save_files(&self) -> Vec<Vec<u8>> {
+        let mut contents = vec![];
+        let mut pending = vec![self.config.local_save_folder.clone()];
+        while let Some(dir) = pending.pop() {
+            for entry in std::fs::read_dir(&dir).expect("Unable to read local save folder") {
+                let path = entry.expect("Unable to read local save entry").path();
+                match path.is_dir() {
+                    true => pending.push(path),
+                    false => contents.push(std::fs::read(&path).expect("Unable to read save")),
+                }
+            }
+        }
+        return contents;
+    }
+
+    pub fn read_local_head_str(&self) -> String {
+        std::fs::read_to_string(get_local_head_filepath(&self.config))
+            .expect("Unable to read local HEAD")
+    }
+
+    // Helper for simulating a local HEAD written by an older client version
+    pub fn rewrite_local_head_as_legacy(&self) -> () {
+        let head = read_local_head(&self.config)
+            .expect("Unable to read local HEAD")
+            .expect("No local HEAD to rewrite");
+        std::fs::write(
+            get_local_head_filepath(&self.config),
+            head.serialize_legacy(),
+        )
+        .expect("Unable to rewrite local HEAD");
+    }
+
+    pub fn has_hash_cache(&self) -> bool {
+        get_local_hash_cache_filepath(&self.config).exists()
+    }
+
     pub fn get_local_hash(&self) -> String {
-        let (hash, _ts) =
-            tree_folder_hash(&self.config.local_save_folder, &GlobSet::empty()).unwrap();
+        let (hash, _ts) = tree_folder_hash(
+            &self.config.local_save_folder,
+            &GlobSet::empty(),
+            self.config.hash_algorithm,
+        )
+        .unwrap();
         return hash;
     }
 
@@ -54,9 +343,14 @@ impl TestSyncClient {
     pub fn modify_stored_save(&self) -> () {
         // Modify files in the test folder to simulate gameplay
         // This would be implemented based on your test file setup
-        let save_path = self.config.local_save_folder.join("save_state.json");
+        self.modify_stored_file("save_state.json");
+    }
+
+    // Helper for simulating game play that touches a specific file (e.g. a single save slot)
+    pub fn modify_stored_file(&self, relpath: &str) -> () {
+        let save_path = self.config.local_save_folder.join(relpath);
         if !save_path.exists() {
-            panic!("Cannot modify save path as didn't find save_state.json");
+            panic!("Cannot modify save path as didn't find {relpath}");
         }
 
         let mut filebuf = OpenOptions::new()
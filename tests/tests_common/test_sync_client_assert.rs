@@ -11,6 +11,7 @@ pub trait AssertableCheckSyncResult {
     fn assert_fast_forward_remote(&self);
     fn assert_fast_forward_local(&self);
     fn assert_conflict(&self);
+    fn assert_auto_merge(&self);
 }
 
 impl AssertableCheckSyncResult for CheckSyncResult {
@@ -33,12 +34,17 @@ impl AssertableCheckSyncResult for CheckSyncResult {
     fn assert_conflict(&self) {
         assert!(matches!(*self, CheckSyncResult::Conflict { .. }));
     }
+
+    fn assert_auto_merge(&self) {
+        assert!(matches!(*self, CheckSyncResult::AutoMerge { .. }));
+    }
 }
 
 impl TestSyncClient {
     pub fn assert_local_data_matches_remote_data(&self, remote: &TestRemote) {
         let local_hash = self.get_local_hash();
-        let remote_hash = remote.get_remote_hash(&self.config.remote_sync_key);
+        let remote_hash =
+            remote.get_remote_hash(&self.config.remote_sync_key, self.config.hash_algorithm);
         assert_eq!(
             local_hash, remote_hash,
             "Local and remote hashes don't match"
@@ -101,6 +107,7 @@ impl TestSyncClient {
                 .join(REMOTE_SAVES_FOLDER_NAME)
                 .join(&self.config.remote_sync_key),
             &GlobSet::empty(),
+            self.config.hash_algorithm,
         )
         .expect("Failed to hash restored snapshot");
 
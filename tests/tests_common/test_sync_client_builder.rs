@@ -1,5 +1,10 @@
 use std::{fs, path::PathBuf};
 
+use local_cloud_game_sync::config::{
+    ConflictStrategy, RemoteStorageMode, RemoteTimeouts, TransferCompression,
+};
+use local_cloud_game_sync::hashing::{HashAlgorithm, PREFERRED_HASH_ALGORITHM};
+
 use super::*;
 use crate::tests_common::{
     common::{
@@ -13,6 +18,12 @@ pub struct TestSyncClientBuilder {
     client_name: Option<String>,
     sync_key: Option<String>,
     starting_save_folder: Option<Option<PathBuf>>,
+    conflict_strategy: ConflictStrategy,
+    hash_algorithm: HashAlgorithm,
+    transfer_compression: TransferCompression,
+    remote_storage: RemoteStorageMode,
+    encryption_passphrase: Option<String>,
+    process_names: Vec<String>,
 }
 
 impl TestSyncClientBuilder {
@@ -21,6 +32,12 @@ impl TestSyncClientBuilder {
             client_name: None,
             sync_key: None,
             starting_save_folder: None,
+            conflict_strategy: ConflictStrategy::Manual,
+            hash_algorithm: PREFERRED_HASH_ALGORITHM,
+            transfer_compression: TransferCompression::None,
+            remote_storage: RemoteStorageMode::Tree,
+            encryption_passphrase: None,
+            process_names: vec![],
         }
     }
 
@@ -34,6 +51,36 @@ impl TestSyncClientBuilder {
         self
     }
 
+    pub fn with_conflict_strategy(mut self, strategy: ConflictStrategy) -> Self {
+        self.conflict_strategy = strategy;
+        self
+    }
+
+    pub fn with_hash_algorithm(mut self, algorithm: HashAlgorithm) -> Self {
+        self.hash_algorithm = algorithm;
+        self
+    }
+
+    pub fn with_transfer_compression(mut self, compression: TransferCompression) -> Self {
+        self.transfer_compression = compression;
+        self
+    }
+
+    pub fn with_remote_storage(mut self, storage: RemoteStorageMode) -> Self {
+        self.remote_storage = storage;
+        self
+    }
+
+    pub fn with_encryption_passphrase(mut self, passphrase: impl Into<String>) -> Self {
+        self.encryption_passphrase = Some(passphrase.into());
+        self
+    }
+
+    pub fn with_process_names(mut self, names: &[&str]) -> Self {
+        self.process_names = names.iter().map(|name| name.to_string()).collect();
+        self
+    }
+
     pub fn with_empty_test_folder(mut self) -> Self {
         self.starting_save_folder = Some(None);
         self
@@ -81,13 +128,30 @@ impl TestSyncClientBuilder {
             remote_sync_root: REMOTE_CONTAINER_INTERNAL_ROOT_FOLDER_PATH.to_string(),
             local_head_folder: head_folder,
             local_save_folder: client_save_folder,
+            save_folder_variant: String::from("saveFolderPath"),
             ignore_globset: GlobSet::empty(),
+            conflict_strategy: self.conflict_strategy,
+            hash_algorithm: self.hash_algorithm,
+            transfer_compression: self.transfer_compression,
+            remote_storage: self.remote_storage,
+            encryption_key: None,
+            remote_timeouts: RemoteTimeouts::default(),
+            process_names: self.process_names,
+            rehash: false,
+            allow_unencrypted_pull: false,
         };
 
-        TestSyncClient {
+        let mut client = TestSyncClient {
             config: cfg,
             _client_name: client_name,
             _client_folder: TestTempFolder::from_path(client_root), // will auto cleanup when client is out
+        };
+
+        if let Some(passphrase) = self.encryption_passphrase {
+            client
+                .init_encryption_key(&passphrase)
+                .expect("Unable to init test client encryption key");
         }
+        client
     }
 }
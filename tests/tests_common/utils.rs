@@ -105,9 +105,15 @@ pub fn restic_restore_cmd_call(
 }
 
 pub fn delete_all_head_files(dir: &Path) -> io::Result<()> {
-    // Build a glob matcher for "*.HEAD"
+    // Build a glob matcher for "*.HEAD" and its companion metadata files (plus encryption key parameters)
     let mut builder = GlobSetBuilder::new();
     builder.add(Glob::new("*.HEAD").unwrap());
+    builder.add(Glob::new("*.MANIFEST").unwrap());
+    builder.add(Glob::new("*.LOG").unwrap());
+    builder.add(Glob::new("*.HISTORY").unwrap());
+    builder.add(Glob::new("*.JOURNAL").unwrap());
+    builder.add(Glob::new("*.HASHCACHE").unwrap());
+    builder.add(Glob::new("encryption.json").unwrap());
     let matcher = builder.build().unwrap();
 
     for entry in fs::read_dir(dir)? {